@@ -1,4 +1,4 @@
-import {Optim, Uuid, MaintenanceSummary } from "maintenance_site";
+import {Uuid, MaintenanceSummary } from "maintenance_site";
 
 
 const input_file = document.getElementById('file');
@@ -6,14 +6,48 @@ const send_file = document.getElementById('send-file');
 const send_file_2 = document.getElementById('send-file-json');
 const planning = document.getElementById('planning');
 const gl_planning = planning.getContext('webgl');
+const solve = document.getElementById('solve');
+const stop = document.getElementById('stop');
+const api_key = document.getElementById('api-key');
+const move = document.getElementById('move');
+const solve_local = document.getElementById('solve-local');
+const stop_local = document.getElementById('stop-local');
+
+// the server may only accept the requests carrying one of its API keys
+const authorize = (request) => {
+    if(api_key.value) {
+        request.setRequestHeader("Authorization", "Bearer " + api_key.value);
+    }
+};
+const convergence = document.getElementById('convergence');
+const ctx_convergence = convergence.getContext('2d');
+const resources = document.getElementById('resources');
+const risks = document.getElementById('risks');
+const ctx_risks = risks.getContext('2d');
+const scenarios = document.getElementById('scenarios');
+const ctx_scenarios = scenarios.getContext('2d');
 
 
 const CELL_SIZE = 18; // px
 const GRID_COLOR = "#CCCCCC";
 const TASK_COLOR = "#FFFFFF";
 
-let optim = Optim.new();
+// loads and solves the instances out of the main thread, see worker.js
+const worker = new Worker(new URL('./worker.js', import.meta.url));
 let maintenance;
+let uuid;
+let progress;
+let improvements = [];
+// start day of each intervention in the planning moved by hand, once the
+// local search is stopped
+let local_planning;
+let local_running = false;
+// scales from the canvas to the grid of the last Gantt chart drawn
+let planning_layout;
+// day whose scenarios are drawn, from 0
+let risk_day;
+// horizon of the risks drawn
+let risk_ndays = 0;
 
 // create vertex shader program (how the vertices are treated)
 const vertCode = 
@@ -31,8 +65,9 @@ gl_planning.compileShader(vertShader);
 // create fragment shader program (how the pixel are treated)
 const fragCode =
     'precision mediump float;' +
+    'uniform vec4 color;' +
     'void main(void) {' +
-    'gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);' +
+    'gl_FragColor = color;' +
     '}';
 const fragShader = gl_planning.createShader(gl_planning.FRAGMENT_SHADER);
 gl_planning.shaderSource(fragShader, fragCode);
@@ -46,12 +81,13 @@ gl_planning.linkProgram(shaderProgram);
 gl_planning.useProgram(shaderProgram);
 
 
-const loadPlanning = () => {
+// one row by planned intervention of the Gantt chart posted by the worker
+const loadPlanning = (gantt) => {
     const grid_size = 1.8;
     const grid_row_start = -0.9;
     const grid_column_start = -0.9;
-    const grid_width = maintenance.ndays();
-    const grid_height = 5;
+    const grid_width = gantt.ndays;
+    const grid_height = Math.max(gantt.nrows, 1);
     const grid_w_step = grid_size / grid_width;
     const grid_h_step = grid_size / grid_height;
     const grid_vertices = [];
@@ -113,6 +149,7 @@ const loadPlanning = () => {
     const Pmatrix = gl_planning.getUniformLocation(shaderProgram, "Pmatrix");
     const Vmatrix = gl_planning.getUniformLocation(shaderProgram, "Vmatrix");
     const Mmatrix = gl_planning.getUniformLocation(shaderProgram, "Mmatrix");
+    const color = gl_planning.getUniformLocation(shaderProgram, "color");
     gl_planning.bindBuffer(gl_planning.ARRAY_BUFFER, gl_grid_vertices);
 
     /*========================= MATRIX ========================= */
@@ -131,6 +168,17 @@ const loadPlanning = () => {
     const full_h_step = 2.0 / grid_height;
     const scale = 1.0 / (grid_h_step*grid_height/full_h_step);
     var proj_matrix = get_projection(10.0, planning.width/planning.height, 1, 6);
+    // the grid is at z = -6 once viewed, where x and y are divided by 6
+    const ang = Math.tan(5.0*Math.PI/180);
+    planning_layout = {
+        gantt: gantt,
+        x_scale: 0.5/ang/6,
+        y_scale: 0.5*(planning.width/planning.height)/ang*scale/6,
+        grid_row_start: grid_row_start,
+        grid_column_start: grid_column_start,
+        grid_w_step: grid_w_step,
+        grid_h_step: grid_h_step,
+    };
     //var proj_matrix = [1,0,0,0, 0,1,0,0, 0,0,1,0, 0,0,0,1];
     var mov_matrix = [1,0,0,0, 0,1,0,0, 0,0,1,0, 0,0,0,1];
     var view_matrix = [1,0,0,0, 0,scale,0,0, 0,0,1,0, 0,0,0,1];
@@ -159,7 +207,24 @@ const loadPlanning = () => {
     gl_planning.uniformMatrix4fv(Vmatrix, false, view_matrix);
     gl_planning.uniformMatrix4fv(Mmatrix, false, mov_matrix);
 
+    gl_planning.uniform4f(color, 0.8, 0.8, 0.8, 1.0);
     gl_planning.drawArrays(gl_planning.LINES, 0, 2*(grid_width + grid_height + 2));
+
+    // the tasks are given in (day, row), scaled to the grid by the model matrix
+    const gl_task_vertices = gl_planning.createBuffer();
+    gl_planning.bindBuffer(gl_planning.ARRAY_BUFFER, gl_task_vertices);
+    gl_planning.bufferData(gl_planning.ARRAY_BUFFER, gantt.vertices, gl_planning.STATIC_DRAW);
+    gl_planning.vertexAttribPointer(position, 2, gl_planning.FLOAT, false, 0, 0);
+    const task_matrix = [
+        grid_w_step, 0, 0, 0,
+        0, grid_h_step, 0, 0,
+        0, 0, 1, 0,
+        grid_column_start, grid_row_start, 0, 1
+    ];
+    gl_planning.uniformMatrix4fv(Mmatrix, false, task_matrix);
+    gl_planning.uniform4f(color, 0.2, 0.4, 0.8, 1.0);
+    gl_planning.drawArrays(gl_planning.TRIANGLES, 0, 6*gantt.nrows);
+    gl_planning.bindBuffer(gl_planning.ARRAY_BUFFER, null);
 };
 
 //var request = new XMLHttpRequest();
@@ -184,17 +249,71 @@ input_file.addEventListener("change", event => {
     console.log("load file");
 });
 
+// summary sent by the server or the worker, with the fields of
+// `maintenance::MaintenanceSummary`
+const showSummary = (summary) => {
+    const seconds = (time) => time.toFixed(3) + "s";
+    const cells = {
+        "ninterventions": summary.ninterventions,
+        "ndays": summary.ndays,
+        "nresources": summary.nresources,
+        "nscenarios": summary.min_scenarios === summary.nscenarios
+            ? summary.nscenarios
+            : summary.min_scenarios + " to " + summary.nscenarios,
+        "nseasons": summary.nseasons,
+        "nexclusions": summary.nexclusions,
+        "alpha": summary.alpha,
+        "quantile": summary.quantile,
+        "computation-time": summary.computation_time + "s",
+        "timings": [summary.timings.read, summary.timings.parse, summary.timings.convert].map(seconds).join(", "),
+        "hash": summary.hash || "unknown",
+    };
+    Object.keys(cells).forEach(id => {
+        document.getElementById(id).textContent = "" + cells[id];
+    });
+    document.getElementById('summary-table').style = '';
+};
+
+// the instance is loaded by the server after the upload
+const SUMMARY_POLL = 1000; // ms
+const fetchSummary = () => {
+    var request = new XMLHttpRequest();
+    request.open('GET', 'http://192.168.56.3:8000/optim/' + uuid, true);
+    authorize(request);
+    request.onload = function() {
+        if(request.status !== 200) {
+            return;
+        }
+        const job = JSON.parse(request.responseText);
+        if(job.summary) {
+            showSummary(job.summary);
+        } else if(job.status === "queued" || job.status === "loading") {
+            setTimeout(fetchSummary, SUMMARY_POLL);
+        }
+    }
+    request.send();
+};
+
 var start = new Date();
-send_file.addEventListener("click", event => {
-    const selectedFile = input_file.files[0];
+// SHA-256 of the file in hexadecimal, as computed by the server
+const fileHash = (file) => file.arrayBuffer()
+    .then(buffer => crypto.subtle.digest('SHA-256', buffer))
+    .then(digest => Array.from(new Uint8Array(digest))
+        .map(byte => byte.toString(16).padStart(2, '0'))
+        .join(''));
+
+const uploadFile = (selectedFile) => {
     const fd = new FormData();
     fd.append("file", selectedFile);
     var request = new XMLHttpRequest();
     request.overrideMimeType("application/octet-stream")
     request.responseType = 'arraybuffer';
     request.open('POST', 'http://192.168.56.3:8000/optim/new', true);
+    authorize(request);
     request.onload = function() {
         const data = Uuid.from_bytes(new Uint8Array(request.response));
+        uuid = data.as_string();
+        fetchSummary();
         //maintenance = summary;
         //const summary_table = document.getElementById('summary-table');
         //summary_table.style = '';
@@ -209,6 +328,322 @@ send_file.addEventListener("click", event => {
         //loadPlanning();
     }
     request.send(fd);
+};
+
+// the same instances are uploaded all day, the server may already have it
+send_file.addEventListener("click", event => {
+    const selectedFile = input_file.files[0];
+    fileHash(selectedFile).then(hash => {
+        var request = new XMLHttpRequest();
+        request.responseType = 'arraybuffer';
+        request.open('POST', 'http://192.168.56.3:8000/hashes/' + hash + '/optim?name='
+            + encodeURIComponent(selectedFile.name), true);
+        authorize(request);
+        request.onload = function() {
+            if(request.status === 202) {
+                uuid = Uuid.from_bytes(new Uint8Array(request.response)).as_string();
+                fetchSummary();
+            } else {
+                uploadFile(selectedFile);
+            }
+        }
+        request.send();
+    });
+});
+
+// cost of each improvement against the elapsed time of the run
+const drawConvergence = () => {
+    const width = convergence.width;
+    const height = convergence.height;
+    const margin = 10;
+    ctx_convergence.clearRect(0, 0, width, height);
+    if(improvements.length === 0) {
+        return;
+    }
+    const last = improvements[improvements.length - 1];
+    const max_elapsed = Math.max(last.elapsed, 1e-3);
+    const costs = improvements.map(improvement => improvement.cost);
+    const max_cost = Math.max(...costs);
+    const min_cost = Math.min(...costs);
+    const cost_range = Math.max(max_cost - min_cost, 1e-9);
+    const x = (elapsed) => margin + (width - 2*margin) * elapsed / max_elapsed;
+    const y = (cost) => height - margin - (height - 2*margin) * (cost - min_cost) / cost_range;
+    ctx_convergence.strokeStyle = GRID_COLOR;
+    ctx_convergence.strokeRect(margin, margin, width - 2*margin, height - 2*margin);
+    ctx_convergence.beginPath();
+    ctx_convergence.strokeStyle = "#000000";
+    improvements.forEach((improvement, idx) => {
+        if(idx === 0) {
+            ctx_convergence.moveTo(x(improvement.elapsed), y(improvement.cost));
+        } else {
+            // the best cost stays the same until the next improvement
+            ctx_convergence.lineTo(x(improvement.elapsed), y(improvements[idx - 1].cost));
+            ctx_convergence.lineTo(x(improvement.elapsed), y(improvement.cost));
+        }
+    });
+    ctx_convergence.stroke();
+    improvements.forEach(improvement => {
+        ctx_convergence.fillStyle = improvement.feasible ? "#00AA00" : "#CC0000";
+        ctx_convergence.fillRect(x(improvement.elapsed) - 2, y(improvement.cost) - 2, 4, 4);
+    });
+};
+
+const RESOURCE_CHART_WIDTH = 1280; // px
+const RESOURCE_CHART_HEIGHT = 120; // px
+
+// one chart by resource: the workload of each day between the min and the
+// max, the days out of the bounds are marked in red
+const drawResources = (charts) => {
+    while(resources.firstChild) {
+        resources.removeChild(resources.firstChild);
+    }
+    charts.forEach(chart => {
+        const canvas = document.createElement('canvas');
+        canvas.width = RESOURCE_CHART_WIDTH;
+        canvas.height = RESOURCE_CHART_HEIGHT;
+        canvas.title = chart.name;
+        resources.appendChild(canvas);
+        const ctx = canvas.getContext('2d');
+        const width = canvas.width;
+        const height = canvas.height;
+        const margin = 10;
+        const ndays = Math.max(chart.workloads.length, 1);
+        const top = Math.max(...chart.max, ...chart.workloads, 1e-9);
+        const x = (day) => margin + (width - 2*margin) * day / ndays;
+        const y = (value) => height - margin - (height - 2*margin) * value / top;
+        const step = (values, color) => {
+            ctx.beginPath();
+            ctx.strokeStyle = color;
+            values.forEach((value, day) => {
+                if(day === 0) {
+                    ctx.moveTo(x(day), y(value));
+                } else {
+                    ctx.lineTo(x(day), y(values[day - 1]));
+                    ctx.lineTo(x(day), y(value));
+                }
+            });
+            ctx.lineTo(x(values.length), y(values[values.length - 1]));
+            ctx.stroke();
+        };
+        ctx.fillStyle = "#FFCCCC";
+        chart.violations.forEach(day => {
+            ctx.fillRect(x(day), margin, x(day + 1) - x(day), height - 2*margin);
+        });
+        ctx.strokeStyle = GRID_COLOR;
+        ctx.strokeRect(margin, margin, width - 2*margin, height - 2*margin);
+        step(chart.min, "#0000CC");
+        step(chart.max, "#CC0000");
+        step(chart.workloads, "#000000");
+        ctx.fillStyle = "#000000";
+        ctx.fillText(chart.name, margin + 2, margin + 10);
+    });
+};
+
+// the violation days sent by the server start at 1
+const fetchResources = () => {
+    var request = new XMLHttpRequest();
+    request.open('GET', 'http://192.168.56.3:8000/optim/' + uuid + '/workloads', true);
+    authorize(request);
+    request.onload = function() {
+        if(request.status === 200) {
+            drawResources(JSON.parse(request.responseText).map(chart => {
+                chart.violations = chart.violations.map(day => day - 1);
+                return chart;
+            }));
+        }
+    }
+    request.send();
+};
+
+const RISK_MARGIN = 10; // px
+
+// mean, quantile and excess risk of each day, the selected day is
+// highlighted
+const drawRisks = (profile) => {
+    const width = risks.width;
+    const height = risks.height;
+    const margin = RISK_MARGIN;
+    ctx_risks.clearRect(0, 0, width, height);
+    const ndays = Math.max(profile.mean_risks.length, 1);
+    risk_ndays = profile.mean_risks.length;
+    const top = Math.max(...profile.mean_risks, ...profile.quantile_risks, ...profile.excess_risks, 1e-9);
+    const x = (day) => margin + (width - 2*margin) * day / ndays;
+    const y = (risk) => height - margin - (height - 2*margin) * risk / top;
+    if(profile.day !== undefined && profile.day !== null) {
+        ctx_risks.fillStyle = "#EEEEEE";
+        ctx_risks.fillRect(x(profile.day), margin, x(profile.day + 1) - x(profile.day), height - 2*margin);
+    }
+    ctx_risks.strokeStyle = GRID_COLOR;
+    ctx_risks.strokeRect(margin, margin, width - 2*margin, height - 2*margin);
+    const line = (values, color) => {
+        ctx_risks.beginPath();
+        ctx_risks.strokeStyle = color;
+        values.forEach((value, day) => {
+            if(day === 0) {
+                ctx_risks.moveTo(x(day + 0.5), y(value));
+            } else {
+                ctx_risks.lineTo(x(day + 0.5), y(value));
+            }
+        });
+        ctx_risks.stroke();
+    };
+    line(profile.mean_risks, "#0000CC");
+    line(profile.quantile_risks, "#CC0000");
+    line(profile.excess_risks, "#00AA00");
+    ctx_risks.fillStyle = "#000000";
+    ctx_risks.fillText("cost " + profile.cost.toFixed(3) + ", mean (blue), quantile (red), excess (green)", margin + 2, margin + 10);
+    drawScenarios(profile);
+};
+
+// histogram of the risk of the scenarios of the selected day
+const SCENARIO_BINS = 40;
+const drawScenarios = (profile) => {
+    const width = scenarios.width;
+    const height = scenarios.height;
+    const margin = RISK_MARGIN;
+    ctx_scenarios.clearRect(0, 0, width, height);
+    if(profile.scenarios.length === 0) {
+        return;
+    }
+    const low = Math.min(...profile.scenarios);
+    const range = Math.max(Math.max(...profile.scenarios) - low, 1e-9);
+    const bins = new Array(SCENARIO_BINS).fill(0);
+    profile.scenarios.forEach(risk => {
+        bins[Math.min(Math.floor(SCENARIO_BINS * (risk - low) / range), SCENARIO_BINS - 1)] += 1;
+    });
+    const top = Math.max(...bins);
+    const bin_width = (width - 2*margin) / SCENARIO_BINS;
+    ctx_scenarios.fillStyle = "#888888";
+    bins.forEach((count, bin) => {
+        const bar = (height - 2*margin) * count / top;
+        ctx_scenarios.fillRect(margin + bin*bin_width, height - margin - bar, bin_width - 1, bar);
+    });
+    ctx_scenarios.fillStyle = "#000000";
+    ctx_scenarios.fillText("day " + (profile.day + 1) + ": " + profile.scenarios.length + " scenarios from "
+        + low.toFixed(3) + " to " + (low + range).toFixed(3), margin + 2, margin + 10);
+};
+
+// the days of the server start at 1
+const fetchRisks = () => {
+    var request = new XMLHttpRequest();
+    const query = risk_day === undefined ? '' : '?day=' + (risk_day + 1);
+    request.open('GET', 'http://192.168.56.3:8000/optim/' + uuid + '/risks' + query, true);
+    authorize(request);
+    request.onload = function() {
+        if(request.status === 200) {
+            const profile = JSON.parse(request.responseText);
+            if(profile.day) {
+                profile.day -= 1;
+            }
+            drawRisks(profile);
+        }
+    }
+    request.send();
+};
+
+risks.addEventListener("click", event => {
+    if(risk_ndays === 0) {
+        return;
+    }
+    const bounds = risks.getBoundingClientRect();
+    const offset = (event.clientX - bounds.left) * risks.width / bounds.width - RISK_MARGIN;
+    risk_day = Math.floor(risk_ndays * offset / (risks.width - 2*RISK_MARGIN));
+    if(local_running || local_planning) {
+        worker.postMessage({type: "risks", day: risk_day});
+    } else if(uuid) {
+        fetchRisks();
+    }
+});
+
+const onProgress = (name, data) => {
+    switch(name) {
+        case "improvement":
+            improvements.push(JSON.parse(data));
+            drawConvergence();
+            fetchResources();
+            fetchRisks();
+            break;
+        case "done": {
+            const done = JSON.parse(data);
+            console.log(done.cancelled ? "run stopped" : "run finished");
+            break;
+        }
+    }
+};
+
+// EventSource cannot send the API key in a header, the stream is read by hand
+const followProgress = () => {
+    if(progress) {
+        progress.abort();
+    }
+    improvements = [];
+    drawConvergence();
+    progress = new AbortController();
+    const headers = api_key.value ? {"Authorization": "Bearer " + api_key.value} : {};
+    fetch('http://192.168.56.3:8000/optim/' + uuid + '/progress', {headers, signal: progress.signal})
+        .then(async response => {
+            if(!response.ok) {
+                console.log("progress: " + response.status);
+                return;
+            }
+            const reader = response.body.getReader();
+            const decoder = new TextDecoder();
+            let pending = "";
+            for(;;) {
+                const {done, value} = await reader.read();
+                if(done) {
+                    return;
+                }
+                pending += decoder.decode(value, {stream: true});
+                let end;
+                while((end = pending.indexOf("\n\n")) >= 0) {
+                    let name = "message";
+                    let data = "";
+                    for(const line of pending.slice(0, end).split("\n")) {
+                        if(line.startsWith("event: ")) {
+                            name = line.slice(7);
+                        } else if(line.startsWith("data: ")) {
+                            data += line.slice(6);
+                        }
+                    }
+                    pending = pending.slice(end + 2);
+                    onProgress(name, data);
+                }
+            }
+        })
+        .catch(err => {
+            if(err.name !== "AbortError") {
+                console.log("progress: " + err);
+            }
+        });
+};
+
+solve.addEventListener("click", event => {
+    if(!uuid) {
+        return;
+    }
+    var request = new XMLHttpRequest();
+    request.open('POST', 'http://192.168.56.3:8000/optim/' + uuid + '/solve', true);
+    request.setRequestHeader("Content-Type", "application/json");
+    authorize(request);
+    request.onload = function() {
+        if(request.status === 202) {
+            followProgress();
+        } else {
+            console.log("solve: " + request.status);
+        }
+    }
+    request.send(JSON.stringify({solver: "local-search", time_limit: 900}));
+});
+
+stop.addEventListener("click", event => {
+    if(!uuid) {
+        return;
+    }
+    var request = new XMLHttpRequest();
+    request.open('POST', 'http://192.168.56.3:8000/optim/' + uuid + '/cancel', true);
+    authorize(request);
+    request.send();
 });
 
 //var start = new Date();
@@ -244,13 +679,157 @@ send_file_2.addEventListener("click", event => {
     fd.append("file", selectedFile);
     var request = new XMLHttpRequest();
     request.open('POST', 'http://192.168.56.3:8000/optim-json', true);
+    authorize(request);
     request.onload = function() {
-        console.log(request.responseText);
+        if(request.status === 202) {
+            showSummary(JSON.parse(request.responseText));
+        } else {
+            console.log(request.responseText);
+        }
     }
     request.send(fd);
 });
 
 
+const drawCharts = (charts) => {
+    loadPlanning(charts.gantt);
+    drawResources(charts.resources);
+    if(charts.risks) {
+        drawRisks(charts.risks);
+    }
+};
+
+// small instances are solved in the browser, without the server
+worker.onmessage = (event) => {
+    const message = event.data;
+    switch(message.type) {
+        case "loaded":
+            showSummary(message.summary);
+            worker.postMessage({type: "solve", solver: "local-search", seed: 0});
+            break;
+        case "progress":
+            local_running = true;
+            improvements.push(message.improvement);
+            drawConvergence();
+            drawCharts(message.charts);
+            break;
+        case "result":
+            local_running = false;
+            local_planning = message.planning;
+            console.log(message.export);
+            drawCharts(message.charts);
+            break;
+        case "risks":
+            drawRisks(message.risks);
+            break;
+        case "move":
+            showMove(message.evaluation);
+            if(message.planning) {
+                local_planning = message.planning;
+                drawCharts(message.charts);
+            }
+            break;
+        case "error":
+            console.log("worker: " + message.message);
+            break;
+    }
+};
+
+solve_local.addEventListener("click", event => {
+    local_planning = undefined;
+    improvements = [];
+    const selectedFile = input_file.files[0];
+    selectedFile.arrayBuffer().then(buffer => {
+        worker.postMessage({type: "load", bytes: new Uint8Array(buffer)});
+    });
+});
+
+stop_local.addEventListener("click", event => {
+    worker.postMessage({type: "stop"});
+});
+
+// day and row of the Gantt chart under the mouse
+const gridCell = (event) => {
+    const layout = planning_layout;
+    const bounds = planning.getBoundingClientRect();
+    const x = (2*(event.clientX - bounds.left)/bounds.width - 1) / layout.x_scale;
+    const y = (1 - 2*(event.clientY - bounds.top)/bounds.height) / layout.y_scale;
+    return {
+        day: Math.floor((x - layout.grid_column_start) / layout.grid_w_step),
+        row: Math.floor((y - layout.grid_row_start) / layout.grid_h_step),
+    };
+};
+
+const showMove = (evaluation) => {
+    if(!evaluation) {
+        move.textContent = '';
+        return;
+    }
+    const checks = [];
+    if(!evaluation.latest_start) {
+        checks.push("after the latest start");
+    }
+    if(!evaluation.max_workloads) {
+        checks.push("maximum workloads exceeded");
+    }
+    if(!evaluation.exclusions) {
+        checks.push("exclusion");
+    }
+    if(!evaluation.min_workloads) {
+        checks.push("minimum workloads not reached");
+    }
+    const delta = (value) => (value >= 0 ? "+" : "") + value.toFixed(3);
+    move.style.color = evaluation.feasible ? "#00AA00" : "#CC0000";
+    move.textContent = "day " + (evaluation.day + 1)
+        + ": obj1 " + evaluation.obj1.toFixed(3) + " (" + delta(evaluation.delta_obj1) + ")"
+        + ", obj2 " + evaluation.obj2.toFixed(3) + " (" + delta(evaluation.delta_obj2) + ")"
+        + ", cost " + evaluation.cost.toFixed(3) + " (" + delta(evaluation.delta_cost) + ")"
+        + (checks.length ? ", " + checks.join(", ") : ", feasible");
+};
+
+// intervention dragged, with its start day and the day first grabbed
+let dragged;
+
+planning.addEventListener("mousedown", event => {
+    if(!local_planning || !planning_layout) {
+        return;
+    }
+    const cell = gridCell(event);
+    const gantt = planning_layout.gantt;
+    if(cell.row < 0 || cell.row >= gantt.nrows) {
+        return;
+    }
+    const intervention = gantt.interventions[cell.row];
+    dragged = {
+        intervention: intervention,
+        start: local_planning[intervention],
+        grabbed: cell.day,
+        day: local_planning[intervention],
+    };
+});
+
+planning.addEventListener("mousemove", event => {
+    if(!dragged) {
+        return;
+    }
+    const day = dragged.start + gridCell(event).day - dragged.grabbed;
+    if(day === dragged.day || day < 0) {
+        return;
+    }
+    dragged.day = day;
+    worker.postMessage({type: "evaluate", intervention: dragged.intervention, day: day});
+});
+
+planning.addEventListener("mouseup", event => {
+    if(!dragged) {
+        return;
+    }
+    if(dragged.day !== dragged.start) {
+        worker.postMessage({type: "apply", intervention: dragged.intervention, day: dragged.day});
+    }
+    dragged = undefined;
+});
+
 //var start = new Date();
 //send_file.addEventListener("click", event => {
 //console.log("Send");
@@ -3,6 +3,7 @@ extern crate capnpc;
 fn main() {
     ::capnpc::CompilerCommand::new()
         .file("maintenance.capnp")
+        .file("solver.capnp")
         .run()
         .expect("comiling schema");
 }
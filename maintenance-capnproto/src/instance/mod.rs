@@ -0,0 +1,775 @@
+//! Binary cache of a fully built instance.
+//!
+//! Reading a json instance is way slower than a short run of a solver, so
+//! the converted instance can be dumped once and reloaded from the
+//! `MaintenanceInstance` message. The message is read in place from the
+//! file contents and only the final arrays are copied.
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use maintenance::*;
+
+use crate::maintenance_capnp::{
+    intervention, intervention_exclusions, maintenance_instance, mapping, name, period, resource,
+    risks, seasons, workload,
+};
+
+/// Extension of the cache file next to the json instance.
+pub const CACHE_EXTENSION: &str = "capnp.bin";
+
+/// Version of the `MaintenanceInstance` message written, the caches of other
+/// versions are stale.
+pub const FORMAT_VERSION: u32 = 1;
+
+fn error(description: &str) -> ::capnp::Error {
+    ::capnp::Error::failed(description.to_owned())
+}
+
+fn reader_options() -> ::capnp::message::ReaderOptions {
+    // NOTE(vincent): instances are way larger than the default traversal limit.
+    let mut options = ::capnp::message::ReaderOptions::new();
+    options.traversal_limit_in_words(u64::max_value());
+    options
+}
+
+/// Path of the cache corresponding to a json instance.
+pub fn cache_path<P: AsRef<Path>>(instance: P) -> PathBuf {
+    instance.as_ref().with_extension(CACHE_EXTENSION)
+}
+
+/// Test if the cache exists and is more recent than the json instance.
+///
+/// A cache of another `FORMAT_VERSION` is stale too: `load` rejects it.
+pub fn is_fresh<P: AsRef<Path>, Q: AsRef<Path>>(cache: P, instance: Q) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified());
+    match (modified(cache.as_ref()), modified(instance.as_ref())) {
+        (Ok(cache), Ok(instance)) => cache > instance,
+        _ => false,
+    }
+}
+
+pub fn save<P: AsRef<Path>>(path: P, instance: &MaintenanceOptimization) -> ::capnp::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_instance(&mut out, instance)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> ::capnp::Result<MaintenanceOptimization> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    read_instance(&data)
+}
+
+pub fn write_instance<W: Write>(
+    out: &mut W,
+    instance: &MaintenanceOptimization,
+) -> ::capnp::Result<()> {
+    let mut message = ::capnp::message::Builder::new_default();
+    {
+        let root = message.init_root::<maintenance_instance::Builder>();
+        build_instance(root, instance);
+    }
+    ::capnp::serialize::write_message(out, &message)
+}
+
+pub fn read_instance(data: &[u8]) -> ::capnp::Result<MaintenanceOptimization> {
+    let mut data = data;
+    let message = ::capnp::serialize::read_message_from_flat_slice(&mut data, reader_options())?;
+    let root = message.get_root::<maintenance_instance::Reader>()?;
+    convert_instance(root)
+}
+
+fn build_instance(mut root: maintenance_instance::Builder, instance: &MaintenanceOptimization) {
+    let maintenance = &instance.maintenance;
+    root.set_format_version(FORMAT_VERSION);
+    root.set_ndays(maintenance.ndays() as u32);
+    root.set_quantile(maintenance.quantile());
+    root.set_alpha(maintenance.alpha());
+    root.set_computation_time(instance.computation_time.as_secs());
+    {
+        let scenarios_number = maintenance.scenarios_number();
+        let mut list = root
+            .reborrow()
+            .init_scenarios_number(scenarios_number.len() as u32);
+        for (idx, &nb) in scenarios_number.iter().enumerate() {
+            list.set(idx as u32, nb as u32);
+        }
+    }
+    {
+        let mut list = root
+            .reborrow()
+            .init_season_of_days(maintenance.ndays() as u32);
+        for day in 0..maintenance.ndays() {
+            list.set(day as u32, maintenance.season(Day::new(day)).get() as u32);
+        }
+    }
+    {
+        let resources = maintenance.resources();
+        let mut list = root.reborrow().init_resources(resources.len() as u32);
+        for (idx, resource) in resources.iter().enumerate() {
+            build_resource(
+                list.reborrow().get(idx as u32),
+                resource,
+                maintenance.ndays(),
+            );
+        }
+    }
+    {
+        let mut list = root
+            .reborrow()
+            .init_interventions(maintenance.ninterventions() as u32);
+        for (iid, intervention) in maintenance.interventions_with_ids() {
+            build_intervention(list.reborrow().get(iid.get() as u32), intervention);
+        }
+    }
+    {
+        let mut list = root
+            .reborrow()
+            .init_exclusions(maintenance.ninterventions() as u32);
+        for iid in maintenance.interventions_ids() {
+            let exclusions = &maintenance.exclusions(iid).exclusions;
+            let mut excl_list = list
+                .reborrow()
+                .get(iid.get() as u32)
+                .init_exclusions(exclusions.len() as u32);
+            for (idx, (ex_iid, ex_seasons)) in exclusions.iter().enumerate() {
+                let mut excl = excl_list.reborrow().get(idx as u32);
+                excl.set_iid(ex_iid.get() as u32);
+                build_seasons(excl.init_seasons(), ex_seasons);
+            }
+        }
+    }
+    build_mapping(root.init_mapping(), &instance.mapping);
+}
+
+fn build_f64_list(mut list: ::capnp::primitive_list::Builder<f64>, values: &[f64]) {
+    for (idx, &value) in values.iter().enumerate() {
+        list.set(idx as u32, value);
+    }
+}
+
+fn build_resource(mut builder: resource::Builder, resource: &Resource, ndays: usize) {
+    let min = &resource.min[0..ndays];
+    let max = &resource.max[0..ndays];
+    build_f64_list(builder.reborrow().init_min(min.len() as u32), min);
+    build_f64_list(builder.init_max(max.len() as u32), max);
+}
+
+fn build_seasons(mut builder: seasons::Builder, seasons: &Seasons) {
+    builder.set_nseasons(seasons.len() as u32);
+    let blocks = seasons.as_slice();
+    let mut list = builder.init_blocks(blocks.len() as u32);
+    for (idx, &block) in blocks.iter().enumerate() {
+        list.set(idx as u32, block);
+    }
+}
+
+fn build_period(mut builder: period::Builder, period: &Period) {
+    builder.set_start(period.start().get() as u32);
+    builder.set_duration(period.duration().get() as u32);
+}
+
+fn build_risks(mut builder: risks::Builder, risks: &Risks) {
+    builder.set_nscenarios(risks.nscenarios() as u32);
+    {
+        let periods_slice = risks.periods_slice();
+        let mut list = builder
+            .reborrow()
+            .init_periods_slice(periods_slice.len() as u32);
+        for (idx, &offset) in periods_slice.iter().enumerate() {
+            list.set(idx as u32, offset as u64);
+        }
+    }
+    let values = risks.raw_values();
+    build_f64_list(builder.init_values(values.len() as u32), values);
+}
+
+fn build_workload(mut builder: workload::Builder, workload: &Workload) {
+    builder.set_rid(workload.rid().get() as u32);
+    let workloads = workload.workloads();
+    build_f64_list(builder.init_workloads(workloads.len() as u32), workloads);
+}
+
+fn build_intervention(mut builder: intervention::Builder, intervention: &Intervention) {
+    builder.set_latest_start(intervention.latest_start().get() as u32);
+    let days = (0..intervention.nperiods()).map(Day::new);
+    {
+        let mut list = builder
+            .reborrow()
+            .init_periods(intervention.nperiods() as u32);
+        for (idx, period) in intervention.periods().enumerate() {
+            build_period(list.reborrow().get(idx as u32), period);
+        }
+    }
+    {
+        let mut list = builder
+            .reborrow()
+            .init_seasons(intervention.nperiods() as u32);
+        for day in days.clone() {
+            build_seasons(
+                list.reborrow().get(day.get() as u32),
+                intervention.seasons(day),
+            );
+        }
+    }
+    build_risks(builder.reborrow().init_risks(), intervention.risks());
+    {
+        let nresources = intervention.nresources();
+        let mut list = builder
+            .reborrow()
+            .init_workloads((intervention.nperiods() * nresources) as u32);
+        for day in days {
+            for (pos, workload) in intervention.workloads(day).iter().enumerate() {
+                let idx = day.get() * nresources + pos;
+                build_workload(list.reborrow().get(idx as u32), workload);
+            }
+        }
+    }
+    let resources = intervention.resources();
+    let mut list = builder.init_resources(resources.len() as u32);
+    for (idx, rid) in resources.iter().enumerate() {
+        list.set(idx as u32, rid.get() as u32);
+    }
+}
+
+fn build_names<K: Copy>(
+    mut list: ::capnp::struct_list::Builder<name::Builder>,
+    names: &HashMap<K, String>,
+    id: impl Fn(K) -> usize,
+) {
+    let mut names = names.iter().map(|(&k, n)| (id(k), n)).collect::<Vec<_>>();
+    names.sort();
+    for (idx, (nid, n)) in names.into_iter().enumerate() {
+        let mut entry = list.reborrow().get(idx as u32);
+        entry.set_id(nid as u32);
+        entry.set_name(n);
+    }
+}
+
+fn build_mapping(mut builder: mapping::Builder, mapping: &MaintenanceMapping) {
+    build_names(
+        builder
+            .reborrow()
+            .init_resources(mapping.resources.len() as u32),
+        &mapping.resources,
+        |rid: RID| rid.get(),
+    );
+    build_names(
+        builder
+            .reborrow()
+            .init_interventions(mapping.interventions.len() as u32),
+        &mapping.interventions,
+        |iid: IID| iid.get(),
+    );
+    build_names(
+        builder.init_seasons(mapping.seasons.len() as u32),
+        &mapping.seasons,
+        |sid: SID| sid.get(),
+    );
+}
+
+/// `Err` with `description` unless `condition` holds.
+fn check(condition: bool, description: &str) -> ::capnp::Result<()> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error(description))
+    }
+}
+
+// NOTE(vincent): the instance is read through unchecked accessors, so every
+// length of a truncated or stale cache must be rejected here.
+fn convert_instance(
+    root: maintenance_instance::Reader,
+) -> ::capnp::Result<MaintenanceOptimization> {
+    check(
+        root.get_format_version() == FORMAT_VERSION,
+        "stale cache, written by another format version",
+    )?;
+    let ndays = root.get_ndays() as usize;
+    let quantile = root.get_quantile();
+    check(quantile > 0.0 && quantile <= 1.0, "invalid quantile")?;
+    let scenarios_number = root
+        .get_scenarios_number()?
+        .iter()
+        .map(|nb| nb as usize)
+        .collect::<Vec<_>>();
+    check(
+        scenarios_number.len() == ndays && scenarios_number.iter().all(|&nb| nb > 0),
+        "invalid scenarios number",
+    )?;
+    let nscenarios = *scenarios_number
+        .iter()
+        .max()
+        .ok_or_else(|| error("instance without scenarios"))?;
+    let resources = root
+        .get_resources()?
+        .iter()
+        .map(|resource| convert_resource(resource, ndays))
+        .collect::<::capnp::Result<Box<[Resource]>>>()?;
+    let interventions = root
+        .get_interventions()?
+        .iter()
+        .map(|intervention| convert_intervention(intervention, ndays, resources.len(), nscenarios))
+        .collect::<::capnp::Result<Box<[Intervention]>>>()?;
+    let exclusions = root
+        .get_exclusions()?
+        .iter()
+        .map(|excl| convert_exclusions(excl, interventions.len()))
+        .collect::<::capnp::Result<Box<[InterventionExclusions]>>>()?;
+    check(
+        exclusions.len() == interventions.len(),
+        "exclusions and interventions mismatch",
+    )?;
+    let mapping = convert_mapping(root.get_mapping()?)?;
+    check(
+        mapping.resources.len() == resources.len()
+            && mapping
+                .resources
+                .keys()
+                .all(|rid| rid.get() < resources.len())
+            && mapping.interventions.len() == interventions.len()
+            && mapping
+                .interventions
+                .keys()
+                .all(|iid| iid.get() < interventions.len()),
+        "invalid mapping",
+    )?;
+    // NOTE(vincent): the days of no season share the season after the named
+    // ones.
+    let season_of_days = root
+        .get_season_of_days()?
+        .iter()
+        .map(|sid| SID::new(sid as usize))
+        .collect::<Box<[SID]>>();
+    check(
+        season_of_days.len() == ndays
+            && season_of_days
+                .iter()
+                .all(|sid| sid.get() <= mapping.seasons.len()),
+        "invalid seasons of the days",
+    )?;
+    Ok(MaintenanceOptimization {
+        maintenance: Maintenance::builder()
+            .set_ndays(ndays)
+            .set_quantile(quantile)
+            .set_alpha(root.get_alpha())
+            .set_interventions(interventions)
+            .set_resources(resources)
+            .set_exclusions(exclusions)
+            .set_scenarios_number(scenarios_number)
+            .set_season_of_days(season_of_days)
+            .build(),
+        mapping,
+        computation_time: Duration::new(root.get_computation_time(), 0),
+    })
+}
+
+fn convert_f64_list(list: ::capnp::primitive_list::Reader<f64>) -> Box<[f64]> {
+    list.iter().collect()
+}
+
+fn convert_resource(resource: resource::Reader, ndays: usize) -> ::capnp::Result<Resource> {
+    let min = convert_f64_list(resource.get_min()?);
+    let max = convert_f64_list(resource.get_max()?);
+    check(
+        min.len() == ndays && max.len() == ndays,
+        "resource bounds and days mismatch",
+    )?;
+    Ok(Resource {
+        min: min.into(),
+        max: max.into(),
+    })
+}
+
+fn convert_seasons(seasons: seasons::Reader) -> ::capnp::Result<Seasons> {
+    let mut bitset = Seasons::with_capacity(seasons.get_nseasons() as usize);
+    let blocks = seasons.get_blocks()?;
+    if blocks.len() as usize != bitset.as_slice().len() {
+        return Err(error("invalid seasons"));
+    }
+    for (dst, src) in bitset.as_mut_slice().iter_mut().zip(blocks.iter()) {
+        *dst = src;
+    }
+    Ok(bitset)
+}
+
+fn convert_exclusions(
+    exclusions: intervention_exclusions::Reader,
+    ninterventions: usize,
+) -> ::capnp::Result<InterventionExclusions> {
+    let exclusions = exclusions
+        .get_exclusions()?
+        .iter()
+        .map(|ex| {
+            Ok((
+                IID::new(ex.get_iid() as usize),
+                convert_seasons(ex.get_seasons()?)?,
+            ))
+        })
+        .collect::<::capnp::Result<Box<[(IID, Seasons)]>>>()?;
+    // the lookup of the exclusions is a binary search
+    check(
+        exclusions.windows(2).all(|pair| pair[0].0 < pair[1].0)
+            && exclusions.iter().all(|(iid, _)| iid.get() < ninterventions),
+        "invalid exclusions",
+    )?;
+    Ok(InterventionExclusions { exclusions })
+}
+
+fn convert_period(period: period::Reader) -> ::capnp::Result<Period> {
+    Period::new(
+        Day::new(period.get_start() as usize),
+        Day::new(period.get_duration() as usize),
+    )
+    .ok_or_else(|| error("empty period"))
+}
+
+fn convert_risks(
+    risks: risks::Reader,
+    periods: &[Period],
+    nscenarios: usize,
+) -> ::capnp::Result<Risks> {
+    let periods_slice = risks
+        .get_periods_slice()?
+        .iter()
+        .map(|offset| offset as usize)
+        .collect::<Box<[usize]>>();
+    let values = convert_f64_list(risks.get_values()?);
+    // each period holds the risks of all the scenarios of each of its days
+    check(
+        risks.get_nscenarios() as usize == nscenarios
+            && periods_slice.len() == periods.len() + 1
+            && periods_slice.first() == Some(&0)
+            && periods_slice.last() == Some(&values.len())
+            && periods_slice
+                .windows(2)
+                .zip(periods.iter())
+                .all(|(slice, period)| {
+                    slice[0] <= slice[1]
+                        && slice[1] - slice[0] == period.duration().get() * nscenarios
+                }),
+        "risks and periods mismatch",
+    )?;
+    Ok(Risks::builder()
+        .set_nscenarios(nscenarios)
+        .set_periods(periods_slice)
+        .set_risks(values)
+        .build())
+}
+
+fn convert_intervention(
+    intervention: intervention::Reader,
+    ndays: usize,
+    nresources: usize,
+    nscenarios: usize,
+) -> ::capnp::Result<Intervention> {
+    let periods = intervention
+        .get_periods()?
+        .iter()
+        .map(convert_period)
+        .collect::<::capnp::Result<Box<[Period]>>>()?;
+    // the period starting on a day is accessed through this day
+    check(
+        periods.iter().enumerate().all(|(day, period)| {
+            period.start().get() == day && period.end_exclusive().get() <= ndays
+        }),
+        "periods out of the horizon",
+    )?;
+    let seasons = intervention
+        .get_seasons()?
+        .iter()
+        .map(convert_seasons)
+        .collect::<::capnp::Result<Box<[Seasons]>>>()?;
+    let resources = intervention
+        .get_resources()?
+        .iter()
+        .map(|rid| RID::new(rid as usize))
+        .collect::<Box<[RID]>>();
+    check(
+        resources.iter().all(|rid| rid.get() < nresources),
+        "unknown resource",
+    )?;
+    let workloads = intervention
+        .get_workloads()?
+        .iter()
+        .map(|wl| {
+            Ok(Workload::new(
+                RID::new(wl.get_rid() as usize),
+                convert_f64_list(wl.get_workloads()?),
+            ))
+        })
+        .collect::<::capnp::Result<Box<[Workload]>>>()?;
+    if seasons.len() != periods.len() || workloads.len() != periods.len() * resources.len() {
+        return Err(error("intervention periods mismatch"));
+    }
+    // one workload by resource of the intervention, over each period
+    check(
+        workloads.iter().enumerate().all(|(idx, workload)| {
+            let period = &periods[idx / resources.len()];
+            workload.rid() == resources[idx % resources.len()]
+                && workload.workloads().len() == period.duration().get()
+        }),
+        "workloads and periods mismatch",
+    )?;
+    Ok(Intervention::builder()
+        .set_latest_start(Day::new(intervention.get_latest_start() as usize))
+        .set_risks(convert_risks(
+            intervention.get_risks()?,
+            &periods,
+            nscenarios,
+        )?)
+        .set_periods(periods)
+        .set_seasons(seasons)
+        .set_workloads(workloads)
+        .set_resources(resources)
+        .build())
+}
+
+fn convert_names<K: Eq + Hash>(
+    names: ::capnp::struct_list::Reader<name::Reader>,
+    id: impl Fn(usize) -> K,
+) -> ::capnp::Result<HashMap<K, String>> {
+    names
+        .iter()
+        .map(|n| Ok((id(n.get_id() as usize), n.get_name()?.to_owned())))
+        .collect()
+}
+
+fn convert_mapping(mapping: mapping::Reader) -> ::capnp::Result<MaintenanceMapping> {
+    Ok(MaintenanceMapping {
+        resources: convert_names(mapping.get_resources()?, RID::new)?,
+        interventions: convert_names(mapping.get_interventions()?, IID::new)?,
+        seasons: convert_names(mapping.get_seasons()?, SID::new)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance() -> MaintenanceOptimization {
+        let period = |start, duration| Period::new(Day::new(start), Day::new(duration)).unwrap();
+        let seasons = |sids: &[usize]| {
+            let mut seasons = Seasons::with_capacity(3);
+            sids.iter().for_each(|&sid| seasons.insert(sid));
+            seasons
+        };
+        let intervention = |durations: &[usize], workload: f64| {
+            let periods = durations
+                .iter()
+                .enumerate()
+                .map(|(start, &duration)| period(start, duration))
+                .collect::<Box<[Period]>>();
+            let periods_slice = std::iter::once(0)
+                .chain(periods.iter().scan(0, |offset, period| {
+                    *offset += period.duration().get() * 2;
+                    Some(*offset)
+                }))
+                .collect::<Box<[usize]>>();
+            let values = (0..periods_slice[periods.len()])
+                .map(|idx| idx as f64)
+                .collect::<Box<[f64]>>();
+            Intervention::builder()
+                .set_latest_start(Day::new(durations.len() - 1))
+                .set_seasons(periods.iter().map(|_| seasons(&[0, 1])).collect())
+                .set_workloads(
+                    periods
+                        .iter()
+                        .map(|period| {
+                            Workload::new(
+                                RID::new(0),
+                                vec![workload; period.duration().get()].into_boxed_slice(),
+                            )
+                        })
+                        .collect(),
+                )
+                .set_risks(
+                    Risks::builder()
+                        .set_nscenarios(2)
+                        .set_periods(periods_slice)
+                        .set_risks(values)
+                        .build(),
+                )
+                .set_periods(periods)
+                .set_resources(vec![RID::new(0)].into_boxed_slice())
+                .build()
+        };
+        let exclusion = |other| InterventionExclusions {
+            exclusions: vec![(IID::new(other), seasons(&[1]))].into_boxed_slice(),
+        };
+        let maintenance = Maintenance::builder()
+            .set_ndays(3)
+            .set_quantile(0.5)
+            .set_alpha(0.5)
+            .set_interventions(
+                vec![intervention(&[2, 2], 2.0), intervention(&[1, 1, 1], 3.0)].into_boxed_slice(),
+            )
+            .set_resources(
+                vec![Resource {
+                    min: vec![1.0, 0.0, 0.0].into(),
+                    max: vec![4.0, 4.0, 4.0].into(),
+                }]
+                .into_boxed_slice(),
+            )
+            .set_exclusions(vec![exclusion(1), exclusion(0)].into_boxed_slice())
+            .set_scenarios_number(vec![2, 2, 2])
+            .set_season_of_days(vec![SID::new(0), SID::new(1), SID::new(1)].into_boxed_slice())
+            .build();
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (id, name.to_string()))
+                .collect::<Vec<_>>()
+        };
+        MaintenanceOptimization {
+            maintenance,
+            mapping: MaintenanceMapping {
+                resources: names(&["c1"])
+                    .into_iter()
+                    .map(|(id, name)| (RID::new(id), name))
+                    .collect(),
+                interventions: names(&["I1", "I2"])
+                    .into_iter()
+                    .map(|(id, name)| (IID::new(id), name))
+                    .collect(),
+                seasons: names(&["winter", "summer"])
+                    .into_iter()
+                    .map(|(id, name)| (SID::new(id), name))
+                    .collect(),
+            },
+            computation_time: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let expected = instance();
+        let mut data = Vec::new();
+        write_instance(&mut data, &expected).unwrap();
+        let instance = read_instance(&data).unwrap();
+        let (lhs, rhs) = (&instance.maintenance, &expected.maintenance);
+        assert_eq!(lhs.ndays(), rhs.ndays());
+        assert_eq!(lhs.quantile(), rhs.quantile());
+        assert_eq!(lhs.alpha(), rhs.alpha());
+        assert_eq!(lhs.scenarios_number(), rhs.scenarios_number());
+        for day in (0..rhs.ndays()).map(Day::new) {
+            assert_eq!(lhs.season(day), rhs.season(day));
+        }
+        for (lhs, rhs) in lhs.resources().iter().zip(rhs.resources().iter()) {
+            assert_eq!(lhs.min[0..3], rhs.min[0..3]);
+            assert_eq!(lhs.max[0..3], rhs.max[0..3]);
+        }
+        assert_eq!(lhs.ninterventions(), rhs.ninterventions());
+        for iid in rhs.interventions_ids() {
+            let (lhs_int, rhs_int) = (lhs.intervention(iid), rhs.intervention(iid));
+            assert_eq!(lhs_int.latest_start(), rhs_int.latest_start());
+            assert!(lhs_int.periods().eq(rhs_int.periods()));
+            assert_eq!(lhs_int.resources(), rhs_int.resources());
+            assert_eq!(
+                lhs_int.risks().periods_slice(),
+                rhs_int.risks().periods_slice()
+            );
+            assert_eq!(lhs_int.risks().raw_values(), rhs_int.risks().raw_values());
+            for period in rhs_int.periods() {
+                let day = period.start();
+                assert_eq!(lhs_int.seasons(day), rhs_int.seasons(day));
+                let workloads = |int: &Intervention| {
+                    int.workloads(day)
+                        .iter()
+                        .map(|wl| (wl.rid(), wl.workloads().to_vec()))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(workloads(lhs_int), workloads(rhs_int));
+            }
+            assert_eq!(
+                lhs.exclusions(iid).exclusions,
+                rhs.exclusions(iid).exclusions
+            );
+        }
+        assert_eq!(instance.mapping.resources, expected.mapping.resources);
+        assert_eq!(
+            instance.mapping.interventions,
+            expected.mapping.interventions
+        );
+        assert_eq!(instance.mapping.seasons, expected.mapping.seasons);
+        assert_eq!(instance.computation_time, expected.computation_time);
+    }
+
+    fn read_altered<F>(alter: F) -> ::capnp::Result<MaintenanceOptimization>
+    where
+        F: FnOnce(maintenance_instance::Builder),
+    {
+        let mut message = ::capnp::message::Builder::new_default();
+        {
+            let mut root = message.init_root::<maintenance_instance::Builder>();
+            build_instance(root.reborrow(), &instance());
+            alter(root);
+        }
+        convert_instance(message.get_root_as_reader()?)
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        assert!(read_altered(|_| ()).is_ok());
+        assert!(read_altered(|mut root| root.set_ndays(4)).is_err());
+        assert!(read_altered(|mut root| root.set_format_version(0)).is_err());
+        assert!(read_altered(|root| {
+            root.get_resources().unwrap().get(0).init_max(2);
+        })
+        .is_err());
+        assert!(read_altered(|root| {
+            root.get_interventions()
+                .unwrap()
+                .get(0)
+                .get_risks()
+                .unwrap()
+                .init_values(3);
+        })
+        .is_err());
+        assert!(read_altered(|root| {
+            root.get_interventions()
+                .unwrap()
+                .get(1)
+                .get_workloads()
+                .unwrap()
+                .get(2)
+                .init_workloads(2);
+        })
+        .is_err());
+        assert!(read_altered(|root| {
+            root.get_interventions()
+                .unwrap()
+                .get(0)
+                .get_periods()
+                .unwrap()
+                .get(1)
+                .set_duration(3);
+        })
+        .is_err());
+        assert!(read_altered(|root| {
+            root.get_exclusions()
+                .unwrap()
+                .get(0)
+                .get_exclusions()
+                .unwrap()
+                .get(0)
+                .set_iid(2);
+        })
+        .is_err());
+        assert!(read_altered(|mut root| {
+            root.init_season_of_days(2);
+        })
+        .is_err());
+        assert!(read_altered(|root| {
+            root.get_season_of_days().unwrap().set(0, 3);
+        })
+        .is_err());
+    }
+}
@@ -2,12 +2,20 @@ extern crate capnp;
 extern crate maintenance;
 extern crate uuid;
 
-use maintenance::common::Maintenance;
+use maintenance::LoadTimings;
 
 pub mod maintenance_capnp {
     include!(concat!(env!("OUT_DIR"), "/maintenance_capnp.rs"));
 }
 
+/// Interfaces of the RPC front-end of the server.
+pub mod solver_capnp {
+    include!(concat!(env!("OUT_DIR"), "/solver_capnp.rs"));
+}
+
+pub mod instance;
+pub mod planning;
+
 pub struct MaintenanceSummaryBuilder {
     message: ::capnp::message::Builder<::capnp::message::HeapAllocator>,
 }
@@ -36,6 +44,28 @@ impl<'a> MaintenanceSummary<'a> {
     pub fn nscenarios(&self) -> u32 {
         self.reader.get_nscenarios()
     }
+
+    pub fn summary(&self) -> ::capnp::Result<maintenance::MaintenanceSummary> {
+        let r = &self.reader;
+        Ok(maintenance::MaintenanceSummary {
+            ndays: r.get_ndays(),
+            ninterventions: r.get_ninterventions(),
+            nresources: r.get_nresources(),
+            nscenarios: r.get_nscenarios(),
+            min_scenarios: r.get_min_scenarios(),
+            nseasons: r.get_nseasons(),
+            nexclusions: r.get_nexclusions(),
+            alpha: r.get_alpha(),
+            quantile: r.get_quantile(),
+            computation_time: r.get_computation_time(),
+            timings: LoadTimings {
+                read: r.get_read_time(),
+                parse: r.get_parse_time(),
+                convert: r.get_convert_time(),
+            },
+            hash: r.get_hash()?.to_owned(),
+        })
+    }
 }
 
 pub mod capnp_uuid {
@@ -81,16 +111,34 @@ pub mod capnp_uuid {
     }
 }
 
+/// Fills a summary held by another message, e.g. an RPC result.
+pub fn build_summary(
+    mut m: crate::maintenance_capnp::maintenance::Builder,
+    summary: &maintenance::MaintenanceSummary,
+) {
+    m.set_ndays(summary.ndays);
+    m.set_ninterventions(summary.ninterventions);
+    m.set_nresources(summary.nresources);
+    m.set_nscenarios(summary.nscenarios);
+    m.set_min_scenarios(summary.min_scenarios);
+    m.set_nseasons(summary.nseasons);
+    m.set_nexclusions(summary.nexclusions);
+    m.set_alpha(summary.alpha);
+    m.set_quantile(summary.quantile);
+    m.set_computation_time(summary.computation_time);
+    m.set_read_time(summary.timings.read);
+    m.set_parse_time(summary.timings.parse);
+    m.set_convert_time(summary.timings.convert);
+    m.set_hash(&summary.hash);
+}
+
 impl MaintenanceSummaryBuilder {
-    pub fn from_maintenance(maintenance: &Maintenance) -> Self {
+    pub fn from_summary(summary: &maintenance::MaintenanceSummary) -> Self {
         let mut message = ::capnp::message::Builder::new_default();
-        {
-            let mut m = message.init_root::<crate::maintenance_capnp::maintenance::Builder>();
-            m.set_ndays(maintenance.ndays() as u32);
-            m.set_ninterventions(maintenance.ninterventions() as u32);
-            m.set_nresources(maintenance.nresources() as u32);
-            m.set_nscenarios(maintenance.nscenarios() as u32);
-        }
+        build_summary(
+            message.init_root::<crate::maintenance_capnp::maintenance::Builder>(),
+            summary,
+        );
         MaintenanceSummaryBuilder { message: message }
     }
 
@@ -0,0 +1,355 @@
+//! Messages exchanged once an instance has been optimized: the planning,
+//! its cost report and the workloads of the resources.
+use maintenance::validation::{ResourceUsage, RiskProfile};
+use maintenance::{CostReport, Day, Planning, IID, RID};
+
+use crate::maintenance_capnp::{cost_report, planning, resources_usage, risk_profile, workloads};
+
+type OwnedMessage = ::capnp::message::Reader<::capnp::serialize::OwnedSegments>;
+type HeapMessage = ::capnp::message::Builder<::capnp::message::HeapAllocator>;
+
+fn message_bytes(message: &HeapMessage) -> ::capnp::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    ::capnp::serialize::write_message(&mut data, message)?;
+    Ok(data)
+}
+
+/// Words read at most from a message, 64 MiB.
+const TRAVERSAL_LIMIT: u64 = 8 * 1024 * 1024;
+
+// NOTE(vincent): unlike the instance cache, these messages come from the
+// network, so their size stays bounded.
+fn read_message(data: &[u8]) -> ::capnp::Result<OwnedMessage> {
+    let mut reader = std::io::BufReader::new(data);
+    let mut options = ::capnp::message::ReaderOptions::new();
+    options.traversal_limit_in_words(TRAVERSAL_LIMIT);
+    ::capnp::serialize::read_message(&mut reader, options)
+}
+
+fn build_f64_list(mut list: ::capnp::primitive_list::Builder<f64>, values: &[f64]) {
+    for (idx, &value) in values.iter().enumerate() {
+        list.set(idx as u32, value);
+    }
+}
+
+/// Fills a planning held by another message, e.g. an RPC result.
+pub fn build_planning(p: planning::Builder, planning: &Planning) {
+    let mut list = p.init_interventions(planning.interventions.len() as u32);
+    for (idx, (iid, day)) in planning.interventions.iter().enumerate() {
+        let mut intervention = list.reborrow().get(idx as u32);
+        intervention.set_iid(iid.get() as u32);
+        intervention.set_start(day.get() as u32);
+    }
+}
+
+pub struct PlanningBuilder {
+    message: HeapMessage,
+}
+
+impl PlanningBuilder {
+    pub fn from_planning(planning: &Planning) -> Self {
+        let mut message = ::capnp::message::Builder::new_default();
+        build_planning(message.init_root::<planning::Builder>(), planning);
+        PlanningBuilder { message }
+    }
+
+    pub fn bytes(&self) -> ::capnp::Result<Vec<u8>> {
+        message_bytes(&self.message)
+    }
+}
+
+pub struct PlanningReader {
+    message: OwnedMessage,
+}
+
+impl PlanningReader {
+    pub fn from_bytes(data: &[u8]) -> ::capnp::Result<Self> {
+        Ok(PlanningReader {
+            message: read_message(data)?,
+        })
+    }
+
+    pub fn planning(&self) -> ::capnp::Result<Planning> {
+        let p = self.message.get_root::<planning::Reader>()?;
+        let interventions = p
+            .get_interventions()?
+            .iter()
+            .map(|i| {
+                (
+                    IID::new(i.get_iid() as usize),
+                    Day::new(i.get_start() as usize),
+                )
+            })
+            .collect();
+        Ok(Planning { interventions })
+    }
+}
+
+fn build_cost_report(mut r: cost_report::Builder, report: &CostReport) {
+    r.set_obj1(report.obj1);
+    r.set_obj2(report.obj2);
+    r.set_total(report.cost);
+    build_f64_list(
+        r.reborrow().init_mean_risks(report.mean_risks.len() as u32),
+        &report.mean_risks,
+    );
+    build_f64_list(
+        r.reborrow()
+            .init_quantile_risks(report.quantile_risks.len() as u32),
+        &report.quantile_risks,
+    );
+    build_f64_list(
+        r.init_excess_risks(report.excess_risks.len() as u32),
+        &report.excess_risks,
+    );
+}
+
+fn read_cost_report(r: cost_report::Reader) -> ::capnp::Result<CostReport> {
+    Ok(CostReport {
+        obj1: r.get_obj1(),
+        obj2: r.get_obj2(),
+        cost: r.get_total(),
+        mean_risks: r.get_mean_risks()?.iter().collect(),
+        quantile_risks: r.get_quantile_risks()?.iter().collect(),
+        excess_risks: r.get_excess_risks()?.iter().collect(),
+    })
+}
+
+pub struct CostReportBuilder {
+    message: HeapMessage,
+}
+
+impl CostReportBuilder {
+    pub fn from_report(report: &CostReport) -> Self {
+        let mut message = ::capnp::message::Builder::new_default();
+        {
+            build_cost_report(message.init_root::<cost_report::Builder>(), report);
+        }
+        CostReportBuilder { message }
+    }
+
+    pub fn bytes(&self) -> ::capnp::Result<Vec<u8>> {
+        message_bytes(&self.message)
+    }
+}
+
+pub struct CostReportReader {
+    message: OwnedMessage,
+}
+
+pub struct CostReportSummary<'a> {
+    reader: cost_report::Reader<'a>,
+}
+
+impl<'a> CostReportSummary<'a> {
+    pub fn obj1(&self) -> f64 {
+        self.reader.get_obj1()
+    }
+
+    pub fn obj2(&self) -> f64 {
+        self.reader.get_obj2()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.reader.get_total()
+    }
+}
+
+impl CostReportReader {
+    pub fn from_bytes(data: &[u8]) -> ::capnp::Result<Self> {
+        Ok(CostReportReader {
+            message: read_message(data)?,
+        })
+    }
+
+    pub fn reader<'a>(&'a self) -> ::capnp::Result<CostReportSummary<'a>> {
+        Ok(CostReportSummary {
+            reader: self.message.get_root::<cost_report::Reader>()?,
+        })
+    }
+
+    pub fn report(&self) -> ::capnp::Result<CostReport> {
+        read_cost_report(self.message.get_root::<cost_report::Reader>()?)
+    }
+}
+
+pub struct WorkloadsBuilder {
+    message: HeapMessage,
+}
+
+impl WorkloadsBuilder {
+    /// Workloads of each resource by day, indexed by resource identifier.
+    pub fn from_workloads(workloads: &[Box<[f64]>]) -> Self {
+        let mut message = ::capnp::message::Builder::new_default();
+        {
+            let w = message.init_root::<workloads::Builder>();
+            let mut list = w.init_resources(workloads.len() as u32);
+            for (rid, days) in workloads.iter().enumerate() {
+                let mut resource = list.reborrow().get(rid as u32);
+                resource.set_rid(rid as u32);
+                build_f64_list(resource.init_workloads(days.len() as u32), days);
+            }
+        }
+        WorkloadsBuilder { message }
+    }
+
+    pub fn bytes(&self) -> ::capnp::Result<Vec<u8>> {
+        message_bytes(&self.message)
+    }
+}
+
+pub struct WorkloadsReader {
+    message: OwnedMessage,
+}
+
+impl WorkloadsReader {
+    pub fn from_bytes(data: &[u8]) -> ::capnp::Result<Self> {
+        Ok(WorkloadsReader {
+            message: read_message(data)?,
+        })
+    }
+
+    pub fn workloads(&self) -> ::capnp::Result<Vec<Box<[f64]>>> {
+        let w = self.message.get_root::<workloads::Reader>()?;
+        let resources = w.get_resources()?;
+        let mut workloads = vec![Box::<[f64]>::default(); resources.len() as usize];
+        for resource in resources.iter() {
+            let rid = resource.get_rid() as usize;
+            if rid >= workloads.len() {
+                return Err(::capnp::Error::failed("invalid resource".to_owned()));
+            }
+            workloads[rid] = resource.get_workloads()?.iter().collect();
+        }
+        Ok(workloads)
+    }
+}
+
+pub struct ResourcesUsageBuilder {
+    message: HeapMessage,
+}
+
+impl ResourcesUsageBuilder {
+    pub fn from_usage(usage: &[ResourceUsage]) -> Self {
+        let mut message = ::capnp::message::Builder::new_default();
+        {
+            let u = message.init_root::<resources_usage::Builder>();
+            let mut list = u.init_resources(usage.len() as u32);
+            for (idx, resource_usage) in usage.iter().enumerate() {
+                let mut resource = list.reborrow().get(idx as u32);
+                resource.set_rid(resource_usage.rid.get() as u32);
+                build_f64_list(
+                    resource
+                        .reborrow()
+                        .init_workloads(resource_usage.workloads.len() as u32),
+                    &resource_usage.workloads,
+                );
+                build_f64_list(
+                    resource
+                        .reborrow()
+                        .init_min(resource_usage.min.len() as u32),
+                    &resource_usage.min,
+                );
+                build_f64_list(
+                    resource
+                        .reborrow()
+                        .init_max(resource_usage.max.len() as u32),
+                    &resource_usage.max,
+                );
+                let mut violations =
+                    resource.init_violations(resource_usage.violations.len() as u32);
+                for (idx, day) in resource_usage.violations.iter().enumerate() {
+                    violations.set(idx as u32, day.get() as u32);
+                }
+            }
+        }
+        ResourcesUsageBuilder { message }
+    }
+
+    pub fn bytes(&self) -> ::capnp::Result<Vec<u8>> {
+        message_bytes(&self.message)
+    }
+}
+
+pub struct ResourcesUsageReader {
+    message: OwnedMessage,
+}
+
+impl ResourcesUsageReader {
+    pub fn from_bytes(data: &[u8]) -> ::capnp::Result<Self> {
+        Ok(ResourcesUsageReader {
+            message: read_message(data)?,
+        })
+    }
+
+    pub fn usage(&self) -> ::capnp::Result<Vec<ResourceUsage>> {
+        let u = self.message.get_root::<resources_usage::Reader>()?;
+        u.get_resources()?
+            .iter()
+            .map(|resource| {
+                Ok(ResourceUsage {
+                    rid: RID::new(resource.get_rid() as usize),
+                    workloads: resource.get_workloads()?.iter().collect(),
+                    min: resource.get_min()?.iter().collect(),
+                    max: resource.get_max()?.iter().collect(),
+                    violations: resource
+                        .get_violations()?
+                        .iter()
+                        .map(|day| Day::new(day as usize))
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct RiskProfileBuilder {
+    message: HeapMessage,
+}
+
+impl RiskProfileBuilder {
+    pub fn from_profile(profile: &RiskProfile) -> Self {
+        let mut message = ::capnp::message::Builder::new_default();
+        {
+            let mut p = message.init_root::<risk_profile::Builder>();
+            build_cost_report(p.reborrow().init_report(), &profile.report);
+            if let Some(day) = profile.day {
+                p.set_has_day(true);
+                p.set_day(day.get() as u32);
+            }
+            build_f64_list(
+                p.init_scenarios(profile.scenarios.len() as u32),
+                &profile.scenarios,
+            );
+        }
+        RiskProfileBuilder { message }
+    }
+
+    pub fn bytes(&self) -> ::capnp::Result<Vec<u8>> {
+        message_bytes(&self.message)
+    }
+}
+
+pub struct RiskProfileReader {
+    message: OwnedMessage,
+}
+
+impl RiskProfileReader {
+    pub fn from_bytes(data: &[u8]) -> ::capnp::Result<Self> {
+        Ok(RiskProfileReader {
+            message: read_message(data)?,
+        })
+    }
+
+    pub fn profile(&self) -> ::capnp::Result<RiskProfile> {
+        let p = self.message.get_root::<risk_profile::Reader>()?;
+        Ok(RiskProfile {
+            report: read_cost_report(p.get_report()?)?,
+            day: if p.get_has_day() {
+                Some(Day::new(p.get_day() as usize))
+            } else {
+                None
+            },
+            scenarios: p.get_scenarios()?.iter().collect(),
+        })
+    }
+}
@@ -0,0 +1,8 @@
+DROP TABLE planning_interventions;
+DROP TABLE plannings;
+DROP TABLE runs;
+DROP TABLE exclusions;
+DROP TABLE seasons;
+DROP TABLE interventions;
+DROP TABLE resources;
+DROP TABLE instances;
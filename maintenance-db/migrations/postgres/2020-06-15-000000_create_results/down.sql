@@ -0,0 +1,3 @@
+DROP TABLE improvements;
+DROP INDEX runs_instance_id_solver;
+ALTER TABLE runs DROP COLUMN code_version;
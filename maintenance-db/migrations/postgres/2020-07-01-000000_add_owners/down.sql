@@ -0,0 +1,2 @@
+DROP INDEX instances_owner;
+ALTER TABLE instances DROP COLUMN owner;
@@ -0,0 +1,2 @@
+DROP INDEX instances_hash;
+ALTER TABLE instances DROP COLUMN hash;
@@ -0,0 +1,5 @@
+-- SHA-256 of the uploaded file, in hexadecimal, to find an instance already
+-- uploaded
+ALTER TABLE instances ADD COLUMN hash TEXT NOT NULL DEFAULT '';
+
+CREATE INDEX instances_hash ON instances (hash);
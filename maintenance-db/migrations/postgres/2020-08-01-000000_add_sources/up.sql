@@ -0,0 +1,4 @@
+-- instance holding the data of an instance uploaded again from the same
+-- file; the data of the reference is empty and it is described by the
+-- rows of its source
+ALTER TABLE instances ADD COLUMN source_id VARCHAR(36) REFERENCES instances (id);
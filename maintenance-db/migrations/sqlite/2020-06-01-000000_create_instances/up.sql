@@ -0,0 +1,86 @@
+-- NOTE(vincent): identifiers are the uuids given by the server, the whole
+-- instance is kept as a Cap'n Proto message in `data`, the other tables
+-- only describe it.
+CREATE TABLE instances (
+    id VARCHAR(36) PRIMARY KEY,
+    name TEXT NOT NULL,
+    ndays INTEGER NOT NULL,
+    nresources INTEGER NOT NULL,
+    ninterventions INTEGER NOT NULL,
+    nseasons INTEGER NOT NULL,
+    nexclusions INTEGER NOT NULL,
+    nscenarios INTEGER NOT NULL,
+    quantile DOUBLE PRECISION NOT NULL,
+    alpha DOUBLE PRECISION NOT NULL,
+    -- in seconds
+    computation_time BIGINT NOT NULL,
+    -- seconds since the unix epoch
+    created_on BIGINT NOT NULL,
+    data BLOB NOT NULL
+);
+
+CREATE TABLE resources (
+    instance_id VARCHAR(36) NOT NULL REFERENCES instances (id) ON DELETE CASCADE,
+    rid INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (instance_id, rid)
+);
+
+CREATE TABLE interventions (
+    instance_id VARCHAR(36) NOT NULL REFERENCES instances (id) ON DELETE CASCADE,
+    iid INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    -- first day is 0
+    latest_start INTEGER NOT NULL,
+    PRIMARY KEY (instance_id, iid)
+);
+
+CREATE TABLE seasons (
+    instance_id VARCHAR(36) NOT NULL REFERENCES instances (id) ON DELETE CASCADE,
+    sid INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (instance_id, sid)
+);
+
+-- one row by pair of interventions (first_iid < second_iid) and season
+CREATE TABLE exclusions (
+    instance_id VARCHAR(36) NOT NULL REFERENCES instances (id) ON DELETE CASCADE,
+    first_iid INTEGER NOT NULL,
+    second_iid INTEGER NOT NULL,
+    sid INTEGER NOT NULL,
+    PRIMARY KEY (instance_id, first_iid, second_iid, sid)
+);
+
+CREATE TABLE runs (
+    id VARCHAR(36) PRIMARY KEY,
+    instance_id VARCHAR(36) NOT NULL REFERENCES instances (id) ON DELETE CASCADE,
+    solver TEXT NOT NULL,
+    -- in seconds
+    time_limit BIGINT NOT NULL,
+    seed BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    started_on BIGINT NOT NULL,
+    finished_on BIGINT
+);
+
+CREATE INDEX runs_instance_id ON runs (instance_id);
+
+-- best planning of a run
+CREATE TABLE plannings (
+    run_id VARCHAR(36) PRIMARY KEY REFERENCES runs (id) ON DELETE CASCADE,
+    cost DOUBLE PRECISION NOT NULL,
+    obj1 DOUBLE PRECISION NOT NULL,
+    obj2 DOUBLE PRECISION NOT NULL,
+    feasible BOOLEAN NOT NULL,
+    -- in seconds
+    elapsed DOUBLE PRECISION NOT NULL,
+    iteration BIGINT NOT NULL
+);
+
+CREATE TABLE planning_interventions (
+    run_id VARCHAR(36) NOT NULL REFERENCES plannings (run_id) ON DELETE CASCADE,
+    iid INTEGER NOT NULL,
+    -- first day is 0
+    start_day INTEGER NOT NULL,
+    PRIMARY KEY (run_id, iid)
+);
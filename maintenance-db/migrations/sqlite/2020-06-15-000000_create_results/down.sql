@@ -0,0 +1,3 @@
+DROP TABLE improvements;
+DROP INDEX runs_instance_id_solver;
+-- NOTE(vincent): SQLite cannot drop a column, `code_version` stays in runs.
@@ -0,0 +1,18 @@
+-- NOTE(vincent): the results live next to the instances, SQLite has no
+-- schemas.
+ALTER TABLE runs ADD COLUMN code_version TEXT NOT NULL DEFAULT '';
+
+CREATE INDEX runs_instance_id_solver ON runs (instance_id, solver);
+
+-- every improvement of a run, to follow the cost over time
+CREATE TABLE improvements (
+    run_id VARCHAR(36) NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    iteration BIGINT NOT NULL,
+    -- in seconds
+    elapsed DOUBLE PRECISION NOT NULL,
+    cost DOUBLE PRECISION NOT NULL,
+    obj1 DOUBLE PRECISION NOT NULL,
+    obj2 DOUBLE PRECISION NOT NULL,
+    feasible BOOLEAN NOT NULL,
+    PRIMARY KEY (run_id, iteration)
+);
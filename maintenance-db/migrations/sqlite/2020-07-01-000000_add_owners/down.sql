@@ -0,0 +1,2 @@
+DROP INDEX instances_owner;
+-- NOTE(vincent): SQLite cannot drop a column, `owner` stays in instances.
@@ -0,0 +1,5 @@
+-- name of the API key that uploaded the instance, empty when the server
+-- runs without authentication
+ALTER TABLE instances ADD COLUMN owner TEXT NOT NULL DEFAULT '';
+
+CREATE INDEX instances_owner ON instances (owner);
@@ -0,0 +1,2 @@
+DROP INDEX instances_hash;
+-- NOTE(vincent): SQLite cannot drop a column, `hash` stays in instances.
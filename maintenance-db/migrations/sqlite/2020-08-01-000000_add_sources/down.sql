@@ -0,0 +1 @@
+-- NOTE(vincent): SQLite cannot drop a column, `source_id` stays in instances.
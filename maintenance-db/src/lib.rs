@@ -0,0 +1,951 @@
+//! Persistence of the instances, of the solver runs and of their plannings.
+//!
+//! The same schema is used with SQLite (tests, single host) and PostgreSQL
+//! (production), the backend is chosen from the url given to
+//! [`Database::open`].
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+extern crate capnp;
+extern crate maintenance;
+extern crate maintenance_capnproto;
+extern crate uuid;
+
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use maintenance::search::Improvement;
+use maintenance::{Day, MaintenanceOptimization, Planning, IID};
+use maintenance_capnproto::instance::{read_instance, write_instance};
+
+mod models;
+mod schema;
+
+use crate::models::*;
+pub use crate::models::{ImprovementRow, InstanceRow, PlanningRow, RunRow};
+pub use crate::results::RunResult;
+
+#[cfg(not(any(feature = "sqlite", feature = "postgres")))]
+compile_error!("maintenance-db needs the `sqlite` or the `postgres` feature");
+
+#[cfg(feature = "sqlite")]
+mod sqlite_migrations {
+    embed_migrations!("migrations/sqlite");
+    pub use self::embedded_migrations::run;
+}
+
+#[cfg(feature = "postgres")]
+mod postgres_migrations {
+    embed_migrations!("migrations/postgres");
+    pub use self::embedded_migrations::run;
+}
+
+pub enum DbPool {
+    #[cfg(feature = "sqlite")]
+    Sqlite(Pool<ConnectionManager<diesel::SqliteConnection>>),
+    #[cfg(feature = "postgres")]
+    Postgres(Pool<ConnectionManager<diesel::PgConnection>>),
+}
+
+// NOTE(vincent): diesel queries are typed by backend, each arm is compiled
+// for its own connection type.
+macro_rules! with_connection {
+    ($pool:expr, $conn:ident => $body:expr) => {
+        match $pool {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(ref pool) => {
+                let connection = pool.get()?;
+                let $conn: &diesel::SqliteConnection = &connection;
+                $body
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(ref pool) => {
+                let connection = pool.get()?;
+                let $conn: &diesel::PgConnection = &connection;
+                $body
+            }
+        }
+    };
+}
+
+mod results;
+
+#[derive(Debug)]
+pub enum DbError {
+    UnsupportedUrl(String),
+    /// No connection of the pool available
+    Pool(diesel::r2d2::PoolError),
+    Migration(diesel_migrations::RunMigrationsError),
+    Query(diesel::result::Error),
+    Capnp(capnp::Error),
+    /// Stored identifier that is not a uuid
+    Key(uuid::Error),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::UnsupportedUrl(url) => write!(f, "unsupported database url {:?}", url),
+            DbError::Pool(err) => write!(f, "no connection available: {}", err),
+            DbError::Migration(err) => write!(f, "migration failed: {}", err),
+            DbError::Query(err) => write!(f, "query failed: {}", err),
+            DbError::Capnp(err) => write!(f, "invalid instance data: {}", err),
+            DbError::Key(err) => write!(f, "invalid stored key: {}", err),
+        }
+    }
+}
+
+impl From<diesel::r2d2::PoolError> for DbError {
+    fn from(err: diesel::r2d2::PoolError) -> Self {
+        DbError::Pool(err)
+    }
+}
+
+impl From<diesel_migrations::RunMigrationsError> for DbError {
+    fn from(err: diesel_migrations::RunMigrationsError) -> Self {
+        DbError::Migration(err)
+    }
+}
+
+impl From<diesel::result::Error> for DbError {
+    fn from(err: diesel::result::Error) -> Self {
+        DbError::Query(err)
+    }
+}
+
+impl From<capnp::Error> for DbError {
+    fn from(err: capnp::Error) -> Self {
+        DbError::Capnp(err)
+    }
+}
+
+impl From<uuid::Error> for DbError {
+    fn from(err: uuid::Error) -> Self {
+        DbError::Key(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, DbError>;
+
+// NOTE(vincent): PostgreSQL binds at most 65535 parameters by statement, the
+// batch inserts are split in chunks of rows under this limit.
+const MAX_BINDS: usize = 65535;
+
+/// Rows inserted by statement, for rows of `ncolumns` columns.
+const fn chunk_size(ncolumns: usize) -> usize {
+    MAX_BINDS / ncolumns
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn key(uuid: &Uuid) -> String {
+    uuid.to_hyphenated().to_string()
+}
+
+/// Settings of each SQLite connection of the pool.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+struct SqlitePragmas;
+
+#[cfg(feature = "sqlite")]
+impl diesel::r2d2::CustomizeConnection<diesel::SqliteConnection, diesel::r2d2::Error>
+    for SqlitePragmas
+{
+    fn on_acquire(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+    ) -> std::result::Result<(), diesel::r2d2::Error> {
+        use diesel::connection::SimpleConnection;
+        // NOTE(vincent): foreign keys are off by default in SQLite, and a
+        // connection waits for the writes of the others instead of failing.
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+impl DbPool {
+    /// `postgres://...` or `postgresql://...` for PostgreSQL, anything else is
+    /// the path of a SQLite database (`sqlite://` prefix optional,
+    /// `:memory:` for an in-memory one).
+    pub fn connect(url: &str) -> Result<Self> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            #[cfg(feature = "postgres")]
+            return Ok(DbPool::Postgres(Pool::new(ConnectionManager::new(url))?));
+        } else {
+            #[cfg(feature = "sqlite")]
+            {
+                let path = url.trim_start_matches("sqlite://");
+                let builder = Pool::builder().connection_customizer(Box::new(SqlitePragmas));
+                // NOTE(vincent): each connection to `:memory:` opens its own
+                // database, the pool keeps a single one for good.
+                let builder = if path == ":memory:" {
+                    builder.max_size(1).idle_timeout(None).max_lifetime(None)
+                } else {
+                    builder
+                };
+                return Ok(DbPool::Sqlite(builder.build(ConnectionManager::new(path))?));
+            }
+        }
+        #[allow(unreachable_code)]
+        Err(DbError::UnsupportedUrl(url.to_owned()))
+    }
+
+    pub fn run_migrations(&self) -> Result<()> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => sqlite_migrations::run(&pool.get()?)?,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => postgres_migrations::run(&pool.get()?)?,
+        }
+        Ok(())
+    }
+}
+
+/// Rows describing an instance, built before opening the transaction.
+struct InstanceRows<'a> {
+    instance: NewInstance<'a>,
+    resources: Vec<NewResource<'a>>,
+    interventions: Vec<NewIntervention<'a>>,
+    seasons: Vec<NewSeason<'a>>,
+    exclusions: Vec<NewExclusion<'a>>,
+}
+
+impl<'a> InstanceRows<'a> {
+    fn new(
+        id: &'a str,
+        name: &'a str,
+        owner: &'a str,
+        hash: &'a str,
+        instance: &'a MaintenanceOptimization,
+        data: &'a [u8],
+    ) -> Self {
+        let maintenance = &instance.maintenance;
+        let mapping = &instance.mapping;
+        let resources = mapping
+            .resources
+            .iter()
+            .map(|(rid, name)| NewResource {
+                instance_id: id,
+                rid: rid.get() as i32,
+                name,
+            })
+            .collect();
+        let interventions = maintenance
+            .interventions_with_ids()
+            .map(|(iid, intervention)| NewIntervention {
+                instance_id: id,
+                iid: iid.get() as i32,
+                name: mapping.interventions.get(&iid).map_or("", String::as_str),
+                latest_start: intervention.latest_start().get() as i32,
+            })
+            .collect();
+        let seasons = mapping
+            .seasons
+            .iter()
+            .map(|(sid, name)| NewSeason {
+                instance_id: id,
+                sid: sid.get() as i32,
+                name,
+            })
+            .collect::<Vec<_>>();
+        let mut exclusions = Vec::new();
+        for first in maintenance.interventions_ids() {
+            for (second, excluded) in maintenance.exclusions(first).exclusions.iter() {
+                if *second <= first {
+                    continue;
+                }
+                exclusions.extend(excluded.ones().map(|sid| NewExclusion {
+                    instance_id: id,
+                    first_iid: first.get() as i32,
+                    second_iid: second.get() as i32,
+                    sid: sid as i32,
+                }));
+            }
+        }
+        InstanceRows {
+            instance: NewInstance {
+                id,
+                name,
+                ndays: maintenance.ndays() as i32,
+                nresources: maintenance.nresources() as i32,
+                ninterventions: maintenance.ninterventions() as i32,
+                nseasons: seasons.len() as i32,
+                nexclusions: exclusions.len() as i32,
+                nscenarios: maintenance.nscenarios() as i32,
+                quantile: maintenance.quantile(),
+                alpha: maintenance.alpha(),
+                computation_time: instance.computation_time.as_secs() as i64,
+                created_on: now(),
+                data,
+                owner,
+                hash,
+                source_id: None,
+            },
+            resources,
+            interventions,
+            seasons,
+            exclusions,
+        }
+    }
+}
+
+/// Parameters of a solver run to record.
+pub struct NewRunParameters<'a> {
+    pub id: &'a Uuid,
+    pub instance_id: &'a Uuid,
+    pub solver: &'a str,
+    /// in seconds
+    pub time_limit: u64,
+    pub seed: u64,
+    /// Version of the solver, to compare the runs across changes
+    pub code_version: &'a str,
+}
+
+/// Shared by the threads of the server, each query takes a connection of
+/// the pool.
+pub struct Database {
+    pool: DbPool,
+}
+
+impl Database {
+    /// Connects and brings the schema up to date.
+    pub fn open(url: &str) -> Result<Self> {
+        let pool = DbPool::connect(url)?;
+        pool.run_migrations()?;
+        Ok(Database { pool })
+    }
+
+    /// Stores the instance, its data and the tables describing it.
+    /// `owner` names who uploaded it, `hash` identifies the uploaded file.
+    pub fn insert_instance(
+        &self,
+        uuid: &Uuid,
+        name: &str,
+        owner: &str,
+        hash: &str,
+        instance: &MaintenanceOptimization,
+    ) -> Result<()> {
+        let mut data = Vec::new();
+        write_instance(&mut data, instance)?;
+        let id = key(uuid);
+        let rows = InstanceRows::new(&id, name, owner, hash, instance, &data);
+        with_connection!(self.pool, conn => conn.transaction::<_, DbError, _>(|| {
+            diesel::insert_into(schema::instances::table)
+                .values(&rows.instance)
+                .execute(conn)?;
+            for chunk in rows.resources.chunks(chunk_size(3)) {
+                diesel::insert_into(schema::resources::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            for chunk in rows.interventions.chunks(chunk_size(4)) {
+                diesel::insert_into(schema::interventions::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            for chunk in rows.seasons.chunks(chunk_size(3)) {
+                diesel::insert_into(schema::seasons::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            for chunk in rows.exclusions.chunks(chunk_size(4)) {
+                diesel::insert_into(schema::exclusions::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }))
+    }
+
+    pub fn instance_row(&self, uuid: &Uuid) -> Result<Option<InstanceRow>> {
+        use crate::schema::instances::dsl::*;
+        let row = with_connection!(self.pool, conn => instances
+            .select((
+                id,
+                name,
+                ndays,
+                nresources,
+                ninterventions,
+                nseasons,
+                nexclusions,
+                nscenarios,
+                quantile,
+                alpha,
+                computation_time,
+                created_on,
+                owner,
+                hash,
+            ))
+            .find(key(uuid))
+            .first::<InstanceRow>(conn)
+            .optional()?);
+        Ok(row)
+    }
+
+    /// Stored instances, of every owner or of `of_owner`, newest first.
+    pub fn instance_rows(&self, of_owner: Option<&str>) -> Result<Vec<InstanceRow>> {
+        use crate::schema::instances::dsl::*;
+        let rows = with_connection!(self.pool, conn => {
+            let mut query = instances
+                .select((
+                    id,
+                    name,
+                    ndays,
+                    nresources,
+                    ninterventions,
+                    nseasons,
+                    nexclusions,
+                    nscenarios,
+                    quantile,
+                    alpha,
+                    computation_time,
+                    created_on,
+                    owner,
+                    hash,
+                ))
+                .order((created_on.desc(), id))
+                .into_boxed();
+            if let Some(of_owner) = of_owner {
+                query = query.filter(owner.eq(of_owner));
+            }
+            query.load::<InstanceRow>(conn)?
+        });
+        Ok(rows)
+    }
+
+    /// Stores an instance uploaded again from the file of `source`, as a
+    /// reference to the stored data and description of `source`.
+    pub fn insert_reference(
+        &self,
+        uuid: &Uuid,
+        name: &str,
+        owner: &str,
+        source: &Uuid,
+    ) -> Result<()> {
+        use crate::schema::instances::dsl as instances;
+        let id = key(uuid);
+        with_connection!(self.pool, conn => conn.transaction::<_, DbError, _>(|| {
+            let (row, source_id) = instances::instances
+                .select((
+                    (
+                        instances::id,
+                        instances::name,
+                        instances::ndays,
+                        instances::nresources,
+                        instances::ninterventions,
+                        instances::nseasons,
+                        instances::nexclusions,
+                        instances::nscenarios,
+                        instances::quantile,
+                        instances::alpha,
+                        instances::computation_time,
+                        instances::created_on,
+                        instances::owner,
+                        instances::hash,
+                    ),
+                    instances::source_id,
+                ))
+                .find(key(source))
+                .first::<(InstanceRow, Option<String>)>(conn)?;
+            // references always point to the instance holding the data
+            let source_id = source_id.unwrap_or(row.id);
+            diesel::insert_into(schema::instances::table)
+                .values(&NewInstance {
+                    id: &id,
+                    name,
+                    ndays: row.ndays,
+                    nresources: row.nresources,
+                    ninterventions: row.ninterventions,
+                    nseasons: row.nseasons,
+                    nexclusions: row.nexclusions,
+                    nscenarios: row.nscenarios,
+                    quantile: row.quantile,
+                    alpha: row.alpha,
+                    computation_time: row.computation_time,
+                    created_on: now(),
+                    data: &[],
+                    owner,
+                    hash: &row.hash,
+                    source_id: Some(&source_id),
+                })
+                .execute(conn)?;
+            Ok(())
+        }))
+    }
+
+    /// Oldest stored instance uploaded from a file with this `hash`, of
+    /// every owner or of `of_owner`.
+    pub fn instance_by_hash(
+        &self,
+        file_hash: &str,
+        of_owner: Option<&str>,
+    ) -> Result<Option<Uuid>> {
+        use crate::schema::instances::dsl::*;
+        let found = with_connection!(self.pool, conn => {
+            let mut query = instances
+                .select(id)
+                .filter(hash.eq(file_hash))
+                .order(created_on)
+                .into_boxed();
+            if let Some(of_owner) = of_owner {
+                query = query.filter(owner.eq(of_owner));
+            }
+            query.first::<String>(conn).optional()?
+        });
+        Ok(found.and_then(|found| Uuid::parse_str(&found).ok()))
+    }
+
+    /// Cap'n Proto message of a stored instance, read from its source for a
+    /// reference.
+    fn instance_data(&self, uuid: &Uuid) -> Result<Option<Vec<u8>>> {
+        use crate::schema::instances::dsl::*;
+        // NOTE(vincent): the empty data of a reference is never selected,
+        // diesel reads an empty SQLite blob from a null pointer.
+        let found = with_connection!(self.pool, conn => {
+            let source = instances
+                .select(source_id)
+                .find(key(uuid))
+                .first::<Option<String>>(conn)
+                .optional()?;
+            match source {
+                Some(source) => instances
+                    .select(data)
+                    .find(source.unwrap_or_else(|| key(uuid)))
+                    .first::<Vec<u8>>(conn)
+                    .optional()?,
+                None => None,
+            }
+        });
+        Ok(found)
+    }
+
+    /// Rebuilds a stored instance.
+    pub fn instance(&self, uuid: &Uuid) -> Result<Option<MaintenanceOptimization>> {
+        match self.instance_data(uuid)? {
+            Some(bytes) => Ok(Some(read_instance(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert_run(&self, run: &NewRunParameters) -> Result<()> {
+        let (run_id, instance_id) = (key(run.id), key(run.instance_id));
+        let row = NewRun {
+            id: &run_id,
+            instance_id: &instance_id,
+            solver: run.solver,
+            time_limit: run.time_limit as i64,
+            seed: run.seed as i64,
+            status: "solving",
+            started_on: now(),
+            code_version: run.code_version,
+        };
+        with_connection!(self.pool, conn => diesel::insert_into(schema::runs::table)
+            .values(&row)
+            .execute(conn)?);
+        Ok(())
+    }
+
+    /// Records the end of a run with its best planning, if any, and the
+    /// improvements found on the way.
+    pub fn finish_run(
+        &self,
+        uuid: &Uuid,
+        run_status: &str,
+        best: Option<(&Improvement, &Planning)>,
+        history: &[Improvement],
+    ) -> Result<()> {
+        use crate::schema::runs::dsl::*;
+        let run_id = key(uuid);
+        let planning = best.map(|(improvement, planning)| {
+            let row = PlanningRow {
+                run_id: run_id.clone(),
+                cost: improvement.cost,
+                obj1: improvement.obj1,
+                obj2: improvement.obj2,
+                feasible: improvement.feasible,
+                elapsed: improvement.elapsed.as_secs_f64(),
+                iteration: improvement.iteration as i64,
+            };
+            let interventions = planning
+                .interventions
+                .iter()
+                .map(|(iid, day)| NewPlannedIntervention {
+                    run_id: &run_id,
+                    iid: iid.get() as i32,
+                    start_day: day.get() as i32,
+                })
+                .collect::<Vec<_>>();
+            (row, interventions)
+        });
+        let history = history
+            .iter()
+            .map(|improvement| ImprovementRow {
+                run_id: run_id.clone(),
+                iteration: improvement.iteration as i64,
+                elapsed: improvement.elapsed.as_secs_f64(),
+                cost: improvement.cost,
+                obj1: improvement.obj1,
+                obj2: improvement.obj2,
+                feasible: improvement.feasible,
+            })
+            .collect::<Vec<_>>();
+        with_connection!(self.pool, conn => conn.transaction::<_, DbError, _>(|| {
+            diesel::update(runs.find(&run_id))
+                .set((status.eq(run_status), finished_on.eq(Some(now()))))
+                .execute(conn)?;
+            if let Some((ref row, ref interventions)) = planning {
+                diesel::insert_into(schema::plannings::table)
+                    .values(row)
+                    .execute(conn)?;
+                for chunk in interventions.chunks(chunk_size(3)) {
+                    diesel::insert_into(schema::planning_interventions::table)
+                        .values(chunk)
+                        .execute(conn)?;
+                }
+            }
+            for chunk in history.chunks(chunk_size(7)) {
+                diesel::insert_into(schema::improvements::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }))
+    }
+
+    pub fn run(&self, uuid: &Uuid) -> Result<Option<RunRow>> {
+        use crate::schema::runs::dsl::*;
+        let row = with_connection!(self.pool, conn => runs
+            .find(key(uuid))
+            .first::<RunRow>(conn)
+            .optional()?);
+        Ok(row)
+    }
+
+    /// Best planning recorded for a run.
+    pub fn planning(&self, uuid: &Uuid) -> Result<Option<(PlanningRow, Planning)>> {
+        use crate::schema::planning_interventions::dsl as pi;
+        use crate::schema::plannings::dsl::*;
+        let planning_id = key(uuid);
+        let (row, days) = with_connection!(self.pool, conn => {
+            let row = plannings
+                .find(&planning_id)
+                .first::<PlanningRow>(conn)
+                .optional()?;
+            let days = pi::planning_interventions
+                .select((pi::iid, pi::start_day))
+                .filter(pi::run_id.eq(&planning_id))
+                .order(pi::iid)
+                .load::<(i32, i32)>(conn)?;
+            (row, days)
+        });
+        Ok(row.map(|row| {
+            let interventions = days
+                .into_iter()
+                .map(|(iid, day)| (IID::new(iid as usize), Day::new(day as usize)))
+                .collect();
+            (row, Planning { interventions })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maintenance::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn instance() -> MaintenanceOptimization {
+        let period = |start, duration| Period::new(Day::new(start), Day::new(duration)).unwrap();
+        let mut seasons = Seasons::with_capacity(1);
+        seasons.insert(0);
+        let intervention = |risk: f64| {
+            Intervention::builder()
+                .set_latest_start(Day::new(1))
+                .set_periods(vec![period(0, 1), period(1, 1)].into_boxed_slice())
+                .set_seasons(vec![seasons.clone(), seasons.clone()].into_boxed_slice())
+                .set_risks(
+                    Risks::builder()
+                        .set_nscenarios(1)
+                        .set_periods(vec![0, 1, 2].into_boxed_slice())
+                        .set_risks(vec![risk, risk].into_boxed_slice())
+                        .build(),
+                )
+                .set_workloads(vec![].into_boxed_slice())
+                .set_resources(vec![].into_boxed_slice())
+                .build()
+        };
+        let exclusion = |other| InterventionExclusions {
+            exclusions: vec![(IID::new(other), seasons.clone())].into_boxed_slice(),
+        };
+        let maintenance = Maintenance::builder()
+            .set_ndays(2)
+            .set_quantile(0.5)
+            .set_alpha(0.5)
+            .set_interventions(vec![intervention(1.0), intervention(2.0)].into_boxed_slice())
+            .set_resources(vec![].into_boxed_slice())
+            .set_exclusions(vec![exclusion(1), exclusion(0)].into_boxed_slice())
+            .set_scenarios_number(vec![1, 1])
+            .set_season_of_days(vec![SID::new(0); 2].into_boxed_slice())
+            .build();
+        let mut interventions = HashMap::new();
+        interventions.insert(IID::new(0), "I1".to_owned());
+        interventions.insert(IID::new(1), "I2".to_owned());
+        let mut seasons = HashMap::new();
+        seasons.insert(SID::new(0), "winter".to_owned());
+        MaintenanceOptimization {
+            maintenance,
+            mapping: MaintenanceMapping {
+                resources: HashMap::new(),
+                interventions,
+                seasons,
+            },
+            computation_time: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn store_instance() {
+        let db = Database::open(":memory:").unwrap();
+        let uuid = Uuid::new_v4();
+        db.insert_instance(&uuid, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        let row = db.instance_row(&uuid).unwrap().unwrap();
+        assert_eq!(row.name, "small.json");
+        assert_eq!(row.ninterventions, 2);
+        assert_eq!(row.nseasons, 1);
+        assert_eq!(row.nexclusions, 1);
+        assert_eq!(row.computation_time, 60);
+        assert_eq!(row.owner, "alice");
+        assert!(db.instance_row(&Uuid::new_v4()).unwrap().is_none());
+    }
+
+    #[test]
+    fn instances_by_owner() {
+        let db = Database::open(":memory:").unwrap();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        db.insert_instance(&first, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        db.insert_instance(&second, "small.json", "bob", "f00d", &instance())
+            .unwrap();
+        assert_eq!(db.instance_rows(None).unwrap().len(), 2);
+        let rows = db.instance_rows(Some("bob")).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, second.to_string());
+        assert_eq!(db.instance_by_hash("f00d", None).unwrap(), Some(second));
+        assert_eq!(
+            db.instance_by_hash("f00d", Some("bob")).unwrap(),
+            Some(second)
+        );
+        assert_eq!(db.instance_by_hash("f00d", Some("alice")).unwrap(), None);
+        assert_eq!(db.instance_by_hash("beef", None).unwrap(), None);
+    }
+
+    #[test]
+    fn concurrent_uploads() {
+        let path = std::env::temp_dir().join(format!("{}.db", Uuid::new_v4()));
+        let db = std::sync::Arc::new(Database::open(path.to_str().unwrap()).unwrap());
+        let threads = (0..4)
+            .map(|_| {
+                let db = std::sync::Arc::clone(&db);
+                std::thread::spawn(move || {
+                    let uuid = Uuid::new_v4();
+                    db.insert_instance(&uuid, "small.json", "alice", "cafe", &instance())
+                        .unwrap();
+                    db.instance_row(&uuid).unwrap().is_some()
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert!(thread.join().unwrap());
+        }
+        assert_eq!(db.instance_rows(None).unwrap().len(), 4);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn store_reference() {
+        let db = Database::open(":memory:").unwrap();
+        let (source, first, second) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        db.insert_instance(&source, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        db.insert_reference(&first, "copy.json", "bob", &source)
+            .unwrap();
+        db.insert_reference(&second, "", "carol", &first).unwrap();
+        let row = db.instance_row(&second).unwrap().unwrap();
+        assert_eq!(row.name, "");
+        assert_eq!(row.owner, "carol");
+        assert_eq!(row.hash, "cafe");
+        assert_eq!(row.ninterventions, 2);
+        let data = db.instance_data(&source).unwrap();
+        assert!(data.is_some());
+        assert_eq!(db.instance_data(&first).unwrap(), data);
+        assert_eq!(db.instance_data(&second).unwrap(), data);
+        assert_eq!(
+            db.instance_by_hash("cafe", Some("bob")).unwrap(),
+            Some(first)
+        );
+        assert!(db
+            .insert_reference(&Uuid::new_v4(), "", "bob", &Uuid::new_v4())
+            .is_err());
+    }
+
+    #[test]
+    fn store_run_and_planning() {
+        let db = Database::open(":memory:").unwrap();
+        let (instance_id, run_id) = (Uuid::new_v4(), Uuid::new_v4());
+        db.insert_instance(&instance_id, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        db.insert_run(&NewRunParameters {
+            id: &run_id,
+            instance_id: &instance_id,
+            solver: "local-search",
+            time_limit: 60,
+            seed: 3,
+            code_version: "0.1.0",
+        })
+        .unwrap();
+        assert_eq!(db.run(&run_id).unwrap().unwrap().status, "solving");
+        let improvement = Improvement {
+            cost: 1.5,
+            obj1: 1.0,
+            obj2: 2.0,
+            feasible: true,
+            elapsed: Duration::from_millis(500),
+            iteration: 10,
+        };
+        let planning = Planning {
+            interventions: vec![(IID::new(0), Day::new(0)), (IID::new(1), Day::new(1))],
+        };
+        db.finish_run(
+            &run_id,
+            "solved",
+            Some((&improvement, &planning)),
+            std::slice::from_ref(&improvement),
+        )
+        .unwrap();
+        let run = db.run(&run_id).unwrap().unwrap();
+        assert_eq!(run.status, "solved");
+        assert!(run.finished_on.is_some());
+        let (row, stored) = db.planning(&run_id).unwrap().unwrap();
+        assert_eq!(row.cost, 1.5);
+        assert_eq!(row.iteration, 10);
+        assert_eq!(stored.interventions, planning.interventions);
+    }
+
+    #[test]
+    fn run_of_unknown_instance() {
+        let db = Database::open(":memory:").unwrap();
+        let (instance_id, run_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let run = NewRunParameters {
+            id: &run_id,
+            instance_id: &instance_id,
+            solver: "greedy",
+            time_limit: 1,
+            seed: 0,
+            code_version: "0.1.0",
+        };
+        assert!(db.insert_run(&run).is_err());
+    }
+
+    fn record_run(db: &Database, instance_id: &Uuid, solver: &str, costs: &[(f64, bool)]) -> Uuid {
+        let run_id = Uuid::new_v4();
+        db.insert_run(&NewRunParameters {
+            id: &run_id,
+            instance_id,
+            solver,
+            time_limit: 10,
+            seed: 0,
+            code_version: "0.1.0",
+        })
+        .unwrap();
+        let history = costs
+            .iter()
+            .enumerate()
+            .map(|(iteration, &(cost, feasible))| Improvement {
+                cost,
+                obj1: cost,
+                obj2: 0.0,
+                feasible,
+                elapsed: Duration::from_secs(iteration as u64),
+                iteration,
+            })
+            .collect::<Vec<_>>();
+        let planning = Planning {
+            interventions: vec![(IID::new(0), Day::new(0)), (IID::new(1), Day::new(0))],
+        };
+        let best = history.last().map(|improvement| (improvement, &planning));
+        db.finish_run(&run_id, "solved", best, &history).unwrap();
+        run_id
+    }
+
+    #[test]
+    fn run_history() {
+        let db = Database::open(":memory:").unwrap();
+        let instance_id = Uuid::new_v4();
+        db.insert_instance(&instance_id, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        assert!(db.best_planning(&instance_id).unwrap().is_none());
+        let greedy = record_run(&db, &instance_id, "greedy", &[(3.0, true)]);
+        let infeasible = record_run(&db, &instance_id, "local-search", &[(1.0, false)]);
+        let best = record_run(
+            &db,
+            &instance_id,
+            "local-search",
+            &[(4.0, true), (2.0, true)],
+        );
+        record_run(&db, &instance_id, "local-search", &[]);
+
+        let (run, row, planning) = db.best_planning(&instance_id).unwrap().unwrap();
+        assert_eq!(run.id, key(&best));
+        assert_eq!(row.cost, 2.0);
+        assert_eq!(planning.interventions.len(), 2);
+
+        // runs started in the same second have no order
+        let runs = db.runs(&instance_id, Some("local-search")).unwrap();
+        assert_eq!(runs.len(), 3);
+        assert!(runs.iter().all(|result| result.run.id != key(&greedy)));
+        assert!(runs.iter().any(|result| result.run.id == key(&infeasible)));
+        assert_eq!(
+            runs.iter()
+                .filter(|result| result.planning.is_none())
+                .count(),
+            1
+        );
+        assert_eq!(db.runs(&instance_id, None).unwrap().len(), 4);
+        assert!(db.runs(&Uuid::new_v4(), None).unwrap().is_empty());
+
+        let costs = db
+            .improvements(&best)
+            .unwrap()
+            .into_iter()
+            .map(|improvement| improvement.cost)
+            .collect::<Vec<_>>();
+        assert_eq!(costs, vec![4.0, 2.0]);
+    }
+
+    #[test]
+    fn long_history() {
+        let db = Database::open(":memory:").unwrap();
+        let instance_id = Uuid::new_v4();
+        db.insert_instance(&instance_id, "small.json", "alice", "cafe", &instance())
+            .unwrap();
+        let nimprovements = chunk_size(7) + 1;
+        let costs = (0..nimprovements)
+            .map(|iteration| ((nimprovements - iteration) as f64, true))
+            .collect::<Vec<_>>();
+        let run_id = record_run(&db, &instance_id, "local-search", &costs);
+        let improvements = db.improvements(&run_id).unwrap();
+        assert_eq!(improvements.len(), nimprovements);
+        assert_eq!(improvements.last().unwrap().cost, 1.0);
+    }
+}
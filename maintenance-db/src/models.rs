@@ -0,0 +1,139 @@
+use crate::schema::*;
+
+#[derive(Insertable)]
+#[table_name = "instances"]
+pub(crate) struct NewInstance<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub ndays: i32,
+    pub nresources: i32,
+    pub ninterventions: i32,
+    pub nseasons: i32,
+    pub nexclusions: i32,
+    pub nscenarios: i32,
+    pub quantile: f64,
+    pub alpha: f64,
+    pub computation_time: i64,
+    pub created_on: i64,
+    pub data: &'a [u8],
+    pub owner: &'a str,
+    pub hash: &'a str,
+    /// Instance holding the data of a reference
+    pub source_id: Option<&'a str>,
+}
+
+/// Description of a stored instance, without its data.
+#[derive(Clone, Debug, PartialEq, Queryable)]
+pub struct InstanceRow {
+    pub id: String,
+    pub name: String,
+    pub ndays: i32,
+    pub nresources: i32,
+    pub ninterventions: i32,
+    pub nseasons: i32,
+    pub nexclusions: i32,
+    pub nscenarios: i32,
+    pub quantile: f64,
+    pub alpha: f64,
+    pub computation_time: i64,
+    pub created_on: i64,
+    pub owner: String,
+    /// SHA-256 of the uploaded file
+    pub hash: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "resources"]
+pub(crate) struct NewResource<'a> {
+    pub instance_id: &'a str,
+    pub rid: i32,
+    pub name: &'a str,
+}
+
+#[derive(Insertable)]
+#[table_name = "interventions"]
+pub(crate) struct NewIntervention<'a> {
+    pub instance_id: &'a str,
+    pub iid: i32,
+    pub name: &'a str,
+    pub latest_start: i32,
+}
+
+#[derive(Insertable)]
+#[table_name = "seasons"]
+pub(crate) struct NewSeason<'a> {
+    pub instance_id: &'a str,
+    pub sid: i32,
+    pub name: &'a str,
+}
+
+#[derive(Insertable)]
+#[table_name = "exclusions"]
+pub(crate) struct NewExclusion<'a> {
+    pub instance_id: &'a str,
+    pub first_iid: i32,
+    pub second_iid: i32,
+    pub sid: i32,
+}
+
+#[derive(Insertable)]
+#[table_name = "runs"]
+pub(crate) struct NewRun<'a> {
+    pub id: &'a str,
+    pub instance_id: &'a str,
+    pub solver: &'a str,
+    pub time_limit: i64,
+    pub seed: i64,
+    pub status: &'a str,
+    pub started_on: i64,
+    pub code_version: &'a str,
+}
+
+#[derive(Clone, Debug, PartialEq, Queryable)]
+pub struct RunRow {
+    pub id: String,
+    pub instance_id: String,
+    pub solver: String,
+    /// in seconds
+    pub time_limit: i64,
+    pub seed: i64,
+    pub status: String,
+    pub started_on: i64,
+    pub finished_on: Option<i64>,
+    pub code_version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Insertable, Queryable)]
+#[table_name = "plannings"]
+pub struct PlanningRow {
+    pub run_id: String,
+    pub cost: f64,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub feasible: bool,
+    /// in seconds
+    pub elapsed: f64,
+    pub iteration: i64,
+}
+
+#[derive(Insertable)]
+#[table_name = "planning_interventions"]
+pub(crate) struct NewPlannedIntervention<'a> {
+    pub run_id: &'a str,
+    pub iid: i32,
+    pub start_day: i32,
+}
+
+/// Cost of a run when a better planning was found.
+#[derive(Clone, Debug, PartialEq, Insertable, Queryable)]
+#[table_name = "improvements"]
+pub struct ImprovementRow {
+    pub run_id: String,
+    pub iteration: i64,
+    /// in seconds
+    pub elapsed: f64,
+    pub cost: f64,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub feasible: bool,
+}
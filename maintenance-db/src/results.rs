@@ -0,0 +1,74 @@
+//! History of the solver runs, to follow whether the changes of the solvers
+//! pay off across the runs.
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use maintenance::Planning;
+
+use crate::models::{ImprovementRow, PlanningRow, RunRow};
+use crate::schema::{improvements, plannings, runs};
+use crate::{key, Database, DbPool, Result};
+
+/// A run with its best planning, if it found one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunResult {
+    pub run: RunRow,
+    pub planning: Option<PlanningRow>,
+}
+
+impl Database {
+    /// Runs on an instance, of every solver or of the given one, oldest first.
+    pub fn runs(&self, instance: &Uuid, solver: Option<&str>) -> Result<Vec<RunResult>> {
+        let instance_id = key(instance);
+        let rows = with_connection!(self.pool, conn => {
+            let mut query = runs::table
+                .left_join(plannings::table)
+                .filter(runs::instance_id.eq(&instance_id))
+                .order((runs::started_on, runs::id))
+                .into_boxed();
+            if let Some(solver) = solver {
+                query = query.filter(runs::solver.eq(solver));
+            }
+            query.load::<(RunRow, Option<PlanningRow>)>(conn)?
+        });
+        Ok(rows
+            .into_iter()
+            .map(|(run, planning)| RunResult { run, planning })
+            .collect())
+    }
+
+    /// Improvements of a run in the order they were found.
+    pub fn improvements(&self, run: &Uuid) -> Result<Vec<ImprovementRow>> {
+        let run_id = key(run);
+        let rows = with_connection!(self.pool, conn => improvements::table
+            .filter(improvements::run_id.eq(&run_id))
+            .order(improvements::iteration)
+            .load::<ImprovementRow>(conn)?);
+        Ok(rows)
+    }
+
+    /// Best planning known for an instance over all the runs: the feasible
+    /// ones first, then the lowest cost.
+    pub fn best_planning(
+        &self,
+        instance: &Uuid,
+    ) -> Result<Option<(RunRow, PlanningRow, Planning)>> {
+        let instance_id = key(instance);
+        let best = {
+            with_connection!(self.pool, conn => runs::table
+                .inner_join(plannings::table)
+                .filter(runs::instance_id.eq(&instance_id))
+                .order((plannings::feasible.desc(), plannings::cost, runs::started_on))
+                .first::<(RunRow, PlanningRow)>(conn)
+                .optional()?)
+        };
+        let run = match best {
+            Some((run, _)) => run,
+            None => return Ok(None),
+        };
+        let run_id = Uuid::parse_str(&run.id)?;
+        Ok(self
+            .planning(&run_id)?
+            .map(|(row, planning)| (run, row, planning)))
+    }
+}
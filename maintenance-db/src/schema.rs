@@ -0,0 +1,121 @@
+table! {
+    instances (id) {
+        id -> Text,
+        name -> Text,
+        ndays -> Integer,
+        nresources -> Integer,
+        ninterventions -> Integer,
+        nseasons -> Integer,
+        nexclusions -> Integer,
+        nscenarios -> Integer,
+        quantile -> Double,
+        alpha -> Double,
+        computation_time -> BigInt,
+        created_on -> BigInt,
+        data -> Binary,
+        owner -> Text,
+        hash -> Text,
+        source_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    resources (instance_id, rid) {
+        instance_id -> Text,
+        rid -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    interventions (instance_id, iid) {
+        instance_id -> Text,
+        iid -> Integer,
+        name -> Text,
+        latest_start -> Integer,
+    }
+}
+
+table! {
+    seasons (instance_id, sid) {
+        instance_id -> Text,
+        sid -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    exclusions (instance_id, first_iid, second_iid, sid) {
+        instance_id -> Text,
+        first_iid -> Integer,
+        second_iid -> Integer,
+        sid -> Integer,
+    }
+}
+
+table! {
+    runs (id) {
+        id -> Text,
+        instance_id -> Text,
+        solver -> Text,
+        time_limit -> BigInt,
+        seed -> BigInt,
+        status -> Text,
+        started_on -> BigInt,
+        finished_on -> Nullable<BigInt>,
+        code_version -> Text,
+    }
+}
+
+table! {
+    plannings (run_id) {
+        run_id -> Text,
+        cost -> Double,
+        obj1 -> Double,
+        obj2 -> Double,
+        feasible -> Bool,
+        elapsed -> Double,
+        iteration -> BigInt,
+    }
+}
+
+table! {
+    planning_interventions (run_id, iid) {
+        run_id -> Text,
+        iid -> Integer,
+        start_day -> Integer,
+    }
+}
+
+table! {
+    improvements (run_id, iteration) {
+        run_id -> Text,
+        iteration -> BigInt,
+        elapsed -> Double,
+        cost -> Double,
+        obj1 -> Double,
+        obj2 -> Double,
+        feasible -> Bool,
+    }
+}
+
+joinable!(resources -> instances (instance_id));
+joinable!(interventions -> instances (instance_id));
+joinable!(seasons -> instances (instance_id));
+joinable!(exclusions -> instances (instance_id));
+joinable!(runs -> instances (instance_id));
+joinable!(plannings -> runs (run_id));
+joinable!(planning_interventions -> plannings (run_id));
+joinable!(improvements -> runs (run_id));
+
+allow_tables_to_appear_in_same_query!(
+    instances,
+    resources,
+    interventions,
+    seasons,
+    exclusions,
+    runs,
+    plannings,
+    planning_interventions,
+    improvements,
+);
@@ -1,39 +1,49 @@
 extern crate maintenance;
+extern crate maintenance_capnproto;
 extern crate maintenance_json;
 
 use std::env;
-use std::fmt;
 use std::io::prelude::*;
 use std::time::Instant;
 
 use maintenance::*;
+use maintenance_capnproto::instance;
+use maintenance_json::ExportPlanning;
 
-struct ExportPlanning<'a> {
-    planning: Planning,
-    mapping: &'a MaintenanceMapping,
+fn load_json(path: &str, now: &Instant) -> Option<MaintenanceOptimization> {
+    let contents = maintenance_json::read_contents(path);
+    println!("Contents reading in: {}s", now.elapsed().as_secs());
+    let m = maintenance_json::read_json(&contents);
+    println!("Json reading in: {}s", now.elapsed().as_secs());
+    maintenance_json::load_instance(m.unwrap())
 }
 
-impl<'a> fmt::Display for ExportPlanning<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (iid, day) in self.planning.interventions.iter() {
-            let intervention_name = &self.mapping.interventions[iid];
-            let day = *day + Day::new(1);
-            writeln!(f, "{} {}", intervention_name, day.get())?;
+// NOTE(vincent): the binary cache is rebuilt each time the json is modified,
+// or when it was written by another format version.
+fn load(path: &str, now: &Instant) -> Option<MaintenanceOptimization> {
+    let cache = instance::cache_path(path);
+    if instance::is_fresh(&cache, path) {
+        match instance::load(&cache) {
+            Ok(m) => {
+                println!("Cache reading in: {}s", now.elapsed().as_secs());
+                return Some(m);
+            }
+            Err(err) => println!("Invalid cache {:?}: {}", cache, err),
         }
-        write!(f, "")
     }
+    let m = load_json(path, now);
+    if let Some(ref m) = m {
+        if let Err(err) = instance::save(&cache, m) {
+            println!("Cache writing failed {:?}: {}", cache, err);
+        }
+    }
+    m
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let now = Instant::now();
-    let m = {
-        let contents = maintenance_json::read_contents(&args[1]);
-        println!("Contents reading in: {}s", now.elapsed().as_secs());
-        let m = maintenance_json::read_json(&contents);
-        println!("Json reading in: {}s", now.elapsed().as_secs());
-        maintenance_json::load_instance(m.unwrap())
-    };
+    let m = load(&args[1], &now);
     match m {
         Some(instance) => {
             println!("loading: {}", instance.maintenance.ninterventions());
@@ -47,7 +57,7 @@ fn main() {
                 out,
                 "{}",
                 ExportPlanning {
-                    planning,
+                    planning: &planning,
                     mapping: &instance.mapping
                 }
             );
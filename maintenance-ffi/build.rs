@@ -0,0 +1,13 @@
+extern crate cbindgen;
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    // NOTE(vincent): the header is kept in the repository, for the C++ tools
+    // built without cargo.
+    cbindgen::generate(&crate_dir)
+        .expect("unable to generate the C header")
+        .write_to_file(crate_dir.join("include").join("maintenance.h"));
+}
@@ -0,0 +1,176 @@
+#ifndef MAINTENANCE_H
+#define MAINTENANCE_H
+
+/* Generated by cbindgen from maintenance-ffi, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum MaintenanceStatus {
+  MAINTENANCE_STATUS_OK = 0,
+  /**
+   * A required pointer is null
+   */
+  MAINTENANCE_STATUS_NULL_POINTER,
+  /**
+   * The file cannot be read
+   */
+  MAINTENANCE_STATUS_IO,
+  /**
+   * Not an instance in the JSON format of the challenge
+   */
+  MAINTENANCE_STATUS_INVALID_INSTANCE,
+  /**
+   * Unknown intervention, or day out of the horizon
+   */
+  MAINTENANCE_STATUS_INVALID_ARGUMENT,
+  /**
+   * Bug of the library, the handles given should not be used anymore
+   */
+  MAINTENANCE_STATUS_PANIC,
+} MaintenanceStatus;
+
+/**
+ * Planning evaluated incrementally: setting a start day only updates the
+ * days of the intervention moved.
+ */
+typedef struct MaintenanceEvaluator MaintenanceEvaluator;
+
+/**
+ * Instance loaded from the JSON format of the challenge.
+ */
+typedef struct MaintenanceInstance MaintenanceInstance;
+
+/**
+ * Feasibility of a start day and change of the objectives, see
+ * `maintenance_evaluator_set_start`.
+ */
+typedef struct MaintenanceMove {
+  /**
+   * The day is not after the latest start of the intervention, otherwise
+   * the planning is unchanged
+   */
+  bool latest_start;
+  /**
+   * Maximum workloads respected during the new period of the intervention
+   */
+  bool max_workloads;
+  /**
+   * No exclusion with the interventions planned during the new period
+   */
+  bool exclusions;
+  /**
+   * Every intervention planned and minimum workloads reached
+   */
+  bool min_workloads;
+  bool feasible;
+  double obj1;
+  double obj2;
+  double cost;
+  double delta_obj1;
+  double delta_obj2;
+  double delta_cost;
+} MaintenanceMove;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Loads an instance file in the JSON format of the challenge. On success,
+ * `*instance` is a handle to free with `maintenance_instance_free`.
+ */
+MaintenanceStatus maintenance_instance_from_path(const char *path, MaintenanceInstance **instance);
+
+/**
+ * Same as `maintenance_instance_from_path`, from the `len` bytes of a file
+ * already in memory.
+ */
+MaintenanceStatus maintenance_instance_from_buffer(const uint8_t *data, size_t len, MaintenanceInstance **instance);
+
+/**
+ * The evaluators created from the instance stay valid.
+ */
+void maintenance_instance_free(MaintenanceInstance *instance);
+
+size_t maintenance_instance_ndays(const MaintenanceInstance *instance);
+
+size_t maintenance_instance_ninterventions(const MaintenanceInstance *instance);
+
+/**
+ * Null for an unknown intervention, otherwise valid as long as the
+ * instance.
+ */
+const char *maintenance_instance_intervention_name(const MaintenanceInstance *instance, size_t iid);
+
+/**
+ * Sets `*iid` to the id of the intervention named `name`.
+ */
+MaintenanceStatus maintenance_instance_find_intervention(const MaintenanceInstance *instance, const char *name, size_t *iid);
+
+/**
+ * Last start day of an intervention, 0 if it is unknown.
+ */
+uint32_t maintenance_instance_latest_start(const MaintenanceInstance *instance, size_t iid);
+
+/**
+ * New evaluator of the instance, with no intervention planned. On success,
+ * `*evaluator` is a handle to free with `maintenance_evaluator_free`.
+ */
+MaintenanceStatus maintenance_evaluator_new(const MaintenanceInstance *instance, MaintenanceEvaluator **evaluator);
+
+void maintenance_evaluator_free(MaintenanceEvaluator *evaluator);
+
+/**
+ * Replaces the whole planning: `days` holds the start day of each of the
+ * `len` interventions, by id, 0 when it is not planned.
+ */
+MaintenanceStatus maintenance_evaluator_set_planning(MaintenanceEvaluator *evaluator, const uint32_t *days, size_t len);
+
+/**
+ * Moves an intervention to start on `day`, even if the planning becomes
+ * infeasible. `report`, when not null, is set to the feasibility and the
+ * change of the objectives.
+ */
+MaintenanceStatus maintenance_evaluator_set_start(MaintenanceEvaluator *evaluator, size_t iid, uint32_t day, MaintenanceMove *report);
+
+/**
+ * Report of `maintenance_evaluator_set_start`, the planning is unchanged.
+ */
+MaintenanceStatus maintenance_evaluator_evaluate_start(MaintenanceEvaluator *evaluator, size_t iid, uint32_t day, MaintenanceMove *report);
+
+/**
+ * Start day of an intervention, 0 when it is not planned or unknown.
+ */
+uint32_t maintenance_evaluator_start(const MaintenanceEvaluator *evaluator, size_t iid);
+
+/**
+ * Cost of the current planning, NaN on failure.
+ */
+double maintenance_evaluator_cost(const MaintenanceEvaluator *evaluator);
+
+/**
+ * Sets the objectives of the current planning: the mean risk and the
+ * expected excess.
+ */
+MaintenanceStatus maintenance_evaluator_objectives(const MaintenanceEvaluator *evaluator, double *obj1, double *obj2);
+
+/**
+ * Every intervention planned, and every constraint respected.
+ */
+bool maintenance_evaluator_is_feasible(const MaintenanceEvaluator *evaluator);
+
+/**
+ * Message of the last failure of the thread, null if none. It is valid
+ * until the next failure of the thread.
+ */
+const char *maintenance_last_error(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* MAINTENANCE_H */
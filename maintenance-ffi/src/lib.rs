@@ -0,0 +1,755 @@
+//! C API of the incremental evaluation, for the planning tools written in C
+//! or C++. The header `include/maintenance.h` is generated by cbindgen when
+//! the crate is built.
+//!
+//! The instances and the evaluators are opaque handles, freed by their
+//! `_free` function. No panic crosses the boundary: it is reported as
+//! `MAINTENANCE_STATUS_PANIC`, or as a neutral value by the functions
+//! without status, and `maintenance_last_error` gives its message.
+//!
+//! As in the instances, the days start at 1. The interventions are
+//! identified by their index, from 0.
+//!
+//! # Safety
+//!
+//! The pointers given are either null or valid, the handles coming from this
+//! library and not freed yet. A handle is not used by two threads at once.
+#![allow(clippy::missing_safety_doc)]
+extern crate maintenance;
+extern crate maintenance_json;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use maintenance::search::{LocalSearch, MoveReport};
+use maintenance::{Day, MaintenanceOptimization, Planning, IID};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintenanceStatus {
+    Ok = 0,
+    /// A required pointer is null
+    NullPointer,
+    /// The file cannot be read
+    Io,
+    /// Not an instance in the JSON format of the challenge
+    InvalidInstance,
+    /// Unknown intervention, or day out of the horizon
+    InvalidArgument,
+    /// Bug of the library, the handles given should not be used anymore
+    Panic,
+}
+
+/// Feasibility of a start day and change of the objectives, see
+/// `maintenance_evaluator_set_start`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaintenanceMove {
+    /// The day is not after the latest start of the intervention, otherwise
+    /// the planning is unchanged
+    pub latest_start: bool,
+    /// Maximum workloads respected during the new period of the intervention
+    pub max_workloads: bool,
+    /// No exclusion with the interventions planned during the new period
+    pub exclusions: bool,
+    /// Every intervention planned and minimum workloads reached
+    pub min_workloads: bool,
+    pub feasible: bool,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub cost: f64,
+    pub delta_obj1: f64,
+    pub delta_obj2: f64,
+    pub delta_cost: f64,
+}
+
+impl From<MoveReport> for MaintenanceMove {
+    fn from(report: MoveReport) -> Self {
+        MaintenanceMove {
+            latest_start: report.latest_start,
+            max_workloads: report.max_workloads,
+            exclusions: report.exclusions,
+            min_workloads: report.min_workloads,
+            feasible: report.is_feasible(),
+            obj1: report.obj1,
+            obj2: report.obj2,
+            cost: report.cost,
+            delta_obj1: report.delta_obj1,
+            delta_obj2: report.delta_obj2,
+            delta_cost: report.delta_cost,
+        }
+    }
+}
+
+struct Error {
+    status: MaintenanceStatus,
+    message: String,
+}
+
+impl Error {
+    fn new<M: Into<String>>(status: MaintenanceStatus, message: M) -> Self {
+        Error {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn null(name: &str) -> Self {
+        Error::new(
+            MaintenanceStatus::NullPointer,
+            format!("`{}` is null", name),
+        )
+    }
+
+    fn invalid_argument<M: Into<String>>(message: M) -> Self {
+        Error::new(MaintenanceStatus::InvalidArgument, message)
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => (*message).to_owned(),
+            Err(_) => "unknown panic".to_owned(),
+        },
+    }
+}
+
+/// Runs the body of an exported function: the failures, panics included,
+/// are recorded for `maintenance_last_error`.
+fn catch<T, F>(f: F) -> Result<T, MaintenanceStatus>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(error)) => {
+            set_last_error(error.message);
+            Err(error.status)
+        }
+        Err(payload) => {
+            set_last_error(format!("panic: {}", panic_message(payload)));
+            Err(MaintenanceStatus::Panic)
+        }
+    }
+}
+
+fn status(result: Result<(), MaintenanceStatus>) -> MaintenanceStatus {
+    result.err().unwrap_or(MaintenanceStatus::Ok)
+}
+
+unsafe fn reference<'a, T>(pointer: *const T, name: &str) -> Result<&'a T, Error> {
+    pointer.as_ref().ok_or_else(|| Error::null(name))
+}
+
+unsafe fn reference_mut<'a, T>(pointer: *mut T, name: &str) -> Result<&'a mut T, Error> {
+    pointer.as_mut().ok_or_else(|| Error::null(name))
+}
+
+unsafe fn string<'a>(pointer: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if pointer.is_null() {
+        return Err(Error::null(name));
+    }
+    CStr::from_ptr(pointer)
+        .to_str()
+        .map_err(|_| Error::invalid_argument(format!("`{}` is not UTF-8", name)))
+}
+
+/// Day from 1 of the API, `None` for 0
+fn day(day: u32) -> Option<Day> {
+    (day as usize).checked_sub(1).map(Day::new)
+}
+
+/// Instance loaded from the JSON format of the challenge.
+pub struct MaintenanceInstance {
+    instance: Arc<MaintenanceOptimization>,
+    /// Name of each intervention, by id
+    names: Vec<CString>,
+}
+
+impl MaintenanceInstance {
+    fn from_json(contents: &str) -> Result<Self, Error> {
+        let m = maintenance_json::read_json(contents).map_err(|err| {
+            Error::new(
+                MaintenanceStatus::InvalidInstance,
+                format!("invalid json: {:?}", err),
+            )
+        })?;
+        // NOTE(vincent): the loader still panics on some malformed instances.
+        let instance = panic::catch_unwind(AssertUnwindSafe(|| maintenance_json::load_instance(m)))
+            .ok()
+            .flatten()
+            .ok_or_else(|| Error::new(MaintenanceStatus::InvalidInstance, "invalid instance"))?;
+        let names = (0..instance.maintenance.ninterventions())
+            .map(|iid| {
+                let name = instance.mapping.interventions[&IID::new(iid)].replace('\0', " ");
+                CString::new(name).unwrap_or_default()
+            })
+            .collect();
+        Ok(MaintenanceInstance {
+            instance: Arc::new(instance),
+            names,
+        })
+    }
+
+    fn intervention(&self, iid: usize) -> Result<IID, Error> {
+        if iid < self.names.len() {
+            Ok(IID::new(iid))
+        } else {
+            Err(Error::invalid_argument(format!(
+                "no intervention {}, the instance has {}",
+                iid,
+                self.names.len()
+            )))
+        }
+    }
+}
+
+unsafe fn new_instance(
+    contents: &str,
+    instance: *mut *mut MaintenanceInstance,
+) -> Result<(), Error> {
+    let instance = reference_mut(instance, "instance")?;
+    *instance = Box::into_raw(Box::new(MaintenanceInstance::from_json(contents)?));
+    Ok(())
+}
+
+/// Loads an instance file in the JSON format of the challenge. On success,
+/// `*instance` is a handle to free with `maintenance_instance_free`.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_from_path(
+    path: *const c_char,
+    instance: *mut *mut MaintenanceInstance,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        let path = string(path, "path")?;
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            Error::new(
+                MaintenanceStatus::Io,
+                format!("cannot read {}: {}", path, err),
+            )
+        })?;
+        new_instance(&contents, instance)
+    }))
+}
+
+/// Same as `maintenance_instance_from_path`, from the `len` bytes of a file
+/// already in memory.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_from_buffer(
+    data: *const u8,
+    len: usize,
+    instance: *mut *mut MaintenanceInstance,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        if data.is_null() {
+            return Err(Error::null("data"));
+        }
+        let contents = std::str::from_utf8(slice::from_raw_parts(data, len))
+            .map_err(|err| Error::new(MaintenanceStatus::InvalidInstance, err.to_string()))?;
+        new_instance(contents, instance)
+    }))
+}
+
+/// The evaluators created from the instance stay valid.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_free(instance: *mut MaintenanceInstance) {
+    let _ = catch(|| {
+        if !instance.is_null() {
+            drop(Box::from_raw(instance));
+        }
+        Ok(())
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_ndays(instance: *const MaintenanceInstance) -> usize {
+    catch(|| {
+        Ok(reference(instance, "instance")?
+            .instance
+            .maintenance
+            .ndays())
+    })
+    .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_ninterventions(
+    instance: *const MaintenanceInstance,
+) -> usize {
+    catch(|| Ok(reference(instance, "instance")?.names.len())).unwrap_or(0)
+}
+
+/// Null for an unknown intervention, otherwise valid as long as the
+/// instance.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_intervention_name(
+    instance: *const MaintenanceInstance,
+    iid: usize,
+) -> *const c_char {
+    catch(|| {
+        let instance = reference(instance, "instance")?;
+        let iid = instance.intervention(iid)?;
+        Ok(instance.names[iid.get()].as_ptr())
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// Sets `*iid` to the id of the intervention named `name`.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_find_intervention(
+    instance: *const MaintenanceInstance,
+    name: *const c_char,
+    iid: *mut usize,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        let instance = reference(instance, "instance")?;
+        let name = string(name, "name")?;
+        let iid = reference_mut(iid, "iid")?;
+        *iid = instance
+            .instance
+            .mapping
+            .interventions
+            .iter()
+            .find(|(_, other)| other.as_str() == name)
+            .map(|(iid, _)| iid.get())
+            .ok_or_else(|| Error::invalid_argument(format!("unknown intervention `{}`", name)))?;
+        Ok(())
+    }))
+}
+
+/// Last start day of an intervention, 0 if it is unknown.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_instance_latest_start(
+    instance: *const MaintenanceInstance,
+    iid: usize,
+) -> u32 {
+    catch(|| {
+        let instance = reference(instance, "instance")?;
+        let iid = instance.intervention(iid)?;
+        let latest_start = instance
+            .instance
+            .maintenance
+            .intervention(iid)
+            .latest_start();
+        Ok(latest_start.get() as u32 + 1)
+    })
+    .unwrap_or(0)
+}
+
+/// Planning evaluated incrementally: setting a start day only updates the
+/// days of the intervention moved.
+pub struct MaintenanceEvaluator {
+    // NOTE(vincent): declared first to be dropped before the instance it
+    // borrows.
+    search: LocalSearch<'static>,
+    instance: Arc<MaintenanceOptimization>,
+}
+
+impl MaintenanceEvaluator {
+    fn new(instance: Arc<MaintenanceOptimization>, planning: &Planning) -> Self {
+        // SAFETY: the instance is kept alive, and never moved, by the `Arc`
+        // held next to the search, which is dropped first.
+        let maintenance = unsafe { &*(&instance.maintenance as *const _) };
+        MaintenanceEvaluator {
+            search: LocalSearch::from_planning(maintenance, planning),
+            instance,
+        }
+    }
+
+    fn intervention(&self, iid: usize) -> Result<IID, Error> {
+        let ninterventions = self.instance.maintenance.ninterventions();
+        if iid < ninterventions {
+            Ok(IID::new(iid))
+        } else {
+            Err(Error::invalid_argument(format!(
+                "no intervention {}, the instance has {}",
+                iid, ninterventions
+            )))
+        }
+    }
+
+    /// Report of a move, not kept unless `apply`
+    fn move_intervention(
+        &mut self,
+        iid: usize,
+        start: u32,
+        apply: bool,
+        report: *mut MaintenanceMove,
+    ) -> Result<(), Error> {
+        let iid = self.intervention(iid)?;
+        let day = day(start).ok_or_else(|| Error::invalid_argument("the first day is 1"))?;
+        let move_report = if apply {
+            self.search.move_intervention(iid, day)
+        } else {
+            self.search.evaluate_move(iid, day)
+        };
+        if let Some(report) = unsafe { report.as_mut() } {
+            *report = move_report.into();
+        }
+        Ok(())
+    }
+}
+
+/// New evaluator of the instance, with no intervention planned. On success,
+/// `*evaluator` is a handle to free with `maintenance_evaluator_free`.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_new(
+    instance: *const MaintenanceInstance,
+    evaluator: *mut *mut MaintenanceEvaluator,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        let instance = reference(instance, "instance")?;
+        let evaluator = reference_mut(evaluator, "evaluator")?;
+        *evaluator = Box::into_raw(Box::new(MaintenanceEvaluator::new(
+            Arc::clone(&instance.instance),
+            &Planning::default(),
+        )));
+        Ok(())
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_free(evaluator: *mut MaintenanceEvaluator) {
+    let _ = catch(|| {
+        if !evaluator.is_null() {
+            drop(Box::from_raw(evaluator));
+        }
+        Ok(())
+    });
+}
+
+/// Replaces the whole planning: `days` holds the start day of each of the
+/// `len` interventions, by id, 0 when it is not planned.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_set_planning(
+    evaluator: *mut MaintenanceEvaluator,
+    days: *const u32,
+    len: usize,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        let evaluator = reference_mut(evaluator, "evaluator")?;
+        if days.is_null() {
+            return Err(Error::null("days"));
+        }
+        let maintenance = &evaluator.instance.maintenance;
+        if len != maintenance.ninterventions() {
+            return Err(Error::invalid_argument(format!(
+                "{} days given for {} interventions",
+                len,
+                maintenance.ninterventions()
+            )));
+        }
+        let mut interventions = Vec::new();
+        for (iid, &start) in slice::from_raw_parts(days, len).iter().enumerate() {
+            let day = match day(start) {
+                Some(day) => day,
+                None => continue,
+            };
+            let iid = IID::new(iid);
+            if !maintenance.intervention(iid).can_start_on(day) {
+                return Err(Error::invalid_argument(format!(
+                    "intervention {} cannot start on day {}",
+                    iid.get(),
+                    start
+                )));
+            }
+            interventions.push((iid, day));
+        }
+        let instance = Arc::clone(&evaluator.instance);
+        *evaluator = MaintenanceEvaluator::new(instance, &Planning { interventions });
+        Ok(())
+    }))
+}
+
+/// Moves an intervention to start on `day`, even if the planning becomes
+/// infeasible. `report`, when not null, is set to the feasibility and the
+/// change of the objectives.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_set_start(
+    evaluator: *mut MaintenanceEvaluator,
+    iid: usize,
+    day: u32,
+    report: *mut MaintenanceMove,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        reference_mut(evaluator, "evaluator")?.move_intervention(iid, day, true, report)
+    }))
+}
+
+/// Report of `maintenance_evaluator_set_start`, the planning is unchanged.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_evaluate_start(
+    evaluator: *mut MaintenanceEvaluator,
+    iid: usize,
+    day: u32,
+    report: *mut MaintenanceMove,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        reference_mut(evaluator, "evaluator")?.move_intervention(iid, day, false, report)
+    }))
+}
+
+/// Start day of an intervention, 0 when it is not planned or unknown.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_start(
+    evaluator: *const MaintenanceEvaluator,
+    iid: usize,
+) -> u32 {
+    catch(|| {
+        let evaluator = reference(evaluator, "evaluator")?;
+        let iid = evaluator.intervention(iid)?;
+        Ok(evaluator
+            .search
+            .start_day(iid)
+            .map_or(0, |day| day.get() as u32 + 1))
+    })
+    .unwrap_or(0)
+}
+
+/// Cost of the current planning, NaN on failure.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_cost(evaluator: *const MaintenanceEvaluator) -> f64 {
+    catch(|| Ok(reference(evaluator, "evaluator")?.search.cost())).unwrap_or(f64::NAN)
+}
+
+/// Sets the objectives of the current planning: the mean risk and the
+/// expected excess.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_objectives(
+    evaluator: *const MaintenanceEvaluator,
+    obj1: *mut f64,
+    obj2: *mut f64,
+) -> MaintenanceStatus {
+    status(catch(|| {
+        let report = reference(evaluator, "evaluator")?.search.cost_report();
+        *reference_mut(obj1, "obj1")? = report.obj1;
+        *reference_mut(obj2, "obj2")? = report.obj2;
+        Ok(())
+    }))
+}
+
+/// Every intervention planned, and every constraint respected.
+#[no_mangle]
+pub unsafe extern "C" fn maintenance_evaluator_is_feasible(
+    evaluator: *const MaintenanceEvaluator,
+) -> bool {
+    catch(|| {
+        Ok(reference(evaluator, "evaluator")?
+            .search
+            .is_strictly_feasible())
+    })
+    .unwrap_or(false)
+}
+
+/// Message of the last failure of the thread, null if none. It is valid
+/// until the next failure of the thread.
+#[no_mangle]
+pub extern "C" fn maintenance_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTANCE: &str = r#"{
+        "Resources": {"c1": {"min": [1.0, 0.0, 0.0, 0.0, 0.0], "max": [4.0, 4.0, 4.0, 4.0, 4.0]}},
+        "Seasons": {"winter": ["1", "2"], "summer": ["3", "4", "5"], "is": []},
+        "Interventions": {
+            "I1": {"tmax": "3", "Delta": [2.0, 2.0, 2.0, 1.0, 1.0],
+                   "workload": {"c1": {"1": {"1": 2.0}, "2": {"1": 2.0, "2": 2.0}, "3": {"2": 2.0, "3": 2.0}, "4": {"3": 2.0}}},
+                   "risk": {"1": {"1": [1.0, 2.0]}, "2": {"1": [1.0, 2.0], "2": [4.0, 4.0]}, "3": {"2": [4.0, 4.0], "3": [2.0, 1.0]}, "4": {"3": [2.0, 1.0]}}},
+            "I2": {"tmax": "4", "Delta": [1.0, 1.0, 1.0, 1.0, 1.0],
+                   "workload": {"c1": {"1": {"1": 3.0}, "2": {"2": 3.0}, "3": {"3": 3.0}, "4": {"4": 3.0}}},
+                   "risk": {"1": {"1": [5.0, 5.0]}, "2": {"2": [1.0, 1.0]}, "3": {"3": [3.0, 3.0]}, "4": {"4": [0.5, 2.0]}}}
+        },
+        "Exclusions": {"E1": ["I1", "I2", "summer"]},
+        "T": 5,
+        "Scenarios_number": [2, 2, 2, 2, 2],
+        "Quantile": 0.5,
+        "Alpha": 0.5,
+        "ComputationTime": 60
+    }"#;
+
+    fn last_error() -> String {
+        let message = maintenance_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    fn from_buffer(data: &[u8]) -> Result<*mut MaintenanceInstance, MaintenanceStatus> {
+        let mut instance = ptr::null_mut();
+        match unsafe { maintenance_instance_from_buffer(data.as_ptr(), data.len(), &mut instance) }
+        {
+            MaintenanceStatus::Ok => Ok(instance),
+            status => Err(status),
+        }
+    }
+
+    /// Evaluator of the test instance, with the instance handle already freed
+    fn evaluator() -> *mut MaintenanceEvaluator {
+        let instance = from_buffer(INSTANCE.as_bytes()).unwrap();
+        let mut evaluator = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                maintenance_evaluator_new(instance, &mut evaluator),
+                MaintenanceStatus::Ok
+            );
+            maintenance_instance_free(instance);
+        }
+        evaluator
+    }
+
+    #[test]
+    fn null_pointers() {
+        assert!(maintenance_last_error().is_null());
+        let mut instance = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                maintenance_instance_from_buffer(ptr::null(), 0, &mut instance),
+                MaintenanceStatus::NullPointer
+            );
+            assert_eq!(last_error(), "`data` is null");
+            assert_eq!(
+                maintenance_instance_from_buffer(
+                    INSTANCE.as_ptr(),
+                    INSTANCE.len(),
+                    ptr::null_mut()
+                ),
+                MaintenanceStatus::NullPointer
+            );
+            assert_eq!(
+                maintenance_instance_from_path(ptr::null(), &mut instance),
+                MaintenanceStatus::NullPointer
+            );
+            assert!(instance.is_null());
+            assert_eq!(maintenance_instance_ndays(ptr::null()), 0);
+            assert!(maintenance_instance_intervention_name(ptr::null(), 0).is_null());
+            assert!(maintenance_evaluator_cost(ptr::null()).is_nan());
+            assert!(!maintenance_evaluator_is_feasible(ptr::null()));
+            assert_eq!(
+                maintenance_evaluator_set_planning(ptr::null_mut(), [1, 1].as_ptr(), 2),
+                MaintenanceStatus::NullPointer
+            );
+            let evaluator = evaluator();
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, ptr::null(), 2),
+                MaintenanceStatus::NullPointer
+            );
+            assert_eq!(last_error(), "`days` is null");
+            maintenance_evaluator_free(evaluator);
+            maintenance_instance_free(ptr::null_mut());
+            maintenance_evaluator_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn invalid_buffer() {
+        assert_eq!(
+            from_buffer(&[0xff, 0xfe]).unwrap_err(),
+            MaintenanceStatus::InvalidInstance
+        );
+        assert_eq!(
+            from_buffer(b"{}").unwrap_err(),
+            MaintenanceStatus::InvalidInstance
+        );
+        assert!(last_error().starts_with("invalid json"));
+        let instance = from_buffer(INSTANCE.as_bytes()).unwrap();
+        unsafe {
+            assert_eq!(maintenance_instance_ndays(instance), 5);
+            assert_eq!(maintenance_instance_ninterventions(instance), 2);
+            maintenance_instance_free(instance);
+        }
+    }
+
+    #[test]
+    fn set_planning() {
+        let evaluator = evaluator();
+        unsafe {
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, [1].as_ptr(), 1),
+                MaintenanceStatus::InvalidArgument
+            );
+            assert_eq!(last_error(), "1 days given for 2 interventions");
+            // I1 cannot start after day 3
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, [4, 1].as_ptr(), 2),
+                MaintenanceStatus::InvalidArgument
+            );
+            assert_eq!(last_error(), "intervention 0 cannot start on day 4");
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, [1, 6].as_ptr(), 2),
+                MaintenanceStatus::InvalidArgument
+            );
+            assert_eq!(maintenance_evaluator_start(evaluator, 0), 0);
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, [1, 0].as_ptr(), 2),
+                MaintenanceStatus::Ok
+            );
+            assert_eq!(maintenance_evaluator_start(evaluator, 0), 1);
+            assert_eq!(maintenance_evaluator_start(evaluator, 1), 0);
+            assert!(!maintenance_evaluator_is_feasible(evaluator));
+            maintenance_evaluator_free(evaluator);
+        }
+    }
+
+    #[test]
+    fn evaluate_then_set_start() {
+        let evaluator = evaluator();
+        let mut report = MaintenanceMove::default();
+        unsafe {
+            assert_eq!(
+                maintenance_evaluator_set_planning(evaluator, [1, 3].as_ptr(), 2),
+                MaintenanceStatus::Ok
+            );
+            assert!(maintenance_evaluator_is_feasible(evaluator));
+            let cost = maintenance_evaluator_cost(evaluator);
+            // I2 on day 2 exceeds the capacity with I1
+            assert_eq!(
+                maintenance_evaluator_evaluate_start(evaluator, 1, 2, &mut report),
+                MaintenanceStatus::Ok
+            );
+            assert!(report.latest_start && !report.max_workloads && !report.feasible);
+            assert_eq!(maintenance_evaluator_start(evaluator, 1), 3);
+            assert_eq!(maintenance_evaluator_cost(evaluator), cost);
+            assert_eq!(
+                maintenance_evaluator_set_start(evaluator, 1, 2, &mut report),
+                MaintenanceStatus::Ok
+            );
+            assert_eq!(maintenance_evaluator_start(evaluator, 1), 2);
+            assert_eq!(maintenance_evaluator_cost(evaluator), report.cost);
+            assert!((report.cost - cost - report.delta_cost).abs() < 1e-9);
+            assert!(!maintenance_evaluator_is_feasible(evaluator));
+            assert_eq!(
+                maintenance_evaluator_set_start(evaluator, 2, 1, ptr::null_mut()),
+                MaintenanceStatus::InvalidArgument
+            );
+            assert_eq!(last_error(), "no intervention 2, the instance has 2");
+            maintenance_evaluator_free(evaluator);
+        }
+    }
+}
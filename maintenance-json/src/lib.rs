@@ -3,6 +3,8 @@ extern crate serde;
 extern crate serde_json;
 
 use maintenance::*;
+/// Serialized with the `serialize` feature of `maintenance`
+pub use maintenance::{LoadTimings, MaintenanceSummary};
 use serde::Deserialize;
 use std::collections::btree_map::Entry;
 use std::collections::HashMap;
@@ -313,6 +315,61 @@ fn create_seasons_of_periods(
         .collect()
 }
 
+/// Planning in the text format of the challenge: one line `name day` by
+/// intervention, days starting at 1.
+pub struct ExportPlanning<'a> {
+    pub planning: &'a Planning,
+    pub mapping: &'a MaintenanceMapping,
+}
+
+impl<'a> fmt::Display for ExportPlanning<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (iid, day) in self.planning.interventions.iter() {
+            let intervention_name = &self.mapping.interventions[iid];
+            let day = *day + Day::new(1);
+            writeln!(f, "{} {}", intervention_name, day.get())?;
+        }
+        write!(f, "")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanningError {
+    /// Line (starting at 1) that is not `name day`
+    Syntax(usize),
+    UnknownIntervention(String),
+}
+
+/// Reads a planning written in the text format of the challenge.
+pub fn read_planning(
+    contents: &str,
+    mapping: &MaintenanceMapping,
+) -> std::result::Result<Planning, PlanningError> {
+    let interventions_codes: HashMap<&str, IID> = mapping
+        .interventions
+        .iter()
+        .map(|(iid, name)| (name.as_str(), *iid))
+        .collect();
+    let mut interventions = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let mut fields = line.split_whitespace();
+        let (name, day) = match (fields.next(), fields.next(), fields.next()) {
+            (None, _, _) => continue,
+            (Some(name), Some(day), None) => (name, day),
+            _ => return Err(PlanningError::Syntax(idx + 1)),
+        };
+        let iid = *interventions_codes
+            .get(name)
+            .ok_or_else(|| PlanningError::UnknownIntervention(name.to_owned()))?;
+        let day = match day.parse::<usize>() {
+            Ok(day) if day > 0 => Day::new(day - 1),
+            _ => return Err(PlanningError::Syntax(idx + 1)),
+        };
+        interventions.push((iid, day));
+    }
+    Ok(Planning { interventions })
+}
+
 #[derive(Debug)]
 pub enum SerdeMaintenanceError {
     IO(std::io::Error),
@@ -479,6 +536,7 @@ pub fn load_instance(maintenance: SerdeMaintenance) -> Option<MaintenanceOptimiz
             .set_resources(working_resources.resources)
             .set_exclusions(exclusions)
             .set_scenarios_number(scenarios_number)
+            .set_season_of_days(working_seasons.season_of_days)
             .build(),
         mapping: MaintenanceMapping {
             resources: working_resources
@@ -0,0 +1,413 @@
+//! Python bindings of the solver, built with `maturin develop`:
+//!
+//! ```python
+//! import pymaintenance
+//!
+//! instance = pymaintenance.Instance.load("A_01.json")
+//! solution = instance.solve("local-search", time_limit=60)
+//! evaluation = instance.evaluate(solution.planning)
+//! ```
+//!
+//! As in the instances and the solution files, the days start at 1.
+extern crate maintenance;
+extern crate maintenance_json;
+extern crate pyo3;
+
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use maintenance::search::{Improvement, SearchControl, SearchParameters, Solver};
+use maintenance::validation::{validate, Violation};
+use maintenance::{Day, MaintenanceOptimization, Planning, IID};
+use maintenance_json::{ExportPlanning, PlanningError};
+
+/// Time between two checks of Ctrl-C while a solver runs
+const SIGNALS_PERIOD: Duration = Duration::from_millis(100);
+
+#[pyclass]
+pub struct Intervention {
+    #[pyo3(get)]
+    id: usize,
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    latest_start: usize,
+    /// Duration by start day
+    #[pyo3(get)]
+    durations: Vec<usize>,
+    /// Names of the resources used
+    #[pyo3(get)]
+    resources: Vec<String>,
+}
+
+/// Same fields as the violations sent by the server.
+fn violation_dict<'py>(
+    py: Python<'py>,
+    instance: &MaintenanceOptimization,
+    violation: &Violation,
+) -> PyResult<&'py PyDict> {
+    let intervention = |iid: IID| instance.mapping.interventions[&iid].as_str();
+    let dict = PyDict::new(py);
+    match *violation {
+        Violation::Duplicated(iid) => {
+            dict.set_item("kind", "duplicated")?;
+            dict.set_item("intervention", intervention(iid))?;
+        }
+        Violation::Unplanned(iid) => {
+            dict.set_item("kind", "unplanned")?;
+            dict.set_item("intervention", intervention(iid))?;
+        }
+        Violation::LateStart { iid, day } => {
+            dict.set_item("kind", "late_start")?;
+            dict.set_item("intervention", intervention(iid))?;
+            dict.set_item("day", day.get() + 1)?;
+        }
+        Violation::Workload {
+            rid,
+            day,
+            workload,
+            min,
+            max,
+        } => {
+            dict.set_item("kind", "workload")?;
+            dict.set_item("resource", &instance.mapping.resources[&rid])?;
+            dict.set_item("day", day.get() + 1)?;
+            dict.set_item("workload", workload)?;
+            dict.set_item("min", min)?;
+            dict.set_item("max", max)?;
+        }
+        Violation::Exclusion {
+            first,
+            second,
+            period,
+        } => {
+            let (start, end) = period.days();
+            dict.set_item("kind", "exclusion")?;
+            dict.set_item("interventions", (intervention(first), intervention(second)))?;
+            dict.set_item("start", start.get() + 1)?;
+            dict.set_item("end", end.get() + 1)?;
+        }
+    }
+    Ok(dict)
+}
+
+/// Cost and violations of a planning, see `Instance.evaluate`.
+#[pyclass]
+pub struct Evaluation {
+    #[pyo3(get)]
+    cost: f64,
+    #[pyo3(get)]
+    obj1: f64,
+    #[pyo3(get)]
+    obj2: f64,
+    violations: Vec<Violation>,
+    instance: Arc<MaintenanceOptimization>,
+}
+
+#[pymethods]
+impl Evaluation {
+    #[getter]
+    fn feasible(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// One dict by violation, its `kind` is duplicated, unplanned,
+    /// late_start, workload or exclusion.
+    #[getter]
+    fn violations<'py>(&self, py: Python<'py>) -> PyResult<Vec<&'py PyDict>> {
+        self.violations
+            .iter()
+            .map(|violation| violation_dict(py, &self.instance, violation))
+            .collect()
+    }
+}
+
+fn improvement_dict<'py>(py: Python<'py>, improvement: &Improvement) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("elapsed", improvement.elapsed.as_secs_f64())?;
+    dict.set_item("cost", improvement.cost)?;
+    dict.set_item("obj1", improvement.obj1)?;
+    dict.set_item("obj2", improvement.obj2)?;
+    dict.set_item("feasible", improvement.feasible)?;
+    dict.set_item("iteration", improvement.iteration)?;
+    Ok(dict)
+}
+
+/// Best planning found by a solver, see `Instance.solve`.
+#[pyclass]
+pub struct Solution {
+    #[pyo3(get)]
+    cost: f64,
+    #[pyo3(get)]
+    obj1: f64,
+    #[pyo3(get)]
+    obj2: f64,
+    #[pyo3(get)]
+    feasible: bool,
+    #[pyo3(get)]
+    iterations: usize,
+    /// Start day of each intervention by name
+    #[pyo3(get)]
+    planning: HashMap<String, usize>,
+    improvements: Vec<Improvement>,
+    export: String,
+}
+
+#[pymethods]
+impl Solution {
+    /// One dict by improvement found, in order: elapsed (in seconds), cost,
+    /// obj1, obj2, feasible and iteration.
+    #[getter]
+    fn improvements<'py>(&self, py: Python<'py>) -> PyResult<Vec<&'py PyDict>> {
+        self.improvements
+            .iter()
+            .map(|improvement| improvement_dict(py, improvement))
+            .collect()
+    }
+
+    /// Planning in the text format of the challenge.
+    fn export(&self) -> String {
+        self.export.clone()
+    }
+}
+
+/// Instance of the challenge.
+#[pyclass]
+pub struct Instance {
+    instance: Arc<MaintenanceOptimization>,
+    /// Intervention of each name
+    interventions: HashMap<String, IID>,
+}
+
+impl Instance {
+    fn new(instance: MaintenanceOptimization) -> Self {
+        let interventions = instance
+            .mapping
+            .interventions
+            .iter()
+            .map(|(iid, name)| (name.clone(), *iid))
+            .collect();
+        Instance {
+            instance: Arc::new(instance),
+            interventions,
+        }
+    }
+
+    fn days(&self, planning: &Planning) -> HashMap<String, usize> {
+        planning
+            .interventions
+            .iter()
+            .map(|&(iid, day)| {
+                (
+                    self.instance.mapping.interventions[&iid].clone(),
+                    day.get() + 1,
+                )
+            })
+            .collect()
+    }
+
+    fn planning(&self, days: HashMap<String, usize>) -> PyResult<Planning> {
+        let mut interventions = days
+            .into_iter()
+            .map(|(name, day)| {
+                let iid = *self.interventions.get(&name).ok_or_else(|| {
+                    PyValueError::new_err(format!("unknown intervention `{}`", name))
+                })?;
+                if day == 0 {
+                    return Err(PyValueError::new_err(format!(
+                        "`{}` starts on day 0, the first day is 1",
+                        name
+                    )));
+                }
+                Ok((iid, Day::new(day - 1)))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        interventions.sort_by_key(|&(iid, _)| iid);
+        Ok(Planning { interventions })
+    }
+}
+
+#[pymethods]
+impl Instance {
+    /// Instance in the JSON format of the challenge.
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| PyIOError::new_err(format!("cannot read {}: {}", path, err)))?;
+        Instance::from_json(&contents)
+    }
+
+    #[staticmethod]
+    fn from_json(contents: &str) -> PyResult<Self> {
+        let m = maintenance_json::read_json(contents)
+            .map_err(|err| PyValueError::new_err(format!("invalid json: {:?}", err)))?;
+        // NOTE(vincent): the loader still panics on some malformed instances.
+        let instance = panic::catch_unwind(AssertUnwindSafe(|| maintenance_json::load_instance(m)))
+            .ok()
+            .flatten()
+            .ok_or_else(|| PyValueError::new_err("invalid instance"))?;
+        Ok(Instance::new(instance))
+    }
+
+    #[getter]
+    fn ndays(&self) -> usize {
+        self.instance.maintenance.ndays()
+    }
+
+    #[getter]
+    fn ninterventions(&self) -> usize {
+        self.instance.maintenance.ninterventions()
+    }
+
+    #[getter]
+    fn nresources(&self) -> usize {
+        self.instance.maintenance.nresources()
+    }
+
+    #[getter]
+    fn nscenarios(&self) -> usize {
+        self.instance.maintenance.nscenarios()
+    }
+
+    #[getter]
+    fn alpha(&self) -> f64 {
+        self.instance.maintenance.alpha()
+    }
+
+    #[getter]
+    fn quantile(&self) -> f64 {
+        self.instance.maintenance.quantile()
+    }
+
+    /// Interventions by id
+    fn interventions(&self) -> Vec<Intervention> {
+        let mapping = &self.instance.mapping;
+        self.instance
+            .maintenance
+            .interventions_with_ids()
+            .map(|(iid, intervention)| Intervention {
+                id: iid.get(),
+                name: mapping.interventions[&iid].clone(),
+                latest_start: intervention.latest_start().get() + 1,
+                durations: intervention
+                    .periods()
+                    .map(|period| period.duration().get())
+                    .collect(),
+                resources: intervention
+                    .resources()
+                    .iter()
+                    .map(|rid| mapping.resources[rid].clone())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Start day of each intervention of a planning in the text format of
+    /// the challenge.
+    fn read_planning(&self, contents: &str) -> PyResult<HashMap<String, usize>> {
+        let planning =
+            maintenance_json::read_planning(contents, &self.instance.mapping).map_err(|err| {
+                match err {
+                    PlanningError::Syntax(line) => {
+                        PyValueError::new_err(format!("line {} is not `name day`", line))
+                    }
+                    PlanningError::UnknownIntervention(name) => {
+                        PyValueError::new_err(format!("unknown intervention `{}`", name))
+                    }
+                }
+            })?;
+        Ok(self.days(&planning))
+    }
+
+    /// `planning` gives the start day of each intervention by name.
+    fn evaluate(&self, planning: HashMap<String, usize>) -> PyResult<Evaluation> {
+        let planning = self.planning(planning)?;
+        let validation = validate(&self.instance.maintenance, &planning);
+        Ok(Evaluation {
+            cost: validation.report.cost,
+            obj1: validation.report.obj1,
+            obj2: validation.report.obj2,
+            violations: validation.violations,
+            instance: Arc::clone(&self.instance),
+        })
+    }
+
+    /// Runs `solver`, greedy or local-search, until the time limit in
+    /// seconds. Ctrl-C stops the search.
+    #[pyo3(signature = (solver = "local-search", time_limit = 60.0, seed = 0))]
+    fn solve(&self, py: Python, solver: &str, time_limit: f64, seed: u64) -> PyResult<Solution> {
+        let solver = solver.parse::<Solver>().map_err(PyValueError::new_err)?;
+        if !time_limit.is_finite() || time_limit < 0.0 {
+            return Err(PyValueError::new_err("invalid time limit"));
+        }
+        let parameters = SearchParameters {
+            time_limit: Duration::from_secs_f64(time_limit),
+            seed,
+        };
+        let control = Arc::new(SearchControl::default());
+        let (sender, receiver) = channel();
+        {
+            let instance = Arc::clone(&self.instance);
+            let control = Arc::clone(&control);
+            thread::spawn(move || {
+                let mut improvements = Vec::new();
+                let planning = solver.run(
+                    &instance.maintenance,
+                    &parameters,
+                    &control,
+                    |improvement, _| improvements.push(improvement.clone()),
+                );
+                let _ = sender.send((planning, improvements));
+            });
+        }
+        // NOTE(vincent): the receiver is not `Sync`, it cannot be borrowed
+        // without the GIL otherwise.
+        let receiver = Mutex::new(receiver);
+        let (planning, improvements) = loop {
+            match py.allow_threads(|| receiver.lock().expect("lock").recv_timeout(SIGNALS_PERIOD)) {
+                Ok(solved) => break solved,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Err(err) = py.check_signals() {
+                        control.stop.store(true, Ordering::Relaxed);
+                        return Err(err);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(PyRuntimeError::new_err("the solver panicked"))
+                }
+            }
+        };
+        let validation = validate(&self.instance.maintenance, &planning);
+        Ok(Solution {
+            cost: validation.report.cost,
+            obj1: validation.report.obj1,
+            obj2: validation.report.obj2,
+            feasible: validation.is_feasible(),
+            iterations: control.iterations(),
+            planning: self.days(&planning),
+            improvements,
+            export: ExportPlanning {
+                planning: &planning,
+                mapping: &self.instance.mapping,
+            }
+            .to_string(),
+        })
+    }
+}
+
+#[pymodule]
+fn pymaintenance(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Instance>()?;
+    m.add_class::<Intervention>()?;
+    m.add_class::<Evaluation>()?;
+    m.add_class::<Solution>()?;
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! Data behind the charts of the best planning of a job, sent as JSON or as
+//! a Cap'n Proto message.
+use rocket::http::{Accept, ContentType, Status};
+use rocket::response::Response;
+use serde::Serialize;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use maintenance::validation::{resources_usage, risk_profile, ResourceUsage};
+use maintenance::{Day, MaintenanceOptimization, Planning};
+use maintenance_capnproto::planning::{ResourcesUsageBuilder, RiskProfileBuilder};
+
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::store::{JobId, JobStore};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DataFormat {
+    Json,
+    Capnp,
+}
+
+impl DataFormat {
+    /// JSON unless a Cap'n Proto message is preferred.
+    fn from_accept(accept: Option<&Accept>) -> Self {
+        match accept.map(|accept| accept.preferred().media_type()) {
+            Some(media_type)
+                if media_type.top() == "application"
+                    && (media_type.sub() == "octet-stream" || media_type.sub() == "x-capnp") =>
+            {
+                DataFormat::Capnp
+            }
+            _ => DataFormat::Json,
+        }
+    }
+}
+
+fn respond<T, F, E>(format: DataFormat, view: &T, capnp: F) -> Result<Response<'static>, ApiError>
+where
+    T: Serialize,
+    F: FnOnce() -> Result<Vec<u8>, E>,
+    E: std::fmt::Display,
+{
+    let (content_type, data) = match format {
+        DataFormat::Json => (
+            ContentType::JSON,
+            serde_json::to_vec(view).map_err(|err| ApiError::internal(err.to_string()))?,
+        ),
+        DataFormat::Capnp => (
+            ContentType::Binary,
+            capnp().map_err(|err| ApiError::internal(err.to_string()))?,
+        ),
+    };
+    Ok(Response::build()
+        .header(content_type)
+        .sized_body(Cursor::new(data))
+        .finalize())
+}
+
+fn best_planning(
+    store: &JobStore,
+    uuid: &JobId,
+    caller: &Caller,
+) -> Result<(Arc<MaintenanceOptimization>, Planning), ApiError> {
+    store.authorize(&uuid.0, caller)?;
+    let instance = store.instance(&uuid.0).ok_or_else(ApiError::unknown_job)?;
+    let (_, planning) = store
+        .run(&uuid.0)
+        .and_then(|run| run.best())
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_planning", "no planning found yet"))?;
+    Ok((instance, planning))
+}
+
+#[derive(Serialize)]
+struct ResourceUsageView<'a> {
+    name: &'a str,
+    workloads: &'a [f64],
+    min: &'a [f64],
+    max: &'a [f64],
+    /// as in the instances: the first day is 1
+    violations: Vec<usize>,
+}
+
+impl<'a> ResourceUsageView<'a> {
+    fn new(instance: &'a MaintenanceOptimization, usage: &'a ResourceUsage) -> Self {
+        ResourceUsageView {
+            name: instance
+                .mapping
+                .resources
+                .get(&usage.rid)
+                .map(String::as_str)
+                .unwrap_or_default(),
+            workloads: &usage.workloads,
+            min: &usage.min,
+            max: &usage.max,
+            violations: usage.violations.iter().map(|day| day.get() + 1).collect(),
+        }
+    }
+}
+
+/// Workloads of each resource by day in the best planning, against the
+/// bounds of the resource.
+#[get("/optim/<uuid>/workloads")]
+pub fn optim_workloads(
+    uuid: JobId,
+    accept: Option<&Accept>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Response<'static>, ApiError> {
+    let (instance, planning) = best_planning(&store, &uuid, &caller)?;
+    let usage = resources_usage(&instance.maintenance, &planning);
+    let view: Vec<_> = usage
+        .iter()
+        .map(|usage| ResourceUsageView::new(&instance, usage))
+        .collect();
+    respond(DataFormat::from_accept(accept), &view, || {
+        ResourcesUsageBuilder::from_usage(&usage).bytes()
+    })
+}
+
+#[derive(Serialize)]
+struct RiskProfileView<'a> {
+    obj1: f64,
+    obj2: f64,
+    cost: f64,
+    mean_risks: &'a [f64],
+    quantile_risks: &'a [f64],
+    excess_risks: &'a [f64],
+    /// as in the instances: the first day is 1
+    day: Option<usize>,
+    /// risk of each scenario of the day
+    scenarios: &'a [f64],
+}
+
+/// Mean, quantile and excess risks of each day in the best planning and, if
+/// a day is given, the risk of each of its scenarios.
+#[get("/optim/<uuid>/risks?<day>")]
+pub fn optim_risks(
+    uuid: JobId,
+    day: Option<usize>,
+    accept: Option<&Accept>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Response<'static>, ApiError> {
+    let (instance, planning) = best_planning(&store, &uuid, &caller)?;
+    let ndays = instance.maintenance.ndays();
+    let day = match day {
+        Some(day) if day >= 1 && day <= ndays => Some(Day::new(day - 1)),
+        Some(_) => {
+            return Err(ApiError::bad_request(
+                "invalid_day",
+                format!("day must be between 1 and {}", ndays),
+            ))
+        }
+        None => None,
+    };
+    let profile = risk_profile(&instance.maintenance, &planning, day);
+    let report = &profile.report;
+    let view = RiskProfileView {
+        obj1: report.obj1,
+        obj2: report.obj2,
+        cost: report.cost,
+        mean_risks: &report.mean_risks,
+        quantile_risks: &report.quantile_risks,
+        excess_risks: &report.excess_risks,
+        day: profile.day.map(|day| day.get() + 1),
+        scenarios: &profile.scenarios,
+    };
+    respond(DataFormat::from_accept(accept), &view, || {
+        RiskProfileBuilder::from_profile(&profile).bytes()
+    })
+}
@@ -0,0 +1,170 @@
+//! API keys of the configuration, a job belongs to the user of the key that
+//! uploaded it.
+//!
+//! Without any key in the configuration the server only starts when it is
+//! `open`: every caller is then an admin, named by the `X-Maintenance-User`
+//! header or its address.
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::{Outcome, State};
+
+use crate::config::ServerConfig;
+use crate::error::ApiError;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Header naming the user when the server has no key.
+pub const USER_HEADER: &str = "X-Maintenance-User";
+
+/// Authenticated client of a request.
+#[derive(Clone, Debug)]
+pub struct Caller {
+    pub user: String,
+    pub admin: bool,
+}
+
+impl Caller {
+    pub fn can_access(&self, owner: &str) -> bool {
+        self.admin || self.user == owner
+    }
+
+    /// Owner of one of the keys of the configuration.
+    pub fn with_key(config: &ServerConfig, key: &str) -> Option<Self> {
+        config
+            .api_keys
+            .iter()
+            .find(|api_key| same_key(&api_key.key, key))
+            .map(|api_key| Caller {
+                user: api_key.user.clone(),
+                admin: api_key.admin,
+            })
+    }
+
+    /// Priority of a job of the caller: only the admins go above the
+    /// `max_user_priority` of the configuration.
+    pub fn priority(&self, requested: i32, config: &ServerConfig) -> i32 {
+        if self.admin {
+            requested
+        } else {
+            requested.min(config.max_user_priority)
+        }
+    }
+
+    /// Forbids the jobs of the other users.
+    pub fn check_access(&self, owner: &str) -> Result<(), ApiError> {
+        if self.can_access(owner) {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden())
+        }
+    }
+}
+
+// NOTE(vincent): never in the query string, the URLs end up in the logs
+// and in the history of the browsers.
+fn sent_key<'r>(request: &'r Request) -> Option<&'r str> {
+    let headers = request.headers();
+    headers
+        .get_one("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get_one(API_KEY_HEADER))
+        .map(str::trim)
+}
+
+/// Compares in a time independent of where the keys differ.
+fn same_key(left: &str, right: &str) -> bool {
+    left.len() == right.len()
+        && left
+            .bytes()
+            .zip(right.bytes())
+            .fold(0, |diff, (l, r)| diff | (l ^ r))
+            == 0
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Caller {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, ()> {
+        let config = match request.guard::<State<ServerConfig>>() {
+            Outcome::Success(config) => config,
+            _ => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+        if config.api_keys.is_empty() && config.open {
+            let user = request
+                .headers()
+                .get_one(USER_HEADER)
+                .map(str::to_owned)
+                .or_else(|| request.client_ip().map(|ip| ip.to_string()))
+                .unwrap_or_else(|| "anonymous".to_owned());
+            return Outcome::Success(Caller { user, admin: true });
+        }
+        match sent_key(request).and_then(|key| Caller::with_key(&config, key)) {
+            Some(caller) => Outcome::Success(caller),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ApiKey;
+
+    fn config() -> ServerConfig {
+        ServerConfig {
+            api_keys: vec![
+                "alice:secret1".parse::<ApiKey>().unwrap(),
+                "root:secret2:admin".parse::<ApiKey>().unwrap(),
+            ],
+            ..ServerConfig::default()
+        }
+    }
+
+    #[test]
+    fn callers_of_keys() {
+        let config = config();
+        let alice = Caller::with_key(&config, "secret1").unwrap();
+        assert_eq!(alice.user, "alice");
+        assert!(!alice.admin);
+        let root = Caller::with_key(&config, "secret2").unwrap();
+        assert_eq!(root.user, "root");
+        assert!(root.admin);
+        for key in &["", "secret", "secret12", "Secret1", "alice"] {
+            assert!(Caller::with_key(&config, key).is_none(), "{}", key);
+        }
+        assert!(Caller::with_key(&ServerConfig::default(), "").is_none());
+    }
+
+    #[test]
+    fn access_of_the_owner() {
+        let config = config();
+        let alice = Caller::with_key(&config, "secret1").unwrap();
+        let root = Caller::with_key(&config, "secret2").unwrap();
+        assert!(alice.can_access("alice"));
+        assert!(!alice.can_access("bob"));
+        assert!(alice.check_access("bob").is_err());
+        assert!(root.can_access("bob"));
+        assert!(root.check_access("bob").is_ok());
+    }
+
+    #[test]
+    fn priority_of_the_users() {
+        let config = ServerConfig {
+            max_user_priority: 2,
+            ..config()
+        };
+        let alice = Caller::with_key(&config, "secret1").unwrap();
+        let root = Caller::with_key(&config, "secret2").unwrap();
+        assert_eq!(alice.priority(10, &config), 2);
+        assert_eq!(alice.priority(-1, &config), -1);
+        assert_eq!(root.priority(10, &config), 10);
+    }
+
+    #[test]
+    fn keys_compared_whole() {
+        assert!(same_key("secret", "secret"));
+        assert!(!same_key("secret", "secreT"));
+        assert!(!same_key("secret", "secre"));
+        assert!(!same_key("", "secret"));
+    }
+}
@@ -0,0 +1,316 @@
+//! Configuration of the server, read from a TOML file and overridden by
+//! `MAINTENANCE_*` environment variables.
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Used when `MAINTENANCE_CONFIG` is not set; a missing file keeps the defaults.
+pub const DEFAULT_CONFIG_PATH: &str = "maintenance-server.toml";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    /// Rocket workers, each open progress stream holds one of them
+    pub workers: u16,
+    pub cors_origins: Vec<String>,
+    /// in bytes
+    pub instance_size_limit: u64,
+    /// in bytes
+    pub solution_size_limit: u64,
+    /// Threads reading the uploaded instances
+    pub reading_workers: usize,
+    /// Uploaded instances waiting to be read
+    pub reading_queue_length: usize,
+    pub solver_threads: usize,
+    /// Solver runs waiting for a thread
+    pub solver_queue_length: usize,
+    /// Longest run a client can ask for, in seconds
+    pub max_time_limit: u64,
+    /// Highest priority of the jobs of the users, only the admins go above
+    pub max_user_priority: i32,
+    /// Uploaded files are kept there
+    pub storage_dir: PathBuf,
+    /// `postgres://...` or the path of a SQLite database, defaults to
+    /// `maintenance.db` in the storage directory
+    pub database_url: Option<String>,
+    /// Keys accepted by the server
+    pub api_keys: Vec<ApiKey>,
+    /// Without any key, the server only starts when it is open to anyone
+    pub open: bool,
+    /// Cap'n Proto RPC front-end, `host:port` or `unix:<path>`, disabled
+    /// when not set
+    pub rpc_address: Option<String>,
+}
+
+/// Key sent by the clients as `Authorization: Bearer <key>` or `X-Api-Key`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    pub key: String,
+    /// Owner of the instances uploaded with the key
+    pub user: String,
+    /// Sees and cancels the jobs of every user
+    #[serde(default)]
+    pub admin: bool,
+}
+
+impl FromStr for ApiKey {
+    type Err = ();
+
+    /// `user:key`, or `user:key:admin`
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut parts = s.trim().split(':');
+        let user = parts.next().filter(|user| !user.is_empty()).ok_or(())?;
+        let key = parts.next().filter(|key| !key.is_empty()).ok_or(())?;
+        let admin = match parts.next() {
+            None => false,
+            Some("admin") => true,
+            Some(_) => return Err(()),
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(ApiKey {
+            key: key.to_owned(),
+            user: user.to_owned(),
+            admin,
+        })
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "0.0.0.0".to_owned(),
+            port: 8000,
+            workers: 16,
+            cors_origins: vec![
+                "http://localhost:8080".to_owned(),
+                "http://127.0.0.1:8080".to_owned(),
+                "http://localhost:8000".to_owned(),
+                "http://127.0.0.1:8000".to_owned(),
+            ],
+            instance_size_limit: 750 * 1024 * 1024,
+            solution_size_limit: 10 * 1024 * 1024,
+            reading_workers: 2,
+            reading_queue_length: 100,
+            solver_threads: 2,
+            solver_queue_length: 100,
+            max_time_limit: 3600,
+            max_user_priority: 0,
+            storage_dir: PathBuf::from("storage"),
+            database_url: None,
+            api_keys: Vec::new(),
+            open: false,
+            rpc_address: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IO(PathBuf, std::io::Error),
+    TOML(PathBuf, toml::de::Error),
+    Env(&'static str, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::IO(path, err) => write!(f, "cannot read {:?}: {}", path, err),
+            ConfigError::TOML(path, err) => write!(f, "invalid configuration {:?}: {}", path, err),
+            ConfigError::Env(name, value) => write!(f, "invalid value for {}: {:?}", name, value),
+        }
+    }
+}
+
+fn override_with<T: FromStr>(value: &mut T, name: &'static str) -> Result<(), ConfigError> {
+    if let Ok(var) = std::env::var(name) {
+        *value = var.parse().map_err(|_| ConfigError::Env(name, var))?;
+    }
+    Ok(())
+}
+
+impl ServerConfig {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|err| ConfigError::IO(path.to_owned(), err))?;
+        toml::from_str(&contents).map_err(|err| ConfigError::TOML(path.to_owned(), err))
+    }
+
+    /// File given by `MAINTENANCE_CONFIG` (or the default one when it
+    /// exists), then the environment overrides.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match std::env::var("MAINTENANCE_CONFIG") {
+            Ok(path) => ServerConfig::from_file(path)?,
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                ServerConfig::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            Err(_) => ServerConfig::default(),
+        };
+        config.override_from_env()?;
+        Ok(config)
+    }
+
+    fn override_from_env(&mut self) -> Result<(), ConfigError> {
+        override_with(&mut self.address, "MAINTENANCE_ADDRESS")?;
+        override_with(&mut self.port, "MAINTENANCE_PORT")?;
+        override_with(&mut self.workers, "MAINTENANCE_WORKERS")?;
+        if let Ok(origins) = std::env::var("MAINTENANCE_CORS_ORIGINS") {
+            self.cors_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
+        override_with(
+            &mut self.instance_size_limit,
+            "MAINTENANCE_INSTANCE_SIZE_LIMIT",
+        )?;
+        override_with(
+            &mut self.solution_size_limit,
+            "MAINTENANCE_SOLUTION_SIZE_LIMIT",
+        )?;
+        override_with(&mut self.reading_workers, "MAINTENANCE_READING_WORKERS")?;
+        override_with(
+            &mut self.reading_queue_length,
+            "MAINTENANCE_READING_QUEUE_LENGTH",
+        )?;
+        override_with(&mut self.solver_threads, "MAINTENANCE_SOLVER_THREADS")?;
+        override_with(
+            &mut self.solver_queue_length,
+            "MAINTENANCE_SOLVER_QUEUE_LENGTH",
+        )?;
+        override_with(&mut self.max_time_limit, "MAINTENANCE_MAX_TIME_LIMIT")?;
+        override_with(&mut self.max_user_priority, "MAINTENANCE_MAX_USER_PRIORITY")?;
+        override_with(&mut self.storage_dir, "MAINTENANCE_STORAGE_DIR")?;
+        if let Ok(url) = std::env::var("MAINTENANCE_DATABASE_URL") {
+            self.database_url = Some(url);
+        }
+        if let Ok(keys) = std::env::var("MAINTENANCE_API_KEYS") {
+            self.api_keys = keys
+                .split(',')
+                .filter(|key| !key.trim().is_empty())
+                .map(|key| key.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| ConfigError::Env("MAINTENANCE_API_KEYS", keys.clone()))?;
+        }
+        override_with(&mut self.open, "MAINTENANCE_OPEN")?;
+        if let Ok(address) = std::env::var("MAINTENANCE_RPC_ADDRESS") {
+            self.rpc_address = Some(address).filter(|address| !address.is_empty());
+        }
+        Ok(())
+    }
+
+    /// Temporary files of the multipart uploads
+    pub fn upload_dir(&self) -> PathBuf {
+        self.storage_dir.join("uploads")
+    }
+
+    pub fn database_url(&self) -> String {
+        match self.database_url {
+            Some(ref url) => url.clone(),
+            None => self
+                .storage_dir
+                .join("maintenance.db")
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_is_the_default() {
+        let example: ServerConfig =
+            toml::from_str(include_str!("../../maintenance-server.example.toml")).unwrap();
+        let default = ServerConfig::default();
+        assert_eq!(example.address, default.address);
+        assert_eq!(example.port, default.port);
+        assert_eq!(example.cors_origins, default.cors_origins);
+        assert_eq!(example.instance_size_limit, default.instance_size_limit);
+        assert_eq!(example.solver_queue_length, default.solver_queue_length);
+        assert_eq!(example.max_time_limit, default.max_time_limit);
+        assert_eq!(example.max_user_priority, default.max_user_priority);
+        assert_eq!(example.storage_dir, default.storage_dir);
+        assert!(example.api_keys.is_empty());
+        assert!(!example.open);
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert!(toml::from_str::<ServerConfig>("port = 9000").is_ok());
+        assert!(toml::from_str::<ServerConfig>("prot = 9000").is_err());
+        let keys = "[[api_keys]]\nkey = \"secret\"\nuser = \"alice\"\nadmin = false\nrole = 1";
+        assert!(toml::from_str::<ServerConfig>(keys).is_err());
+    }
+
+    #[test]
+    fn api_keys_from_str() {
+        let key: ApiKey = "alice:secret".parse().unwrap();
+        assert_eq!(
+            (key.user.as_str(), key.key.as_str(), key.admin),
+            ("alice", "secret", false)
+        );
+        let key: ApiKey = " root:secret:admin ".parse().unwrap();
+        assert_eq!(
+            (key.user.as_str(), key.key.as_str(), key.admin),
+            ("root", "secret", true)
+        );
+        for key in &[
+            "",
+            "alice",
+            "alice:",
+            ":secret",
+            "alice:secret:root",
+            "a:b:admin:c",
+        ] {
+            assert!(key.parse::<ApiKey>().is_err(), "{}", key);
+        }
+    }
+
+    // NOTE(vincent): the only test reading the environment, the variables
+    // are shared by the threads of the tests.
+    #[test]
+    fn environment_overrides() {
+        let mut config = ServerConfig::default();
+        std::env::set_var("MAINTENANCE_PORT", "9000");
+        std::env::set_var("MAINTENANCE_CORS_ORIGINS", "http://a, ,http://b");
+        std::env::set_var("MAINTENANCE_MAX_TIME_LIMIT", "60");
+        std::env::set_var("MAINTENANCE_API_KEYS", "alice:secret1,root:secret2:admin,");
+        std::env::set_var("MAINTENANCE_OPEN", "true");
+        std::env::set_var("MAINTENANCE_RPC_ADDRESS", "");
+        config.override_from_env().unwrap();
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.cors_origins, vec!["http://a", "http://b"]);
+        assert_eq!(config.max_time_limit, 60);
+        assert_eq!(config.api_keys.len(), 2);
+        assert!(config.api_keys[1].admin);
+        assert!(config.open);
+        assert_eq!(config.rpc_address, None);
+
+        std::env::set_var("MAINTENANCE_PORT", "port");
+        assert!(matches!(
+            config.override_from_env(),
+            Err(ConfigError::Env("MAINTENANCE_PORT", _))
+        ));
+        for name in &[
+            "MAINTENANCE_PORT",
+            "MAINTENANCE_CORS_ORIGINS",
+            "MAINTENANCE_MAX_TIME_LIMIT",
+            "MAINTENANCE_API_KEYS",
+            "MAINTENANCE_OPEN",
+            "MAINTENANCE_RPC_ADDRESS",
+        ] {
+            std::env::remove_var(name);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Errors sent back to the clients as `{"code": ..., "message": ...}`.
+//!
+//! The codes are stable: the front-ends match on them, the messages are
+//! only meant to be read.
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::Catcher;
+use rocket_contrib::json::Json;
+use rocket_multipart_form_data::MultipartFormDataError;
+use serde::Serialize;
+use std::any::Any;
+
+use crate::store::JobStatus;
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: Status,
+    code: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    message: &'a str,
+}
+
+impl ApiError {
+    pub fn new<M: Into<String>>(status: Status, code: &'static str, message: M) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request<M: Into<String>>(code: &'static str, message: M) -> Self {
+        ApiError::new(Status::BadRequest, code, message)
+    }
+
+    pub fn too_large<M: Into<String>>(message: M) -> Self {
+        ApiError::new(Status::PayloadTooLarge, "file_too_large", message)
+    }
+
+    pub fn invalid_instance<M: Into<String>>(message: M) -> Self {
+        ApiError::new(Status::UnprocessableEntity, "invalid_instance", message)
+    }
+
+    pub fn internal<M: Into<String>>(message: M) -> Self {
+        ApiError::new(Status::InternalServerError, "internal_error", message)
+    }
+
+    pub fn unknown_job() -> Self {
+        ApiError::new(Status::NotFound, "unknown_job", "no job with this uuid")
+    }
+
+    pub fn unauthorized() -> Self {
+        ApiError::new(
+            Status::Unauthorized,
+            "unauthorized",
+            "missing or unknown API key",
+        )
+    }
+
+    pub fn forbidden() -> Self {
+        ApiError::new(
+            Status::Forbidden,
+            "forbidden",
+            "this job belongs to another user",
+        )
+    }
+
+    pub fn not_ready(status: &JobStatus) -> Self {
+        ApiError::new(
+            Status::Conflict,
+            "job_not_ready",
+            format!("job is {}", status.name()),
+        )
+    }
+
+    pub fn queue_full() -> Self {
+        ApiError::new(
+            Status::ServiceUnavailable,
+            "queue_full",
+            "too many jobs queued, retry later",
+        )
+    }
+
+    pub fn missing_file(field: &str) -> Self {
+        ApiError::bad_request("missing_file", format!("no `{}` file in the form", field))
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<MultipartFormDataError> for ApiError {
+    fn from(error: MultipartFormDataError) -> Self {
+        match error {
+            MultipartFormDataError::DataTooLargeError(field) => {
+                ApiError::too_large(format!("`{}` is too large", field))
+            }
+            error => ApiError::bad_request("invalid_form", format!("{:?}", error)),
+        }
+    }
+}
+
+impl<'r> Responder<'r> for ApiError {
+    fn respond_to(self, request: &Request) -> response::Result<'r> {
+        let body = Json(ErrorBody {
+            code: self.code,
+            message: &self.message,
+        });
+        response::Response::build_from(body.respond_to(request)?)
+            .status(self.status)
+            .ok()
+    }
+}
+
+/// Message of a panic caught in a worker thread.
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => (*message).to_owned(),
+            Err(_) => "unknown panic".to_owned(),
+        },
+    }
+}
+
+#[catch(400)]
+fn bad_request() -> ApiError {
+    ApiError::bad_request("bad_request", "malformed request")
+}
+
+#[catch(401)]
+fn unauthorized() -> ApiError {
+    ApiError::unauthorized()
+}
+
+#[catch(404)]
+fn not_found() -> ApiError {
+    ApiError::new(Status::NotFound, "not_found", "no such resource")
+}
+
+#[catch(413)]
+fn payload_too_large() -> ApiError {
+    ApiError::too_large("request body is too large")
+}
+
+#[catch(422)]
+fn unprocessable_entity() -> ApiError {
+    ApiError::new(
+        Status::UnprocessableEntity,
+        "invalid_body",
+        "request body could not be read",
+    )
+}
+
+#[catch(500)]
+fn internal_error() -> ApiError {
+    ApiError::internal("internal server error")
+}
+
+/// Errors raised by Rocket itself, before reaching the handlers.
+pub fn catchers() -> Vec<Catcher> {
+    catchers![
+        bad_request,
+        unauthorized,
+        not_found,
+        payload_too_large,
+        unprocessable_entity,
+        internal_error
+    ]
+}
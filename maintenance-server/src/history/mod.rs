@@ -0,0 +1,212 @@
+//! Run history kept in the database, to compare the solvers across runs.
+use rocket::http::Status;
+use rocket_contrib::json::Json;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use maintenance::search::Improvement;
+use maintenance_db::{
+    Database, DbError, ImprovementRow, InstanceRow, PlanningRow, RunResult, RunRow,
+};
+use uuid::Uuid;
+
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::planning::PlanningView;
+use crate::store::{JobId, JobStore};
+
+#[derive(Serialize)]
+pub struct RunResultView {
+    id: String,
+    solver: String,
+    /// in seconds
+    time_limit: i64,
+    seed: i64,
+    code_version: String,
+    status: String,
+    /// seconds since the unix epoch
+    started_on: i64,
+    finished_on: Option<i64>,
+    cost: Option<f64>,
+    feasible: Option<bool>,
+}
+
+impl RunResultView {
+    fn new(run: RunRow, planning: Option<&PlanningRow>) -> Self {
+        RunResultView {
+            id: run.id,
+            solver: run.solver,
+            time_limit: run.time_limit,
+            seed: run.seed,
+            code_version: run.code_version,
+            status: run.status,
+            started_on: run.started_on,
+            finished_on: run.finished_on,
+            cost: planning.map(|planning| planning.cost),
+            feasible: planning.map(|planning| planning.feasible),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct InstanceView {
+    id: String,
+    name: String,
+    owner: String,
+    ndays: i32,
+    nresources: i32,
+    ninterventions: i32,
+    nscenarios: i32,
+    /// seconds since the unix epoch
+    created_on: i64,
+}
+
+impl From<InstanceRow> for InstanceView {
+    fn from(row: InstanceRow) -> Self {
+        InstanceView {
+            id: row.id,
+            name: row.name,
+            owner: row.owner,
+            ndays: row.ndays,
+            nresources: row.nresources,
+            ninterventions: row.ninterventions,
+            nscenarios: row.nscenarios,
+            created_on: row.created_on,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ImprovementView {
+    iteration: i64,
+    /// in seconds
+    elapsed: f64,
+    cost: f64,
+    obj1: f64,
+    obj2: f64,
+    feasible: bool,
+}
+
+impl From<ImprovementRow> for ImprovementView {
+    fn from(row: ImprovementRow) -> Self {
+        ImprovementView {
+            iteration: row.iteration,
+            elapsed: row.elapsed,
+            cost: row.cost,
+            obj1: row.obj1,
+            obj2: row.obj2,
+            feasible: row.feasible,
+        }
+    }
+}
+
+fn database(store: &JobStore) -> Result<&Database, ApiError> {
+    store.database().ok_or_else(|| {
+        ApiError::new(
+            Status::NotFound,
+            "no_history",
+            "the runs are not recorded on this server",
+        )
+    })
+}
+
+fn query_error(err: DbError) -> ApiError {
+    ApiError::internal(err.to_string())
+}
+
+/// Stored instances of the caller, of every user for an admin, newest first.
+#[get("/instances")]
+pub fn instances(
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<Vec<InstanceView>>, ApiError> {
+    let owner = if caller.admin {
+        None
+    } else {
+        Some(caller.user.as_str())
+    };
+    let rows = database(&store)?
+        .instance_rows(owner)
+        .map_err(query_error)?;
+    Ok(Json(rows.into_iter().map(InstanceView::from).collect()))
+}
+
+/// Runs on an instance, of every solver or of `solver`, oldest first.
+#[get("/instances/<uuid>/runs?<solver>")]
+pub fn instance_runs(
+    uuid: JobId,
+    solver: Option<String>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<Vec<RunResultView>>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    let runs = database(&store)?
+        .runs(&uuid.0, solver.as_ref().map(String::as_str))
+        .map_err(query_error)?;
+    Ok(Json(
+        runs.into_iter()
+            .map(|RunResult { run, planning }| RunResultView::new(run, planning.as_ref()))
+            .collect(),
+    ))
+}
+
+#[derive(Serialize)]
+struct BestPlanningView<'a> {
+    run: RunResultView,
+    planning: PlanningView<'a>,
+}
+
+/// Best planning known for an instance over all its runs.
+#[get("/instances/<uuid>/best")]
+pub fn instance_best(
+    uuid: JobId,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    let instance = store.instance(&uuid.0).ok_or_else(ApiError::unknown_job)?;
+    let (run, row, planning) = database(&store)?
+        .best_planning(&uuid.0)
+        .map_err(query_error)?
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_planning", "no planning found yet"))?;
+    let improvement = Improvement {
+        cost: row.cost,
+        obj1: row.obj1,
+        obj2: row.obj2,
+        feasible: row.feasible,
+        elapsed: Duration::from_secs_f64(row.elapsed),
+        iteration: row.iteration as usize,
+    };
+    let view = BestPlanningView {
+        run: RunResultView::new(run, Some(&row)),
+        planning: PlanningView::new(&instance, &improvement, &planning),
+    };
+    serde_json::to_value(&view)
+        .map(Json)
+        .map_err(|err| ApiError::internal(err.to_string()))
+}
+
+/// Cost of a run over time.
+#[get("/runs/<uuid>/improvements")]
+pub fn run_improvements(
+    uuid: JobId,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<Vec<ImprovementView>>, ApiError> {
+    let database = database(&store)?;
+    let run = database
+        .run(&uuid.0)
+        .map_err(query_error)?
+        .ok_or_else(|| ApiError::new(Status::NotFound, "unknown_run", "no run with this uuid"))?;
+    let instance_id =
+        Uuid::parse_str(&run.instance_id).map_err(|err| ApiError::internal(err.to_string()))?;
+    store.authorize(&instance_id, &caller)?;
+    let improvements = database.improvements(&uuid.0).map_err(query_error)?;
+    Ok(Json(
+        improvements
+            .into_iter()
+            .map(ImprovementView::from)
+            .collect(),
+    ))
+}
@@ -3,16 +3,27 @@
 
 #[macro_use]
 extern crate rocket;
+extern crate capnp;
+extern crate capnp_rpc;
+extern crate futures;
 extern crate maintenance;
 extern crate maintenance_capnproto;
+extern crate maintenance_db;
 extern crate maintenance_json;
+extern crate rocket_contrib;
 extern crate rocket_cors;
 extern crate rocket_multipart_form_data;
 extern crate serde;
 extern crate serde_json;
+extern crate sha2;
+extern crate tokio;
+extern crate tokio_util;
+extern crate toml;
 extern crate uuid;
 
-use maintenance::io::reader;
+use crate::config::ServerConfig;
+use crate::error::ApiError;
+use maintenance::MaintenanceOptimization;
 use maintenance_json::*;
 
 use std::collections::HashMap;
@@ -35,6 +46,7 @@ use rocket::response::content;
 use rocket::response::status;
 use rocket::response::Response; // 1.
 use rocket::Data;
+use rocket_contrib::json::Json;
 use rocket_cors::{
     AllowedHeaders,
     AllowedOrigins,
@@ -48,7 +60,19 @@ use rocket_multipart_form_data::{
 
 use std::path::{Path, PathBuf};
 
+pub mod analysis;
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod history;
+pub mod metrics;
 pub mod optim;
+pub mod planning;
+pub mod progress;
+pub mod rpc;
+pub mod scheduler;
+pub mod store;
+pub mod validation;
 
 #[get("/test")]
 fn index() -> status::Accepted<content::Json<&'static str>> {
@@ -56,103 +80,79 @@ fn index() -> status::Accepted<content::Json<&'static str>> {
     status::Accepted(Some(response::content::Json(content)))
 }
 
-#[post("/optim", data = "<data>")]
-fn optim<'a, 'r>(
-    content_type: &'a ContentType,
+/// Summary of an instance parsed in the request thread, the loader still
+/// panics on some malformed instances.
+fn load_uploaded_instance(
+    content_type: &ContentType,
     data: Data,
-    state: rocket::State<'r, mpsc::SyncSender<(Uuid, std::path::PathBuf)>>,
-) -> std::result::Result<rocket::Response<'a>, rocket::http::Status> {
-    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
-        MultipartFormDataField::file("file").size_limit(750 * 1024 * 1024),
+    config: &ServerConfig,
+) -> Result<MaintenanceSummary, ApiError> {
+    let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+        MultipartFormDataField::file("file").size_limit(config.instance_size_limit),
     ]);
-    let multipart_form_data = MultipartFormData::parse(content_type, data, options);
-    let multipart_form_data = multipart_form_data.unwrap();
-    let file = multipart_form_data.files.get("file");
-    match file {
-        Some(file) => {
-            let uuid = Uuid::new_v4();
-            let path = &file[0].path;
-            // clone uuid we need to send it back to client
-            // reemove clone when contents will be move to a thread
-            let r = state.send((uuid, path.clone()));
+    options.temporary_dir = config.upload_dir();
+    let mut multipart_form_data = MultipartFormData::parse(content_type, data, options)?;
+    let file = match multipart_form_data.files.remove("file") {
+        Some(ref file) if !file.is_empty() => file[0].path.clone(),
+        _ => return Err(ApiError::missing_file("file")),
+    };
+    let now = Instant::now();
+    let contents = std::fs::read_to_string(&file)
+        .map_err(|err| ApiError::bad_request("unreadable_file", err.to_string()))?;
+    let read = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let m = maintenance_json::read_json(&contents)
+        .map_err(|err| ApiError::invalid_instance(format!("{:?}", err)))?;
+    let parse = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let instance: MaintenanceOptimization =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            maintenance_json::load_instance(m)
+        }))
+        .map_err(|payload| ApiError::invalid_instance(error::panic_message(payload)))?
+        .ok_or_else(|| ApiError::invalid_instance("invalid instance"))?;
+    Ok(MaintenanceSummary {
+        timings: LoadTimings {
+            read,
+            parse,
+            convert: now.elapsed().as_secs_f64(),
+        },
+        hash: optim::file_hash(contents.as_bytes()),
+        ..MaintenanceSummary::new(&instance)
+    })
+}
 
-            let contents = reader::read_contents(path);
-            let now = Instant::now();
-            println!("Contents reading in: {}s", now.elapsed().as_secs());
-            let m = reader::read_json(&contents);
-            println!("Json reading in: {}s", now.elapsed().as_secs());
-            let m = reader::load_instance(m.unwrap());
-            match m {
-                Some(instance) => {
-                    let test = maintenance_capnproto::MaintenanceSummaryBuilder::from_maintenance(
-                        &instance.maintenance,
-                    );
-                    let data = test.bytes().expect("ok");
-                    let response = Response::build()
-                        .status(rocket::http::Status::Accepted)
-                        .header(ContentType::Binary)
-                        .sized_body(std::io::Cursor::new(data))
-                        .finalize();
-                    return Ok(response);
-                    //status::Accepted(Some(response::content::Json(content)))
-                }
-                _ => println!("ERROR"),
-            }
-        }
-        None => println!("No file"),
-    }
-    unimplemented!()
-    //let content = format!("\"code\": {}", "error");
-    //status::Accepted(Some(response::content::Json(content)))
+#[post("/optim", data = "<data>")]
+fn optim<'a>(
+    content_type: &'a ContentType,
+    data: Data,
+    _caller: auth::Caller,
+    config: rocket::State<ServerConfig>,
+) -> std::result::Result<rocket::Response<'a>, ApiError> {
+    let summary = load_uploaded_instance(content_type, data, &config)?;
+    let data = maintenance_capnproto::MaintenanceSummaryBuilder::from_summary(&summary)
+        .bytes()
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    Ok(Response::build()
+        .status(rocket::http::Status::Accepted)
+        .header(ContentType::Binary)
+        .sized_body(std::io::Cursor::new(data))
+        .finalize())
 }
 
 #[post("/optim-json", data = "<data>")]
-fn optim_json(content_type: &ContentType, data: Data) -> status::Accepted<content::Json<String>> {
-    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
-        MultipartFormDataField::file("file").size_limit(750 * 1024 * 1024),
-    ]);
-    let multipart_form_data = MultipartFormData::parse(content_type, data, options);
-    let mut multipart_form_data = multipart_form_data.unwrap();
-    let file = multipart_form_data.files.remove("file");
-    match file {
-        Some(file) => {
-            let path = &file[0].path;
-
-            let contents = reader::read_contents(path);
-            let now = Instant::now();
-            println!("Contents reading in: {}s", now.elapsed().as_secs());
-            let m = reader::read_json(&contents);
-            println!("Json reading in: {}s", now.elapsed().as_secs());
-            let m = reader::load_instance(m.unwrap());
-            match m {
-                Some(instance) => {
-                    let response = maintenance_json::MaintenanceSummary::from_maintenance(
-                        &instance.maintenance,
-                    );
-                    return status::Accepted(Some(response::content::Json(
-                        serde_json::to_string(&response).unwrap(),
-                    )));
-                }
-                _ => println!("ERROR"),
-            }
-        }
-        None => println!("No file"),
-    }
-    let content = format!("\"code\": {}", "error");
-    status::Accepted(Some(response::content::Json(content)))
+fn optim_json(
+    content_type: &ContentType,
+    data: Data,
+    _caller: auth::Caller,
+    config: rocket::State<ServerConfig>,
+) -> std::result::Result<status::Accepted<Json<MaintenanceSummary>>, ApiError> {
+    let summary = load_uploaded_instance(content_type, data, &config)?;
+    Ok(status::Accepted(Some(Json(summary))))
 }
 
-fn make_cors() -> Cors {
-    let allowed_origins = AllowedOrigins::some_exact(&[
-        "http://localhost:8080",
-        "http://127.0.0.1:8080",
-        "http://0.0.0.0:8080",
-        "http://localhost:8000",
-        "http://127.0.0.1:8000",
-        "http://0.0.0.0:8000",
-        "http://192.168.56.3:8000",
-        "http://192.168.56.3:8080",
-    ]);
+fn make_cors(config: &ServerConfig) -> Cors {
+    let allowed_origins = AllowedOrigins::some_exact(&config.cors_origins);
 
     CorsOptions {
         // 5.
@@ -166,6 +166,8 @@ fn make_cors() -> Cors {
             "Accept",
             "Content-type",
             "Access-Control-Allow-Origin", // 6.
+            auth::API_KEY_HEADER,
+            auth::USER_HEADER,
         ]),
         allow_credentials: true,
         ..Default::default()
@@ -174,16 +176,42 @@ fn make_cors() -> Cors {
     .expect("error while building CORS")
 }
 
-// TODO(vincent); use Rocket.toml
-fn rocket() -> rocket::Rocket {
-    let config = Config::build(Environment::Staging)
-        .address("0.0.0.0")
-        .port(8000)
+fn rocket(config: &ServerConfig) -> rocket::Rocket {
+    let rocket_config = Config::build(Environment::Staging)
+        .address(config.address.as_str())
+        .port(config.port)
+        .workers(config.workers)
         .finalize()
-        .expect("K");
-    rocket::custom(config)
-        .mount("/", routes![index, optim, optim_json, optim::receive_optim])
-        .attach(make_cors())
+        .expect("invalid address");
+    rocket::custom(rocket_config)
+        .mount(
+            "/",
+            routes![
+                index,
+                optim,
+                optim_json,
+                optim::receive_optim,
+                optim::find_hash,
+                optim::reuse_optim,
+                optim::solve_optim,
+                optim::cancel_optim,
+                store::list_jobs,
+                planning::optim_planning,
+                analysis::optim_workloads,
+                analysis::optim_risks,
+                progress::optim_progress,
+                store::job_status,
+                scheduler::queue_view,
+                metrics::metrics,
+                history::instances,
+                history::instance_runs,
+                history::instance_best,
+                history::run_improvements,
+                validation::validate_solution
+            ],
+        )
+        .register(error::catchers())
+        .attach(make_cors(config))
 }
 
 enum OptimizationRequest {
@@ -192,42 +220,50 @@ enum OptimizationRequest {
 }
 
 fn main() {
-    let optim: HashMap<Uuid, maintenance::io::reader::MaintenanceOptimization> = HashMap::new();
-    let optim = Arc::new(RwLock::new(optim));
-    // load
-    //let (instance_tx, instance_rx) =
-    //sync_channel::<(Uuid, MultipartFormData)>(INSTANCE_READING_BUFFER);
-    ////sync_channel::<(Uuid, std::path::PathBuf)>(INSTANCE_READING_BUFFER);
-    //{
-    //let optim = Arc::clone(&optim);
-    //thread::spawn(move || loop {
-    ////let (uuid, path) = instance_rx.recv().unwrap();
-    //let (uuid, data) = instance_rx.recv().unwrap();
-
-    //let file = data.files.get("file").unwrap();
-    //let path = &file[0].path;
-    //println!("recv: ({:?}, {:?})", uuid, uuid);
-    //let contents = reader::read_contents(path);
-    ////let contents = reader::read_contents(&*path);
-    //println!("file exits");
-    //let now = Instant::now();
-    //println!("Contents reading in: {}s", now.elapsed().as_secs());
-    //let m = reader::read_json(&contents);
-    //println!("Json reading in: {}s", now.elapsed().as_secs());
-    //let m = reader::load_instance(m.unwrap());
-    //match m {
-    //Some(instance) => {
-    //optim.write().expect("ok").insert(uuid, instance);
-    //}
-    //_ => println!("ERROR"),
-    //}
-    //});
-    //}
-    //let (optim_tx, optim_rx) = channel::<OptimizationRequest>();
-    let (executor, spawner) = optim::new_async_reader();
-    let _executor = executor.run();
-    rocket()
-        //.manage(instance_tx)
-        .manage(spawner.clone())
+    let config = ServerConfig::load().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1)
+    });
+    if let Err(err) = std::fs::create_dir_all(config.upload_dir()) {
+        eprintln!("cannot create {:?}: {}", config.upload_dir(), err);
+        std::process::exit(1)
+    }
+    let database = maintenance_db::Database::open(&config.database_url()).unwrap_or_else(|err| {
+        eprintln!("cannot open the database: {}", err);
+        std::process::exit(1)
+    });
+    if config.api_keys.is_empty() {
+        if !config.open {
+            eprintln!("no API key configured, set `open = true` to serve anyone");
+            std::process::exit(1)
+        }
+        println!("no API key configured, the server is open to anyone");
+    }
+    let store = Arc::new(store::JobStore::with_database(Arc::new(database)));
+    let metrics = Arc::new(metrics::Metrics::default());
+    let (reading, _readers) =
+        optim::start_reading(Arc::clone(&store), Arc::clone(&metrics), &config);
+    let (solving, _solvers) =
+        optim::start_solving(Arc::clone(&store), Arc::clone(&metrics), &config);
+    let _rpc = config.rpc_address.as_ref().map(|address| {
+        let listener = rpc::RpcListener::bind(address).unwrap_or_else(|err| {
+            eprintln!("cannot listen on {}: {}", address, err);
+            std::process::exit(1)
+        });
+        println!("RPC front-end listening on {}", address);
+        let context = rpc::Context {
+            config: config.clone(),
+            store: Arc::clone(&store),
+            reading: Arc::clone(&reading),
+            solving: Arc::clone(&solving),
+        };
+        rpc::start(listener, context)
+    });
+    rocket(&config)
+        .manage(config)
+        .manage(store)
+        .manage(metrics)
+        .manage(reading)
+        .manage(solving)
         .launch();
 }
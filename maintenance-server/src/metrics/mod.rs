@@ -0,0 +1,269 @@
+//! Prometheus metrics of the server, exposed on `/metrics`.
+use rocket::http::ContentType;
+use rocket::response::content::Content;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::optim::{ReadingScheduler, SolvingScheduler};
+use crate::store::JobStore;
+
+/// Upper bounds of the load time buckets, in seconds
+const LOAD_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Phases of the loading of an uploaded instance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LoadPhase {
+    /// Reading the file
+    Read,
+    /// Parsing the JSON
+    Parse,
+    /// Building the instance
+    Convert,
+}
+
+impl LoadPhase {
+    const ALL: [LoadPhase; 3] = [LoadPhase::Read, LoadPhase::Parse, LoadPhase::Convert];
+
+    fn name(self) -> &'static str {
+        match self {
+            LoadPhase::Read => "read",
+            LoadPhase::Parse => "parse",
+            LoadPhase::Convert => "convert",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations by bucket, the last one is `+Inf`
+    counts: [u64; LOAD_BUCKETS.len() + 1],
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        let bucket = LOAD_BUCKETS
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(LOAD_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+}
+
+/// Counters updated by the workers, the gauges are read at scrape time.
+#[derive(Default)]
+pub struct Metrics {
+    load_times: Mutex<[Histogram; 3]>,
+    /// Iterations of the finished runs
+    solver_iterations: AtomicU64,
+    /// Uploads of a file already loaded
+    shared_instances: AtomicU64,
+}
+
+impl Metrics {
+    pub fn observe_load(&self, phase: LoadPhase, elapsed: Duration) {
+        let idx = LoadPhase::ALL
+            .iter()
+            .position(|&other| other == phase)
+            .expect("phase");
+        self.load_times.lock().expect("lock")[idx].observe(elapsed.as_secs_f64());
+    }
+
+    pub fn count_shared_instance(&self) {
+        self.shared_instances.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_solver_iterations(&self, iterations: usize) {
+        self.solver_iterations
+            .fetch_add(iterations as u64, Ordering::Relaxed);
+    }
+
+    fn render(
+        &self,
+        store: &JobStore,
+        reading: &ReadingScheduler,
+        solving: &SolvingScheduler,
+    ) -> String {
+        let mut out = String::new();
+        // NOTE(vincent): writing to a String cannot fail
+        let _ = self.write(&mut out, store, reading, solving);
+        out
+    }
+
+    fn write(
+        &self,
+        out: &mut String,
+        store: &JobStore,
+        reading: &ReadingScheduler,
+        solving: &SolvingScheduler,
+    ) -> std::fmt::Result {
+        let queues = [reading.stats(), solving.stats()];
+        header(
+            out,
+            "maintenance_queue_length",
+            "gauge",
+            "Jobs waiting for a worker.",
+        )?;
+        for queue in queues.iter() {
+            writeln!(
+                out,
+                "maintenance_queue_length{{queue=\"{}\"}} {}",
+                queue.name, queue.queued
+            )?;
+        }
+        header(
+            out,
+            "maintenance_queue_running",
+            "gauge",
+            "Jobs on a worker.",
+        )?;
+        for queue in queues.iter() {
+            writeln!(
+                out,
+                "maintenance_queue_running{{queue=\"{}\"}} {}",
+                queue.name, queue.running
+            )?;
+        }
+        header(
+            out,
+            "maintenance_queue_workers",
+            "gauge",
+            "Workers of a queue.",
+        )?;
+        for queue in queues.iter() {
+            writeln!(
+                out,
+                "maintenance_queue_workers{{queue=\"{}\"}} {}",
+                queue.name, queue.workers
+            )?;
+        }
+        header(
+            out,
+            "maintenance_queue_capacity",
+            "gauge",
+            "Jobs that can wait before the submissions are rejected.",
+        )?;
+        for queue in queues.iter() {
+            writeln!(
+                out,
+                "maintenance_queue_capacity{{queue=\"{}\"}} {}",
+                queue.name, queue.capacity
+            )?;
+        }
+
+        header(out, "maintenance_jobs", "gauge", "Jobs by state.")?;
+        for (state, count) in store.count_by_status() {
+            writeln!(out, "maintenance_jobs{{state=\"{}\"}} {}", state, count)?;
+        }
+
+        header(
+            out,
+            "maintenance_load_seconds",
+            "histogram",
+            "Time spent loading the uploaded instances, by phase.",
+        )?;
+        let load_times = self.load_times.lock().expect("lock");
+        for (phase, histogram) in LoadPhase::ALL.iter().zip(load_times.iter()) {
+            let mut cumulated = 0;
+            for (idx, count) in histogram.counts.iter().enumerate() {
+                cumulated += count;
+                let bound = LOAD_BUCKETS
+                    .get(idx)
+                    .map(f64::to_string)
+                    .unwrap_or_else(|| "+Inf".to_owned());
+                writeln!(
+                    out,
+                    "maintenance_load_seconds_bucket{{phase=\"{}\",le=\"{}\"}} {}",
+                    phase.name(),
+                    bound,
+                    cumulated
+                )?;
+            }
+            writeln!(
+                out,
+                "maintenance_load_seconds_sum{{phase=\"{}\"}} {}",
+                phase.name(),
+                histogram.sum
+            )?;
+            writeln!(
+                out,
+                "maintenance_load_seconds_count{{phase=\"{}\"}} {}",
+                phase.name(),
+                cumulated
+            )?;
+        }
+        drop(load_times);
+        header(
+            out,
+            "maintenance_shared_instances_total",
+            "counter",
+            "Uploads of a file already loaded, not parsed again.",
+        )?;
+        writeln!(
+            out,
+            "maintenance_shared_instances_total {}",
+            self.shared_instances.load(Ordering::Relaxed)
+        )?;
+
+        // NOTE(vincent): the runs that end add their iterations to the
+        // counter with their status change, read them together.
+        let (iterations, speed) = store.with_active_runs(|runs| {
+            let running: u64 = runs.iter().map(|run| run.iterations() as u64).sum();
+            let speed: f64 = runs
+                .iter()
+                .filter_map(|run| run.iterations_per_second())
+                .sum();
+            (
+                self.solver_iterations.load(Ordering::Relaxed) + running,
+                speed,
+            )
+        });
+        header(
+            out,
+            "maintenance_solver_iterations_total",
+            "counter",
+            "Iterations done by the solvers.",
+        )?;
+        writeln!(out, "maintenance_solver_iterations_total {}", iterations)?;
+        header(
+            out,
+            "maintenance_solver_iterations_per_second",
+            "gauge",
+            "Speed of the running solvers, summed.",
+        )?;
+        writeln!(out, "maintenance_solver_iterations_per_second {}", speed)?;
+
+        header(
+            out,
+            "maintenance_instances_memory_bytes",
+            "gauge",
+            "Approximate memory held by the loaded instances.",
+        )?;
+        writeln!(
+            out,
+            "maintenance_instances_memory_bytes {}",
+            store.instances_memory()
+        )
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) -> std::fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)
+}
+
+#[get("/metrics")]
+pub fn metrics(
+    metrics: rocket::State<Arc<Metrics>>,
+    store: rocket::State<Arc<JobStore>>,
+    reading: rocket::State<Arc<ReadingScheduler>>,
+    solving: rocket::State<Arc<SolvingScheduler>>,
+) -> Content<String> {
+    Content(
+        ContentType::with_params("text", "plain", ("version", "0.0.4")),
+        metrics.render(&store, &reading, &solving),
+    )
+}
@@ -1,183 +1,390 @@
 use futures::future::{BoxFuture, FutureExt};
 use futures::task::{waker_ref, ArcWake};
-use rocket::http::ContentType;
+use rocket::http::{ContentType, RawStr, Status};
+use rocket::request::FromParam;
+use rocket::response::status;
 use rocket::response::Response;
 use rocket::Data;
+use rocket_contrib::json::Json;
 use rocket_multipart_form_data::{
     MultipartFormData, MultipartFormDataField, MultipartFormDataOptions,
 };
 use std::future::Future;
-use std::sync::mpsc;
-use std::sync::mpsc::sync_channel;
-use std::sync::mpsc::{Receiver, SyncSender};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
-use std::sync::Mutex;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use std::time::Instant;
 use uuid::Uuid;
 
-use maintenance::io::reader;
+use maintenance::search::{SearchParameters, Solver};
+use maintenance::{LoadTimings, MaintenanceOptimization};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
-const SMALL_SIZE_LIMIT: u64 = 10 * 1024 * 1024;
-const INSTANCE_SIZE_LIMIT: u64 = 750 * 1024 * 1024;
-const INSTANCE_READING_BUFFER: usize = 100;
+use crate::auth::Caller;
+use crate::config::ServerConfig;
+use crate::error::{panic_message, ApiError};
+use crate::metrics::{LoadPhase, Metrics};
+use crate::scheduler::{Scheduler, SchedulerJoinHandle, Task};
+use crate::store::{JobId, JobStatus, JobStore, JobView, RunError, SolverRun};
 
-#[post("/optim/new", data = "<data>")]
+/// Readings wait in a queue shared fairly between the users, `priority`
+/// (default 0, bounded for the users who are not admins) first.
+#[post("/optim/new?<priority>", data = "<data>")]
 pub fn receive_optim<'a, 'r>(
     content_type: &'a ContentType,
     data: Data,
-    state: rocket::State<'r, ReadingSpawner>,
-) -> std::result::Result<rocket::Response<'a>, rocket::http::Status> {
-    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
-        MultipartFormDataField::file("file").size_limit(INSTANCE_SIZE_LIMIT),
+    priority: Option<i32>,
+    caller: Caller,
+    scheduler: rocket::State<'r, Arc<ReadingScheduler>>,
+    store: rocket::State<'r, Arc<JobStore>>,
+    config: rocket::State<'r, ServerConfig>,
+) -> Result<rocket::Response<'a>, ApiError> {
+    let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+        MultipartFormDataField::file("file").size_limit(config.instance_size_limit),
     ]);
-    let multipart_form_data = MultipartFormData::parse(content_type, data, options);
-    let multipart_form_data = multipart_form_data.unwrap();
-    let file = multipart_form_data.files.get("file");
-    println!("opt: {:?}", file);
-    match file {
-        Some(file) => {
-            // NOTE: take ownership of MultiPartFormData baceuse it is responsible
-            // of tmp file deleting.
-            let uuid = state.spawn(ReadingTask::new(multipart_form_data));
-            let data = maintenance_capnproto::capnp_uuid::encode(&uuid).expect("OK");
-            let response = Response::build()
-                .status(rocket::http::Status::Accepted)
-                .header(ContentType::Binary)
-                .sized_body(std::io::Cursor::new(data))
-                .finalize();
-            return Ok(response);
+    options.temporary_dir = config.upload_dir();
+    let multipart_form_data = MultipartFormData::parse(content_type, data, options)?;
+    match multipart_form_data.files.get("file") {
+        Some(file) if !file.is_empty() => (),
+        _ => return Err(ApiError::missing_file("file")),
+    }
+    let uuid = Uuid::new_v4();
+    store.insert(uuid, &caller.user);
+    // NOTE: the task takes ownership of MultiPartFormData because it is
+    // responsible of tmp file deleting.
+    let task = Task {
+        uuid,
+        priority: caller.priority(priority.unwrap_or(0), &config),
+        user: caller.user,
+        payload: Upload::Form(multipart_form_data),
+    };
+    if scheduler.submit(task).is_err() {
+        store.remove(&uuid);
+        return Err(ApiError::queue_full());
+    }
+    accepted_uuid(&uuid)
+}
+
+fn accepted_uuid<'a>(uuid: &Uuid) -> Result<rocket::Response<'a>, ApiError> {
+    let data = maintenance_capnproto::capnp_uuid::encode(uuid)
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    Ok(Response::build()
+        .status(Status::Accepted)
+        .header(ContentType::Binary)
+        .sized_body(std::io::Cursor::new(data))
+        .finalize())
+}
+
+/// SHA-256 of a file as a route parameter, in hexadecimal.
+pub struct FileHash(pub String);
+
+impl<'a> FromParam<'a> for FileHash {
+    type Error = &'a RawStr;
+
+    fn from_param(param: &'a RawStr) -> Result<Self, Self::Error> {
+        let hash = param.as_str();
+        if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(FileHash(hash.to_ascii_lowercase()))
+        } else {
+            Err(param)
         }
-        None => println!("No file"),
     }
-    unimplemented!()
-}
-
-pub fn new_async_reader() -> (ReadingExecutor, ReadingSpawner) {
-    let (small_task_sender, small_ready_queue) = sync_channel(INSTANCE_READING_BUFFER);
-    let (large_task_sender, large_ready_queue) = sync_channel(INSTANCE_READING_BUFFER);
-    (
-        ReadingExecutor {
-            small_ready_queue,
-            large_ready_queue,
-        },
-        ReadingSpawner {
-            small_task_sender,
-            large_task_sender,
-        },
+}
+
+fn unknown_hash() -> ApiError {
+    ApiError::new(
+        Status::NotFound,
+        "unknown_instance",
+        "no instance uploaded with this hash",
     )
 }
 
-#[derive(Clone)]
-pub struct ReadingSpawner {
-    small_task_sender: SyncSender<(Uuid, MultipartFormData)>,
-    large_task_sender: SyncSender<(Uuid, MultipartFormData)>,
+/// Tells whether a file with this SHA-256 was already uploaded, before
+/// sending hundreds of megabytes again.
+#[get("/hashes/<hash>")]
+pub fn find_hash(
+    hash: FileHash,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<status::NoContent, ApiError> {
+    store
+        .find_by_hash(&hash.0, Some(&caller))
+        .map(|_| status::NoContent)
+        .ok_or_else(unknown_hash)
 }
 
-enum ReadingTask {
-    Small(MultipartFormData),
-    Large(MultipartFormData),
+/// New job of the caller on the instance already uploaded with this
+/// SHA-256, answered like `/optim/new`. `name` defaults to the name of the
+/// first upload.
+#[post("/hashes/<hash>/optim?<name>")]
+pub fn reuse_optim(
+    hash: FileHash,
+    name: Option<String>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<rocket::Response<'static>, ApiError> {
+    let (source, instance) = store
+        .find_by_hash(&hash.0, Some(&caller))
+        .ok_or_else(unknown_hash)?;
+    let name = match name {
+        Some(name) => name,
+        None => store.name(&source).unwrap_or_default(),
+    };
+    let uuid = Uuid::new_v4();
+    store.insert(uuid, &caller.user);
+    store.set_reference(&uuid, &name, &source, LoadTimings::default(), instance);
+    accepted_uuid(&uuid)
 }
 
-impl ReadingTask {
-    fn new(data: MultipartFormData) -> Self {
-        let file = data.files.get("file").unwrap();
-        let file = std::fs::metadata(&file[0].path).expect("Ok");
-        let size = file.len();
-        if size > SMALL_SIZE_LIMIT {
-            ReadingTask::Large(data)
-        } else {
-            ReadingTask::Small(data)
-        }
+/// Instance waiting to be read.
+pub enum Upload {
+    /// The temporary file is deleted with the form
+    Form(MultipartFormData),
+    /// Sent over RPC
+    Contents { name: String, contents: Vec<u8> },
+}
+
+pub type ReadingScheduler = Scheduler<Upload>;
+
+pub type SolvingScheduler = Scheduler<Arc<SolverRun>>;
+
+#[derive(Deserialize)]
+pub struct SolveRequest {
+    pub solver: String,
+    /// in seconds, at most `max_time_limit`
+    pub time_limit: u64,
+    #[serde(default)]
+    pub seed: u64,
+    /// Higher first, bounded for the users who are not admins
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Queues a new run on a loaded or solved job.
+pub fn submit_run(
+    store: &JobStore,
+    scheduler: &SolvingScheduler,
+    config: &ServerConfig,
+    uuid: Uuid,
+    caller: &Caller,
+    request: &SolveRequest,
+) -> Result<(), ApiError> {
+    let solver = request.solver.parse::<Solver>().map_err(|_| {
+        ApiError::bad_request(
+            "unknown_solver",
+            format!("unknown solver `{}`", request.solver),
+        )
+    })?;
+    if request.time_limit > config.max_time_limit {
+        return Err(ApiError::bad_request(
+            "time_limit_too_long",
+            format!("the time limit is at most {}s", config.max_time_limit),
+        ));
     }
+    let parameters = SearchParameters {
+        time_limit: Duration::from_secs(request.time_limit),
+        seed: request.seed,
+    };
+    let run = Arc::new(SolverRun::new(solver, parameters));
+    let previous = match store.start_run(&uuid, Arc::clone(&run)) {
+        Ok(previous) => previous,
+        Err(RunError::UnknownJob) => return Err(ApiError::unknown_job()),
+        Err(RunError::NotReady(status)) => return Err(ApiError::not_ready(&status)),
+    };
+    // NOTE(vincent): the run is attached before it is queued, so that a
+    // worker never picks a run the job does not know about yet.
+    let task = Task {
+        uuid,
+        user: caller.user.clone(),
+        priority: caller.priority(request.priority, config),
+        payload: Arc::clone(&run),
+    };
+    if scheduler.submit(task).is_err() {
+        store.undo_run(&uuid, &run, previous);
+        return Err(ApiError::queue_full());
+    }
+    Ok(())
+}
+
+#[post("/optim/<uuid>/solve", data = "<request>")]
+pub fn solve_optim(
+    uuid: JobId,
+    request: Json<SolveRequest>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+    scheduler: rocket::State<Arc<SolvingScheduler>>,
+    config: rocket::State<ServerConfig>,
+) -> Result<status::Accepted<Json<JobView>>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    submit_run(&store, &scheduler, &config, uuid.0, &caller, &request)?;
+    Ok(status::Accepted(store.view(&uuid.0).map(Json)))
+}
+
+#[post("/optim/<uuid>/cancel")]
+pub fn cancel_optim(
+    uuid: JobId,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<JobView>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    let run = store.run(&uuid.0).ok_or_else(ApiError::unknown_job)?;
+    run.cancel();
+    store
+        .view(&uuid.0)
+        .map(Json)
+        .ok_or_else(ApiError::unknown_job)
 }
 
-impl ReadingSpawner {
-    // TODO(vincent): result based on file[0] existence
-    fn spawn(&self, task: ReadingTask) -> Uuid {
-        let uuid = Uuid::new_v4();
-        match task {
-            ReadingTask::Small(task) => self
-                .small_task_sender
-                .send((uuid, task))
-                .expect("too many tasks queued"),
-            ReadingTask::Large(task) => self
-                .large_task_sender
-                .send((uuid, task))
-                .expect("too many tasks queued"),
+/// Starts the solver workers.
+pub fn start_solving(
+    store: Arc<JobStore>,
+    metrics: Arc<Metrics>,
+    config: &ServerConfig,
+) -> (Arc<SolvingScheduler>, SchedulerJoinHandle) {
+    let scheduler = Scheduler::new("solving", config.solver_threads, config.solver_queue_length);
+    let handle = scheduler.run(move |task: Task<Arc<SolverRun>>| {
+        run_solver(&store, &metrics, task.uuid, &task.payload)
+    });
+    (scheduler, handle)
+}
+
+fn run_solver(store: &JobStore, metrics: &Metrics, uuid: Uuid, run: &SolverRun) {
+    let instance = match store.instance(&uuid) {
+        Some(instance) => instance,
+        None => return,
+    };
+    store.record_run(&uuid, run);
+    run.start();
+    let solved = panic::catch_unwind(AssertUnwindSafe(|| {
+        if !run.is_cancelled() {
+            run.solver.run(
+                &instance.maintenance,
+                &run.parameters,
+                run.control(),
+                |improvement, planning| run.improve(improvement, planning),
+            );
+        }
+    }));
+    let status = match solved {
+        Ok(()) => {
+            let status = if run.is_cancelled() {
+                "cancelled"
+            } else {
+                "solved"
+            };
+            store.record_result(run, status);
+            JobStatus::Solved
         }
-        uuid
+        Err(payload) => {
+            store.record_result(run, "failed");
+            JobStatus::Failed(format!("solver panicked: {}", panic_message(payload)))
+        }
+    };
+    // NOTE(vincent): the iterations move from the running jobs to the
+    // counter at once, so that they are never counted twice or missed.
+    store.finish_run(&uuid, status, || {
+        metrics.add_solver_iterations(run.iterations())
+    });
+    run.finish();
+}
+
+/// Starts the reading workers.
+pub fn start_reading(
+    store: Arc<JobStore>,
+    metrics: Arc<Metrics>,
+    config: &ServerConfig,
+) -> (Arc<ReadingScheduler>, SchedulerJoinHandle) {
+    let scheduler = Scheduler::new(
+        "reading",
+        config.reading_workers,
+        config.reading_queue_length,
+    );
+    let handle = scheduler
+        .run(move |task: Task<Upload>| read_instance(&store, &metrics, task.uuid, task.payload));
+    (scheduler, handle)
+}
+
+/// SHA-256 of an uploaded file, in hexadecimal
+pub fn file_hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+fn parse_instance(
+    contents: &str,
+    metrics: &Metrics,
+    timings: &mut LoadTimings,
+) -> Result<MaintenanceOptimization, String> {
+    let now = Instant::now();
+    let m = maintenance_json::read_json(contents).map_err(|err| format!("{:?}", err))?;
+    metrics.observe_load(LoadPhase::Parse, now.elapsed());
+    timings.parse = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let instance = maintenance_json::load_instance(m).ok_or_else(|| "invalid instance".to_owned());
+    metrics.observe_load(LoadPhase::Convert, now.elapsed());
+    timings.convert = now.elapsed().as_secs_f64();
+    instance
+}
+
+/// Name and contents of the uploaded file.
+fn read_upload(upload: Upload) -> Result<(String, Vec<u8>), String> {
+    match upload {
+        Upload::Form(data) => {
+            let file = data
+                .files
+                .get("file")
+                .and_then(|file| file.first())
+                .ok_or_else(|| "no file".to_owned())?;
+            let contents = std::fs::read(&file.path).map_err(|err| err.to_string())?;
+            Ok((file.file_name.clone().unwrap_or_default(), contents))
+        }
+        Upload::Contents { name, contents } => Ok((name, contents)),
     }
 }
 
-pub struct RunningExecutor {
-    small_run_queue: Arc<Queue<Uuid>>,
-    large_run_queue: Arc<Queue<Uuid>>,
-    // 2threadss: if large empty then run small
-}
-
-pub struct ReadingExecutor {
-    small_ready_queue: Receiver<(Uuid, MultipartFormData)>,
-    large_ready_queue: Receiver<(Uuid, MultipartFormData)>,
-}
-
-pub struct ReadingExecutorJoinHandle {
-    small_join_handle: std::thread::JoinHandle<()>,
-    large_join_handle: std::thread::JoinHandle<()>,
-}
-
-impl ReadingExecutor {
-    pub fn run(self) -> ReadingExecutorJoinHandle {
-        let (small_ready_queue, large_ready_queue) =
-            (self.small_ready_queue, self.large_ready_queue);
-        ReadingExecutorJoinHandle {
-            small_join_handle: std::thread::spawn(move || {
-                loop {
-                    //small_ready_queue: Receiver<Arc<ReadingTask>>,
-                    //large_ready_queue: Receiver<Arc<ReadingTask>>,
-                    let (uuid, data) = small_ready_queue.recv().unwrap();
-                    let file = data.files.get("file").unwrap();
-                    let path = &file[0].path;
-                    let contents = reader::read_contents(path);
-                    //let contents = reader::read_contents(&*path);
-                    println!("Small handle: {:?}", file);
-                    let now = Instant::now();
-                    println!("Contents reading in: {}s", now.elapsed().as_secs());
-                    let m = reader::read_json(&contents);
-                    println!("Json reading in: {}s", now.elapsed().as_secs());
-                    let m = reader::load_instance(m.unwrap());
-                    match m {
-                        Some(instance) => println!("OK"),
-                        //optim.write().expect("ok").insert(uuid, instance);
-                        _ => println!("ERROR"),
-                    }
-                }
-            }),
-            large_join_handle: std::thread::spawn(move || {
-                loop {
-                    //small_ready_queue: Receiver<Arc<ReadingTask>>,
-                    //large_ready_queue: Receiver<Arc<ReadingTask>>,
-                    let (uuid, data) = large_ready_queue.recv().unwrap();
-                    let file = data.files.get("file").unwrap();
-                    let path = &file[0].path;
-                    let m = {
-                        let contents = reader::read_contents(path);
-                        //let contents = reader::read_contents(&*path);
-                        println!("Large handle: {:?}", file);
-                        let now = Instant::now();
-                        println!("Contents reading in: {}s", now.elapsed().as_secs());
-                        let m = reader::read_json(&contents);
-                        println!("Json reading in: {}s", now.elapsed().as_secs());
-                        reader::load_instance(m.unwrap())
-                    }; // NOTE: drop contents here
-                    match m {
-                        Some(instance) => println!("OK"),
-                        //optim.write().expect("ok").insert(uuid, instance);
-                        _ => println!("ERROR"),
-                    }
-                }
-            }),
+/// An instance uploaded twice is parsed once, the jobs share it.
+fn read_instance(store: &JobStore, metrics: &Metrics, uuid: Uuid, upload: Upload) {
+    store.set_status(&uuid, JobStatus::Loading);
+    let now = Instant::now();
+    let (name, contents) = match read_upload(upload) {
+        Ok(upload) => upload,
+        Err(err) => return store.set_status(&uuid, JobStatus::Failed(err)),
+    };
+    metrics.observe_load(LoadPhase::Read, now.elapsed());
+    let mut timings = LoadTimings {
+        read: now.elapsed().as_secs_f64(),
+        ..LoadTimings::default()
+    };
+    let hash = file_hash(&contents);
+    // NOTE(vincent): the same file uploaded twice at once is parsed by the
+    // first reading, the other one waits to share its instance.
+    let _parsing = loop {
+        match store.start_parsing(&hash) {
+            Ok(parsing) => break parsing,
+            Err(pending) => pending.wait(),
         }
+    };
+    // NOTE(vincent): the uploader holds the file, the instances of the other
+    // users may be shared.
+    if let Some((source, instance)) = store.find_by_hash(&hash, None) {
+        metrics.count_shared_instance();
+        return store.set_reference(&uuid, &name, &source, timings, instance);
+    }
+    let contents = match String::from_utf8(contents) {
+        Ok(contents) => contents,
+        Err(err) => return store.set_status(&uuid, JobStatus::Failed(err.to_string())),
+    };
+    // NOTE(vincent): the loader still panics on some malformed instances, a
+    // panic must not take the reading thread down.
+    let m = match panic::catch_unwind(AssertUnwindSafe(|| {
+        parse_instance(&contents, metrics, &mut timings)
+    })) {
+        Ok(m) => m,
+        Err(payload) => Err(format!("invalid instance: {}", panic_message(payload))),
+    };
+    match m {
+        Ok(instance) => store.set_instance(&uuid, &name, &hash, timings, Arc::new(instance)),
+        Err(err) => store.set_status(&uuid, JobStatus::Failed(err)),
     }
 }
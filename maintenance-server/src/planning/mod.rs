@@ -0,0 +1,193 @@
+//! Export of the best planning of a job, in the format asked by the client.
+use rocket::http::{Accept, ContentType, Status};
+use rocket::response::Response;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use maintenance::search::Improvement;
+use maintenance::{MaintenanceOptimization, Planning};
+use maintenance_capnproto::planning::PlanningBuilder;
+use maintenance_json::ExportPlanning;
+
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::store::{JobId, JobStore};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PlanningFormat {
+    /// `name day` lines, as expected by the challenge checker
+    Roadef,
+    Json,
+    /// `Planning` message for the browser
+    Capnp,
+}
+
+impl PlanningFormat {
+    /// First supported media type by decreasing weight. Without `Accept`
+    /// header, the planning is sent in the ROADEF format.
+    fn from_accept(accept: Option<&Accept>) -> Option<Self> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return Some(PlanningFormat::Roadef),
+        };
+        // NOTE(vincent): `q=0` marks a media type as not acceptable, and rocket
+        // lets `q=NaN` through.
+        let mut media_types: Vec<_> = accept
+            .iter()
+            .filter(|media_type| media_type.weight_or(1.0) > 0.0)
+            .collect();
+        media_types.sort_by(|a, b| {
+            b.weight_or(1.0)
+                .partial_cmp(&a.weight_or(1.0))
+                .unwrap_or(Ordering::Equal)
+        });
+        media_types.into_iter().find_map(|media_type| {
+            let media_type = media_type.media_type();
+            match (media_type.top().as_str(), media_type.sub().as_str()) {
+                ("application", "json") => Some(PlanningFormat::Json),
+                ("application", "octet-stream") | ("application", "x-capnp") => {
+                    Some(PlanningFormat::Capnp)
+                }
+                ("text", "plain") | ("text", "*") | ("*", "*") => Some(PlanningFormat::Roadef),
+                _ => None,
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct PlannedIntervention<'a> {
+    name: &'a str,
+    /// as in the instances: the first day is 1
+    start: usize,
+    /// last day of the intervention, included
+    end: usize,
+}
+
+#[derive(Serialize)]
+pub struct PlanningView<'a> {
+    cost: f64,
+    obj1: f64,
+    obj2: f64,
+    feasible: bool,
+    /// in seconds
+    elapsed: f64,
+    iteration: usize,
+    interventions: Vec<PlannedIntervention<'a>>,
+}
+
+impl<'a> PlanningView<'a> {
+    pub fn new(
+        instance: &'a MaintenanceOptimization,
+        improvement: &Improvement,
+        planning: &Planning,
+    ) -> Self {
+        let interventions = planning
+            .interventions
+            .iter()
+            .map(|&(iid, day)| {
+                let period = instance.maintenance.intervention(iid).period(day);
+                PlannedIntervention {
+                    name: &instance.mapping.interventions[&iid],
+                    start: period.start().get() + 1,
+                    end: period.end().get() + 1,
+                }
+            })
+            .collect();
+        PlanningView {
+            cost: improvement.cost,
+            obj1: improvement.obj1,
+            obj2: improvement.obj2,
+            feasible: improvement.feasible,
+            elapsed: improvement.elapsed.as_secs_f64(),
+            iteration: improvement.iteration,
+            interventions,
+        }
+    }
+}
+
+/// Best planning found so far by the last run.
+#[get("/optim/<uuid>/planning")]
+pub fn optim_planning(
+    uuid: JobId,
+    accept: Option<&Accept>,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Response<'static>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    let format = PlanningFormat::from_accept(accept).ok_or_else(|| {
+        ApiError::new(
+            Status::NotAcceptable,
+            "not_acceptable",
+            "plannings are sent as text/plain, application/json or application/octet-stream",
+        )
+    })?;
+    let instance = store.instance(&uuid.0).ok_or_else(ApiError::unknown_job)?;
+    let (improvement, planning) = store
+        .run(&uuid.0)
+        .and_then(|run| run.best())
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_planning", "no planning found yet"))?;
+    let (content_type, data) = match format {
+        PlanningFormat::Roadef => {
+            let export = ExportPlanning {
+                planning: &planning,
+                mapping: &instance.mapping,
+            };
+            (ContentType::Plain, export.to_string().into_bytes())
+        }
+        PlanningFormat::Json => {
+            let view = PlanningView::new(&instance, &improvement, &planning);
+            let data =
+                serde_json::to_vec(&view).map_err(|err| ApiError::internal(err.to_string()))?;
+            (ContentType::JSON, data)
+        }
+        PlanningFormat::Capnp => {
+            let data = PlanningBuilder::from_planning(&planning)
+                .bytes()
+                .map_err(|err| ApiError::internal(err.to_string()))?;
+            (ContentType::Binary, data)
+        }
+    };
+    Ok(Response::build()
+        .header(content_type)
+        .sized_body(Cursor::new(data))
+        .finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(accept: &str) -> Option<PlanningFormat> {
+        PlanningFormat::from_accept(Some(&accept.parse::<Accept>().unwrap()))
+    }
+
+    #[test]
+    fn format_from_accept() {
+        assert_eq!(
+            PlanningFormat::from_accept(None),
+            Some(PlanningFormat::Roadef)
+        );
+        assert_eq!(
+            format("application/json;q=0.5, application/octet-stream"),
+            Some(PlanningFormat::Capnp)
+        );
+        assert_eq!(format("image/png"), None);
+    }
+
+    #[test]
+    fn unacceptable_weights() {
+        assert_eq!(
+            format("text/plain;q=NaN, application/json"),
+            Some(PlanningFormat::Json)
+        );
+        assert_eq!(format("text/plain;q=NaN"), None);
+        assert_eq!(
+            format("application/json;q=0, text/plain;q=0.5"),
+            Some(PlanningFormat::Roadef)
+        );
+        assert_eq!(format("application/json;q=0"), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Server-sent events pushing the improvements of a solver run.
+use rocket::http::{ContentType, Status};
+use rocket::response::Response;
+use serde::Serialize;
+use std::io::{self, Read};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use maintenance::search::Improvement;
+
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::store::{JobId, JobStore};
+
+const EVENT_CHUNK_SIZE: u64 = 4096;
+
+#[derive(Clone, Debug)]
+pub enum ProgressEvent {
+    Improvement(Improvement),
+    Done { cancelled: bool },
+}
+
+#[derive(Serialize)]
+struct ImprovementView {
+    cost: f64,
+    obj1: f64,
+    obj2: f64,
+    feasible: bool,
+    /// in seconds
+    elapsed: f64,
+    iteration: usize,
+}
+
+#[derive(Serialize)]
+struct DoneView {
+    cancelled: bool,
+}
+
+impl ProgressEvent {
+    /// Encodes the event in the `text/event-stream` format.
+    fn to_sse(&self) -> String {
+        let (name, data) = match self {
+            ProgressEvent::Improvement(improvement) => (
+                "improvement",
+                serde_json::to_string(&ImprovementView {
+                    cost: improvement.cost,
+                    obj1: improvement.obj1,
+                    obj2: improvement.obj2,
+                    feasible: improvement.feasible,
+                    elapsed: improvement.elapsed.as_secs_f64(),
+                    iteration: improvement.iteration,
+                }),
+            ),
+            ProgressEvent::Done { cancelled } => (
+                "done",
+                serde_json::to_string(&DoneView {
+                    cancelled: *cancelled,
+                }),
+            ),
+        };
+        format!("event: {}\ndata: {}\n\n", name, data.expect("json"))
+    }
+}
+
+/// Body of the stream: blocks until the next event, and asks Rocket to flush
+/// after each one by returning `WouldBlock`. The stream ends with the run.
+struct EventReader {
+    events: Receiver<ProgressEvent>,
+    pending: Vec<u8>,
+    flush: bool,
+}
+
+impl Read for EventReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            if self.flush {
+                self.flush = false;
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            match self.events.recv() {
+                Ok(event) => {
+                    self.pending = event.to_sse().into_bytes();
+                    self.flush = true;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        // a `WouldBlock` at the start of the next chunk would end the stream:
+        // the flush waits for the next event.
+        if self.pending.is_empty() && n == buf.len() {
+            self.flush = false;
+        }
+        Ok(n)
+    }
+}
+
+/// Each open stream holds one of the Rocket workers until the run is over.
+#[get("/optim/<uuid>/progress")]
+pub fn optim_progress(
+    uuid: JobId,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Response<'static>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    let run = store
+        .run(&uuid.0)
+        .ok_or_else(|| ApiError::new(Status::NotFound, "no_run", "no solver run on this job"))?;
+    let reader = EventReader {
+        events: run.subscribe(),
+        pending: Vec::new(),
+        flush: false,
+    };
+    Ok(Response::build()
+        .header(ContentType::new("text", "event-stream"))
+        .raw_header("Cache-Control", "no-cache")
+        .chunked_body(reader, EVENT_CHUNK_SIZE)
+        .finalize())
+}
@@ -0,0 +1,456 @@
+//! Cap'n Proto RPC front-end, see `solver.capnp`: the jobs of the HTTP API
+//! for the batch tools, on a TCP or Unix socket.
+//!
+//! The connections are served by a single thread, the capabilities are not
+//! `Send`. The instances are still read and solved by the workers.
+use capnp::capability::Promise;
+use capnp::message::ReaderOptions;
+use capnp_rpc::{pry, rpc_twoparty_capnp, twoparty, RpcSystem};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::oneshot;
+use futures::{AsyncReadExt, FutureExt, StreamExt};
+use rocket::http::Status;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+use tokio_util::compat::Tokio02AsyncReadCompatExt;
+use uuid::Uuid;
+
+use maintenance::search::Improvement;
+use maintenance_capnproto::planning::build_planning;
+use maintenance_capnproto::solver_capnp::{
+    authenticator, improvement, job, progress_listener, solver, subscription,
+};
+
+use crate::auth::Caller;
+use crate::config::ServerConfig;
+use crate::error::ApiError;
+use crate::optim::{submit_run, ReadingScheduler, SolveRequest, SolvingScheduler, Upload};
+use crate::progress::ProgressEvent;
+use crate::scheduler::Task;
+use crate::store::JobStore;
+
+impl From<ApiError> for capnp::Error {
+    /// The code first, the clients match on it.
+    fn from(error: ApiError) -> Self {
+        capnp::Error::failed(format!("{}: {}", error.code(), error.message()))
+    }
+}
+
+/// State shared with the HTTP front-end.
+pub struct Context {
+    pub config: ServerConfig,
+    pub store: Arc<JobStore>,
+    pub reading: Arc<ReadingScheduler>,
+    pub solving: Arc<SolvingScheduler>,
+}
+
+impl Context {
+    fn reader_options(&self) -> ReaderOptions {
+        // NOTE(vincent): the uploads hold a whole instance, way larger than
+        // the default traversal limit.
+        let mut options = ReaderOptions::new();
+        options.traversal_limit_in_words(self.config.instance_size_limit / 8 + 1024 * 1024);
+        options
+    }
+}
+
+pub enum RpcListener {
+    Tcp(std::net::TcpListener),
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl RpcListener {
+    /// `host:port` or `unix:<path>`. The socket left by a previous server at
+    /// the same path is replaced.
+    pub fn bind(address: &str) -> io::Result<Self> {
+        match address.strip_prefix("unix:") {
+            Some(path) => {
+                let stale = std::fs::symlink_metadata(path)
+                    .map(|metadata| metadata.file_type().is_socket())
+                    .unwrap_or(false);
+                if stale {
+                    std::fs::remove_file(path)?;
+                }
+                let listener = std::os::unix::net::UnixListener::bind(path)?;
+                listener.set_nonblocking(true)?;
+                Ok(RpcListener::Unix(listener))
+            }
+            None => {
+                let listener = std::net::TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                Ok(RpcListener::Tcp(listener))
+            }
+        }
+    }
+}
+
+/// Serves the connections in a new thread.
+pub fn start(listener: RpcListener, context: Context) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+        .name("rpc".to_owned())
+        .spawn(move || {
+            if let Err(err) = serve(listener, context) {
+                eprintln!("RPC front-end stopped: {}", err);
+            }
+        })
+        .expect("rpc thread")
+}
+
+fn serve(listener: RpcListener, context: Context) -> io::Result<()> {
+    let mut runtime = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()?;
+    let context = Rc::new(context);
+    tokio::task::LocalSet::new().block_on(&mut runtime, async move {
+        match listener {
+            RpcListener::Tcp(listener) => {
+                let mut listener = tokio::net::TcpListener::from_std(listener)?;
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, peer)) => {
+                            if let Err(err) = stream.set_nodelay(true) {
+                                eprintln!("cannot disable Nagle on {}: {}", peer, err);
+                            }
+                            connect(stream, peer.ip().to_string(), &context)
+                        }
+                        Err(err) => eprintln!("cannot accept an RPC connection: {}", err),
+                    }
+                }
+            }
+            RpcListener::Unix(listener) => {
+                let mut listener = tokio::net::UnixListener::from_std(listener)?;
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => connect(stream, "anonymous".to_owned(), &context),
+                        Err(err) => eprintln!("cannot accept an RPC connection: {}", err),
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// `peer` names the user when the server has no key.
+fn connect<S>(stream: S, peer: String, context: &Rc<Context>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + 'static,
+{
+    let (reader, writer) = stream.compat().split();
+    let network = twoparty::VatNetwork::new(
+        reader,
+        writer,
+        rpc_twoparty_capnp::Side::Server,
+        context.reader_options(),
+    );
+    let authenticator = authenticator::ToClient::new(AuthenticatorServer {
+        context: Rc::clone(context),
+        peer,
+    })
+    .into_client::<capnp_rpc::Server>();
+    let rpc_system = RpcSystem::new(Box::new(network), Some(authenticator.client));
+    tokio::task::spawn_local(rpc_system.map(|served| {
+        if let Err(err) = served {
+            eprintln!("RPC connection closed: {}", err);
+        }
+    }));
+}
+
+struct AuthenticatorServer {
+    context: Rc<Context>,
+    peer: String,
+}
+
+impl authenticator::Server for AuthenticatorServer {
+    fn login(
+        &mut self,
+        params: authenticator::LoginParams,
+        mut results: authenticator::LoginResults,
+    ) -> Promise<(), capnp::Error> {
+        let key = pry!(pry!(params.get()).get_key());
+        // NOTE(vincent): without a key the clients cannot prove who they
+        // are, they only see their own jobs, named after their address.
+        let caller = if self.context.config.api_keys.is_empty() {
+            Caller {
+                user: self.peer.clone(),
+                admin: false,
+            }
+        } else {
+            match Caller::with_key(&self.context.config, key) {
+                Some(caller) => caller,
+                None => return Promise::err(ApiError::unauthorized().into()),
+            }
+        };
+        results.get().set_solver(
+            solver::ToClient::new(SolverServer {
+                context: Rc::clone(&self.context),
+                caller,
+            })
+            .into_client::<capnp_rpc::Server>(),
+        );
+        Promise::ok(())
+    }
+}
+
+struct SolverServer {
+    context: Rc<Context>,
+    caller: Caller,
+}
+
+impl SolverServer {
+    fn job_client(&self, uuid: Uuid) -> job::Client {
+        job::ToClient::new(JobServer {
+            context: Rc::clone(&self.context),
+            caller: self.caller.clone(),
+            uuid,
+        })
+        .into_client::<capnp_rpc::Server>()
+    }
+}
+
+impl solver::Server for SolverServer {
+    /// Queued like `/optim/new`.
+    fn upload(
+        &mut self,
+        params: solver::UploadParams,
+        mut results: solver::UploadResults,
+    ) -> Promise<(), capnp::Error> {
+        let params = pry!(params.get());
+        let name = pry!(params.get_name()).to_owned();
+        let contents = pry!(params.get_contents());
+        if contents.len() as u64 > self.context.config.instance_size_limit {
+            return Promise::err(ApiError::too_large("`contents` is too large").into());
+        }
+        let uuid = Uuid::new_v4();
+        let store = &self.context.store;
+        store.insert(uuid, &self.caller.user);
+        let task = Task {
+            uuid,
+            user: self.caller.user.clone(),
+            priority: self
+                .caller
+                .priority(params.get_priority(), &self.context.config),
+            payload: Upload::Contents {
+                name,
+                contents: contents.to_vec(),
+            },
+        };
+        if self.context.reading.submit(task).is_err() {
+            store.remove(&uuid);
+            return Promise::err(ApiError::queue_full().into());
+        }
+        results.get().set_job(self.job_client(uuid));
+        Promise::ok(())
+    }
+
+    fn job(
+        &mut self,
+        params: solver::JobParams,
+        mut results: solver::JobResults,
+    ) -> Promise<(), capnp::Error> {
+        let uuid = pry!(pry!(params.get()).get_uuid());
+        let uuid = pry!(Uuid::from_slice(uuid)
+            .map_err(|_| ApiError::bad_request("invalid_uuid", "a uuid is made of 16 bytes")));
+        pry!(self.context.store.authorize(&uuid, &self.caller));
+        results.get().set_job(self.job_client(uuid));
+        Promise::ok(())
+    }
+}
+
+/// Job of the caller, checked when the capability is given.
+struct JobServer {
+    context: Rc<Context>,
+    caller: Caller,
+    uuid: Uuid,
+}
+
+fn build_improvement(mut i: improvement::Builder, improvement: &Improvement) {
+    i.set_cost(improvement.cost);
+    i.set_obj1(improvement.obj1);
+    i.set_obj2(improvement.obj2);
+    i.set_feasible(improvement.feasible);
+    i.set_elapsed(improvement.elapsed.as_secs_f64());
+    i.set_iteration(improvement.iteration as u64);
+}
+
+fn no_run() -> ApiError {
+    ApiError::new(Status::NotFound, "no_run", "no solver run on this job")
+}
+
+fn no_planning() -> ApiError {
+    ApiError::new(Status::NotFound, "no_planning", "no planning found yet")
+}
+
+impl job::Server for JobServer {
+    fn uuid(
+        &mut self,
+        _: job::UuidParams,
+        mut results: job::UuidResults,
+    ) -> Promise<(), capnp::Error> {
+        results.get().set_uuid(self.uuid.as_bytes());
+        Promise::ok(())
+    }
+
+    fn status(
+        &mut self,
+        _: job::StatusParams,
+        mut results: job::StatusResults,
+    ) -> Promise<(), capnp::Error> {
+        let store = &self.context.store;
+        let status = pry!(store.status(&self.uuid).ok_or_else(ApiError::unknown_job));
+        let mut results = results.get();
+        results.set_status(status.name());
+        if let Some(error) = status.error() {
+            results.set_error(error);
+        }
+        if let Some(summary) = store.summary(&self.uuid) {
+            maintenance_capnproto::build_summary(results.init_summary(), &summary);
+        }
+        Promise::ok(())
+    }
+
+    /// Queued like `/optim/<uuid>/solve`.
+    fn solve(
+        &mut self,
+        params: job::SolveParams,
+        _: job::SolveResults,
+    ) -> Promise<(), capnp::Error> {
+        let params = pry!(params.get());
+        let request = SolveRequest {
+            solver: pry!(params.get_solver()).to_owned(),
+            time_limit: params.get_time_limit(),
+            seed: params.get_seed(),
+            priority: params.get_priority(),
+        };
+        pry!(submit_run(
+            &self.context.store,
+            &self.context.solving,
+            &self.context.config,
+            self.uuid,
+            &self.caller,
+            &request
+        ));
+        Promise::ok(())
+    }
+
+    fn subscribe(
+        &mut self,
+        params: job::SubscribeParams,
+        mut results: job::SubscribeResults,
+    ) -> Promise<(), capnp::Error> {
+        let listener = pry!(pry!(params.get()).get_listener());
+        let run = pry!(self.context.store.run(&self.uuid).ok_or_else(no_run));
+        let (subscription, dropped) = oneshot::channel();
+        tokio::task::spawn_local(notify(listener, run.subscribe_async(), dropped));
+        results.get().set_subscription(
+            subscription::ToClient::new(SubscriptionServer {
+                _subscription: subscription,
+            })
+            .into_client::<capnp_rpc::Server>(),
+        );
+        Promise::ok(())
+    }
+
+    fn planning(
+        &mut self,
+        _: job::PlanningParams,
+        mut results: job::PlanningResults,
+    ) -> Promise<(), capnp::Error> {
+        let (improvement, planning) = pry!(self
+            .context
+            .store
+            .run(&self.uuid)
+            .and_then(|run| run.best())
+            .ok_or_else(no_planning));
+        let mut results = results.get();
+        build_improvement(results.reborrow().init_improvement(), &improvement);
+        build_planning(results.init_planning(), &planning);
+        Promise::ok(())
+    }
+
+    fn cancel(&mut self, _: job::CancelParams, _: job::CancelResults) -> Promise<(), capnp::Error> {
+        let run = pry!(self.context.store.run(&self.uuid).ok_or_else(no_run));
+        run.cancel();
+        Promise::ok(())
+    }
+}
+
+/// Dropped by the client to stop the notifications.
+struct SubscriptionServer {
+    _subscription: oneshot::Sender<()>,
+}
+
+impl subscription::Server for SubscriptionServer {}
+
+/// Passes the events of a run to the listener until the run is over or the
+/// subscription is dropped.
+async fn notify(
+    listener: progress_listener::Client,
+    mut events: UnboundedReceiver<ProgressEvent>,
+    dropped: oneshot::Receiver<()>,
+) {
+    let mut dropped = dropped.fuse();
+    loop {
+        let event = futures::select! {
+            event = events.next() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            _ = dropped => break,
+        };
+        let notified = match event {
+            ProgressEvent::Improvement(improvement) => {
+                let mut request = listener.improvement_request();
+                build_improvement(request.get().init_improvement(), &improvement);
+                request.send().promise.await.map(|_| ())
+            }
+            ProgressEvent::Done { cancelled } => {
+                let mut request = listener.done_request();
+                request.get().set_cancelled(cancelled);
+                request.send().promise.await.map(|_| ())
+            }
+        };
+        if let Err(err) = notified {
+            eprintln!("cannot notify an RPC client: {}", err);
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_start_with_their_code() {
+        let error = capnp::Error::from(ApiError::unknown_job());
+        assert_eq!(error.kind, capnp::ErrorKind::Failed);
+        assert!(error.description.starts_with("unknown_job: "));
+    }
+
+    #[test]
+    fn stale_socket_replaced() {
+        let path = std::env::temp_dir().join(format!("maintenance-{}.sock", Uuid::new_v4()));
+        let address = format!("unix:{}", path.display());
+        let first = RpcListener::bind(&address).unwrap();
+        assert!(matches!(first, RpcListener::Unix(_)));
+        // the socket outlives its listener, as after a crash
+        drop(first);
+        assert!(RpcListener::bind(&address).is_ok());
+        // but a regular file is never removed
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(&path, b"data").unwrap();
+        assert!(RpcListener::bind(&address).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tcp_address() {
+        let listener = RpcListener::bind("127.0.0.1:0").unwrap();
+        assert!(matches!(listener, RpcListener::Tcp(_)));
+    }
+}
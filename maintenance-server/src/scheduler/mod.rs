@@ -0,0 +1,345 @@
+//! Bounded queue of jobs served by a pool of workers.
+//!
+//! The next job is the one with the highest priority, then the one of the
+//! user with the fewest jobs running and served the longest time ago, so
+//! that the jobs of a user do not starve the others.
+use rocket_contrib::json::Json;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::auth::Caller;
+
+pub struct Task<T> {
+    pub uuid: Uuid,
+    /// Jobs are shared fairly between the users
+    pub user: String,
+    /// Higher first
+    pub priority: i32,
+    pub payload: T,
+}
+
+struct Queued<T> {
+    task: Task<T>,
+    ticket: u64,
+    submitted: Instant,
+}
+
+struct Running {
+    uuid: Uuid,
+    user: String,
+    priority: i32,
+    started: Instant,
+}
+
+struct Queue<T> {
+    queued: Vec<Queued<T>>,
+    running: Vec<Running>,
+    /// Ticket of the last job started by each user
+    served: HashMap<String, u64>,
+    next_ticket: u64,
+}
+
+impl<T> Queue<T> {
+    fn running_jobs(&self, user: &str) -> usize {
+        self.running.iter().filter(|job| job.user == user).count()
+    }
+
+    fn pop(&mut self) -> Option<Task<T>> {
+        let idx = self
+            .queued
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, queued)| {
+                let user = &queued.task.user;
+                (
+                    Reverse(queued.task.priority),
+                    self.running_jobs(user),
+                    self.served.get(user).cloned().unwrap_or(0),
+                    queued.ticket,
+                )
+            })
+            .map(|(idx, _)| idx)?;
+        let queued = self.queued.remove(idx);
+        self.served
+            .insert(queued.task.user.clone(), self.next_ticket);
+        self.next_ticket += 1;
+        self.running.push(Running {
+            uuid: queued.task.uuid,
+            user: queued.task.user.clone(),
+            priority: queued.task.priority,
+            started: Instant::now(),
+        });
+        Some(queued.task)
+    }
+}
+
+#[derive(Debug)]
+pub struct QueueFull;
+
+pub struct Scheduler<T> {
+    name: &'static str,
+    workers: usize,
+    capacity: usize,
+    queue: Mutex<Queue<T>>,
+    ready: Condvar,
+}
+
+pub struct SchedulerJoinHandle {
+    join_handles: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> Scheduler<T> {
+    /// `capacity` jobs at most wait for one of the `workers`.
+    pub fn new(name: &'static str, workers: usize, capacity: usize) -> Arc<Self> {
+        Arc::new(Scheduler {
+            name,
+            workers: workers.max(1),
+            capacity,
+            queue: Mutex::new(Queue {
+                queued: Vec::new(),
+                running: Vec::new(),
+                served: HashMap::new(),
+                next_ticket: 1,
+            }),
+            ready: Condvar::new(),
+        })
+    }
+
+    pub fn submit(&self, task: Task<T>) -> Result<(), QueueFull> {
+        let mut queue = self.queue.lock().expect("lock");
+        if queue.queued.len() >= self.capacity {
+            return Err(QueueFull);
+        }
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        queue.queued.push(Queued {
+            task,
+            ticket,
+            submitted: Instant::now(),
+        });
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    /// Waits for the next job.
+    fn next(&self) -> Task<T> {
+        let mut queue = self.queue.lock().expect("lock");
+        loop {
+            if let Some(task) = queue.pop() {
+                return task;
+            }
+            queue = self.ready.wait(queue).expect("lock");
+        }
+    }
+
+    fn done(&self, uuid: &Uuid) {
+        let mut queue = self.queue.lock().expect("lock");
+        if let Some(idx) = queue.running.iter().position(|job| job.uuid == *uuid) {
+            queue.running.swap_remove(idx);
+        }
+        // the order may change with the number of running jobs of the user
+        self.ready.notify_all();
+    }
+
+    pub fn run<F>(self: &Arc<Self>, work: F) -> SchedulerJoinHandle
+    where
+        F: Fn(Task<T>) + Send + Sync + 'static,
+    {
+        let work = Arc::new(work);
+        let join_handles = (0..self.workers)
+            .map(|_| {
+                let scheduler = Arc::clone(self);
+                let work = Arc::clone(&work);
+                std::thread::spawn(move || loop {
+                    let task = scheduler.next();
+                    let uuid = task.uuid;
+                    work(task);
+                    scheduler.done(&uuid);
+                })
+            })
+            .collect();
+        SchedulerJoinHandle { join_handles }
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let queue = self.queue.lock().expect("lock");
+        QueueStats {
+            name: self.name,
+            queued: queue.queued.len(),
+            running: queue.running.len(),
+            workers: self.workers,
+            capacity: self.capacity,
+        }
+    }
+
+    /// The uuids of the jobs of the other users are hidden from `caller`.
+    pub fn view(&self, caller: &Caller) -> QueueView {
+        let queue = self.queue.lock().expect("lock");
+        let now = Instant::now();
+        let mut queued: Vec<_> = queue.queued.iter().collect();
+        queued.sort_by_key(|queued| (Reverse(queued.task.priority), queued.ticket));
+        QueueView {
+            name: self.name,
+            workers: self.workers,
+            capacity: self.capacity,
+            running: queue
+                .running
+                .iter()
+                .map(|job| JobEntry {
+                    uuid: visible_uuid(caller, &job.user, &job.uuid),
+                    user: job.user.clone(),
+                    priority: job.priority,
+                    elapsed: now.duration_since(job.started).as_secs_f64(),
+                })
+                .collect(),
+            queued: queued
+                .into_iter()
+                .map(|queued| JobEntry {
+                    uuid: visible_uuid(caller, &queued.task.user, &queued.task.uuid),
+                    user: queued.task.user.clone(),
+                    priority: queued.task.priority,
+                    elapsed: now.duration_since(queued.submitted).as_secs_f64(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn visible_uuid(caller: &Caller, user: &str, uuid: &Uuid) -> Option<String> {
+    if caller.can_access(user) {
+        Some(uuid.to_string())
+    } else {
+        None
+    }
+}
+
+/// Lengths of a queue.
+pub struct QueueStats {
+    pub name: &'static str,
+    pub queued: usize,
+    pub running: usize,
+    pub workers: usize,
+    pub capacity: usize,
+}
+
+#[derive(Serialize)]
+pub struct JobEntry {
+    uuid: Option<String>,
+    user: String,
+    priority: i32,
+    /// in seconds, since the submission for the queued jobs and since the
+    /// start for the running ones
+    elapsed: f64,
+}
+
+/// Content of a queue, the queued jobs by priority then submission.
+#[derive(Serialize)]
+pub struct QueueView {
+    name: &'static str,
+    workers: usize,
+    capacity: usize,
+    running: Vec<JobEntry>,
+    queued: Vec<JobEntry>,
+}
+
+#[derive(Serialize)]
+pub struct QueuesView {
+    reading: QueueView,
+    solving: QueueView,
+}
+
+#[get("/queue")]
+pub fn queue_view(
+    caller: Caller,
+    reading: rocket::State<Arc<crate::optim::ReadingScheduler>>,
+    solving: rocket::State<Arc<crate::optim::SolvingScheduler>>,
+) -> Json<QueuesView> {
+    Json(QueuesView {
+        reading: reading.view(&caller),
+        solving: solving.view(&caller),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    fn task(user: &str, priority: i32) -> Task<()> {
+        Task {
+            uuid: Uuid::new_v4(),
+            user: user.to_owned(),
+            priority,
+            payload: (),
+        }
+    }
+
+    fn pop(scheduler: &Scheduler<()>) -> Option<(Uuid, String)> {
+        let mut queue = scheduler.queue.lock().unwrap();
+        queue.pop().map(|task| (task.uuid, task.user))
+    }
+
+    #[test]
+    fn users_alternate() {
+        let scheduler = Scheduler::new("test", 1, 10);
+        for user in &["alice", "alice", "alice", "bob", "bob"] {
+            scheduler.submit(task(user, 0)).unwrap();
+        }
+        // with the jobs still running
+        let users: Vec<_> = (0..4).map(|_| pop(&scheduler).unwrap().1).collect();
+        assert_eq!(users, vec!["alice", "bob", "alice", "bob"]);
+
+        // with the jobs done as soon as they start
+        for user in &["bob", "alice", "bob"] {
+            scheduler.submit(task(user, 0)).unwrap();
+        }
+        let mut users = Vec::new();
+        while let Some((uuid, user)) = pop(&scheduler) {
+            scheduler.done(&uuid);
+            users.push(user);
+        }
+        assert_eq!(users, vec!["alice", "bob", "alice", "bob"]);
+
+        // with higher priorities asked by a user who is not an admin
+        let scheduler = Scheduler::new("test", 1, 10);
+        let config = ServerConfig::default();
+        for &(user, priority) in &[("alice", 10), ("alice", 5), ("bob", 0), ("bob", 0)] {
+            let caller = Caller {
+                user: user.to_owned(),
+                admin: false,
+            };
+            let priority = caller.priority(priority, &config);
+            scheduler.submit(task(user, priority)).unwrap();
+        }
+        let users: Vec<_> = (0..4).map(|_| pop(&scheduler).unwrap().1).collect();
+        assert_eq!(users, vec!["alice", "bob", "alice", "bob"]);
+    }
+
+    #[test]
+    fn priority_beats_fairness() {
+        let scheduler = Scheduler::new("test", 1, 10);
+        scheduler.submit(task("alice", 0)).unwrap();
+        assert_eq!(pop(&scheduler).unwrap().1, "alice");
+        scheduler.submit(task("bob", 0)).unwrap();
+        scheduler.submit(task("alice", 1)).unwrap();
+        assert_eq!(pop(&scheduler).unwrap().1, "alice");
+        assert_eq!(pop(&scheduler).unwrap().1, "bob");
+        assert!(pop(&scheduler).is_none());
+    }
+
+    #[test]
+    fn full_queue() {
+        let scheduler = Scheduler::new("test", 1, 2);
+        scheduler.submit(task("alice", 0)).unwrap();
+        scheduler.submit(task("bob", 0)).unwrap();
+        assert!(matches!(scheduler.submit(task("carol", 0)), Err(QueueFull)));
+        assert_eq!(scheduler.stats().queued, 2);
+        // the running jobs do not count
+        pop(&scheduler).unwrap();
+        assert!(scheduler.submit(task("carol", 0)).is_ok());
+    }
+}
@@ -0,0 +1,908 @@
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use rocket::http::RawStr;
+use rocket::request::FromParam;
+use rocket_contrib::json::Json;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::progress::ProgressEvent;
+
+use maintenance::search::{Improvement, SearchControl, SearchParameters, Solver};
+use maintenance::{LoadTimings, MaintenanceOptimization, MaintenanceSummary, Planning};
+use maintenance_db::{Database, NewRunParameters};
+
+/// Recorded with the runs, set `MAINTENANCE_CODE_VERSION` at build time
+/// (e.g. to `git describe`) to tell apart the builds of a same version.
+pub const CODE_VERSION: &str = match option_env!("MAINTENANCE_CODE_VERSION") {
+    Some(version) => version,
+    None => env!("CARGO_PKG_VERSION"),
+};
+
+/// Lifecycle of an uploaded instance.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Loading,
+    Loaded,
+    Failed(String),
+    Solving,
+    Solved,
+}
+
+impl JobStatus {
+    pub const NAMES: [&'static str; 6] =
+        ["queued", "loading", "loaded", "failed", "solving", "solved"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Loading => "loading",
+            JobStatus::Loaded => "loaded",
+            JobStatus::Failed(_) => "failed",
+            JobStatus::Solving => "solving",
+            JobStatus::Solved => "solved",
+        }
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            JobStatus::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Blocking for the progress streams of Rocket, asynchronous for the RPC
+/// connections.
+enum Subscriber {
+    Blocking(Sender<ProgressEvent>),
+    Async(UnboundedSender<ProgressEvent>),
+}
+
+impl Subscriber {
+    /// `false` once the receiver is dropped.
+    fn send(&self, event: ProgressEvent) -> bool {
+        match self {
+            Subscriber::Blocking(sender) => sender.send(event).is_ok(),
+            Subscriber::Async(sender) => sender.unbounded_send(event).is_ok(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Progress {
+    best: Option<(Improvement, Planning)>,
+    history: Vec<Improvement>,
+    subscribers: Vec<Subscriber>,
+    started: Option<Instant>,
+    finished: bool,
+}
+
+impl Progress {
+    fn publish(&mut self, event: ProgressEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()));
+    }
+}
+
+/// Solver run on an instance, shared with the worker running it.
+pub struct SolverRun {
+    pub id: Uuid,
+    pub solver: Solver,
+    pub parameters: SearchParameters,
+    control: SearchControl,
+    progress: Mutex<Progress>,
+}
+
+impl SolverRun {
+    pub fn new(solver: Solver, parameters: SearchParameters) -> Self {
+        SolverRun {
+            id: Uuid::new_v4(),
+            solver,
+            parameters,
+            control: SearchControl::default(),
+            progress: Mutex::new(Progress::default()),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.control.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.control.is_stopped()
+    }
+
+    pub fn control(&self) -> &SearchControl {
+        &self.control
+    }
+
+    /// Called by the worker when the search begins.
+    pub fn start(&self) {
+        self.progress.lock().expect("lock").started = Some(Instant::now());
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.control.iterations()
+    }
+
+    /// Speed of the search while it runs
+    pub fn iterations_per_second(&self) -> Option<f64> {
+        let progress = self.progress.lock().expect("lock");
+        match progress.started {
+            Some(started) if !progress.finished => {
+                let elapsed = started.elapsed().as_secs_f64();
+                Some(self.iterations() as f64 / elapsed.max(1e-3))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn improve(&self, improvement: &Improvement, planning: &Planning) {
+        let mut progress = self.progress.lock().expect("lock");
+        progress.best = Some((improvement.clone(), planning.clone()));
+        progress.history.push(improvement.clone());
+        progress.publish(ProgressEvent::Improvement(improvement.clone()));
+    }
+
+    /// Sends the final event and closes the streams of the subscribers.
+    pub fn finish(&self) {
+        let mut progress = self.progress.lock().expect("lock");
+        progress.finished = true;
+        let event = ProgressEvent::Done {
+            cancelled: self.is_cancelled(),
+        };
+        progress.publish(event);
+        progress.subscribers.clear();
+    }
+
+    /// Events of the run: the improvements already found are replayed first.
+    pub fn subscribe(&self) -> Receiver<ProgressEvent> {
+        let (sender, receiver) = channel();
+        self.add_subscriber(Subscriber::Blocking(sender));
+        receiver
+    }
+
+    /// Same events as `subscribe`, for the asynchronous tasks.
+    pub fn subscribe_async(&self) -> UnboundedReceiver<ProgressEvent> {
+        let (sender, receiver) = unbounded();
+        self.add_subscriber(Subscriber::Async(sender));
+        receiver
+    }
+
+    fn add_subscriber(&self, subscriber: Subscriber) {
+        let mut progress = self.progress.lock().expect("lock");
+        for improvement in progress.history.iter() {
+            subscriber.send(ProgressEvent::Improvement(improvement.clone()));
+        }
+        if progress.finished {
+            subscriber.send(ProgressEvent::Done {
+                cancelled: self.is_cancelled(),
+            });
+        } else {
+            progress.subscribers.push(subscriber);
+        }
+    }
+
+    /// Improvements found so far, in order
+    pub fn history(&self) -> Vec<Improvement> {
+        self.progress.lock().expect("lock").history.clone()
+    }
+
+    /// Best planning found so far
+    pub fn best(&self) -> Option<(Improvement, Planning)> {
+        self.progress.lock().expect("lock").best.clone()
+    }
+}
+
+pub struct Job {
+    /// User of the key that uploaded the instance
+    pub owner: String,
+    pub status: JobStatus,
+    pub instance: Option<Arc<MaintenanceOptimization>>,
+    pub run: Option<Arc<SolverRun>>,
+    /// SHA-256 of the uploaded file, in hexadecimal
+    pub hash: Option<String>,
+    /// Unknown for the instances restored from the database
+    pub timings: LoadTimings,
+    /// Approximate memory held by the instance, in bytes
+    memory_size: usize,
+}
+
+impl Job {
+    fn new(owner: String) -> Self {
+        Job {
+            owner,
+            status: JobStatus::Queued,
+            instance: None,
+            run: None,
+            hash: None,
+            timings: LoadTimings::default(),
+            memory_size: 0,
+        }
+    }
+
+    fn set_instance(
+        &mut self,
+        instance: Arc<MaintenanceOptimization>,
+        hash: String,
+        timings: LoadTimings,
+    ) {
+        self.status = JobStatus::Loaded;
+        self.memory_size = instance.memory_size();
+        self.instance = Some(instance);
+        self.hash = Some(hash);
+        self.timings = timings;
+    }
+
+    pub fn summary(&self) -> Option<MaintenanceSummary> {
+        self.instance.as_ref().map(|instance| MaintenanceSummary {
+            timings: self.timings,
+            hash: self.hash.clone().unwrap_or_default(),
+            ..MaintenanceSummary::new(instance)
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct RunView {
+    solver: &'static str,
+    time_limit: u64,
+    seed: u64,
+    cancelled: bool,
+    cost: Option<f64>,
+    feasible: Option<bool>,
+}
+
+impl RunView {
+    fn new(run: &SolverRun) -> Self {
+        let best = run.best();
+        RunView {
+            solver: run.solver.name(),
+            time_limit: run.parameters.time_limit.as_secs(),
+            seed: run.parameters.seed,
+            cancelled: run.is_cancelled(),
+            cost: best.as_ref().map(|(improvement, _)| improvement.cost),
+            feasible: best.as_ref().map(|(improvement, _)| improvement.feasible),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JobView {
+    uuid: String,
+    owner: String,
+    hash: Option<String>,
+    status: &'static str,
+    error: Option<String>,
+    summary: Option<MaintenanceSummary>,
+    run: Option<RunView>,
+}
+
+impl JobView {
+    fn new(uuid: &Uuid, job: &Job) -> Self {
+        JobView {
+            uuid: uuid.to_string(),
+            owner: job.owner.clone(),
+            hash: job.hash.clone(),
+            status: job.status.name(),
+            error: job.status.error().map(str::to_owned),
+            summary: job.summary(),
+            run: job.run.as_ref().map(|run| RunView::new(run)),
+        }
+    }
+}
+
+// NOTE(vincent): the uuids are sent by the clients, the set is cleared once
+// full so that random ones cannot grow it without bound.
+const MISSING_CAPACITY: usize = 4096;
+
+#[derive(Debug)]
+pub enum RunError {
+    UnknownJob,
+    NotReady(JobStatus),
+}
+
+/// State of a job before a new run, put back when the run cannot be queued.
+pub struct PreviousRun {
+    status: JobStatus,
+    run: Option<Arc<SolverRun>>,
+}
+
+/// File being parsed by a reading worker.
+#[derive(Debug, Default)]
+pub struct Parsing {
+    done: Mutex<bool>,
+    ready: Condvar,
+}
+
+impl Parsing {
+    /// Blocks until the file is parsed, or failed to.
+    pub fn wait(&self) {
+        let mut done = self.done.lock().expect("lock");
+        while !*done {
+            done = self.ready.wait(done).expect("lock");
+        }
+    }
+}
+
+/// Held by the only worker parsing a file, the other ones wait for it.
+pub struct ParsingGuard<'a> {
+    store: &'a JobStore,
+    hash: String,
+    parsing: Arc<Parsing>,
+}
+
+impl Drop for ParsingGuard<'_> {
+    fn drop(&mut self) {
+        self.store.parsing.lock().expect("lock").remove(&self.hash);
+        *self.parsing.done.lock().expect("lock") = true;
+        self.parsing.ready.notify_all();
+    }
+}
+
+/// Instances uploaded on the server, keyed by the uuid sent back to the client.
+#[derive(Default)]
+pub struct JobStore {
+    jobs: RwLock<HashMap<Uuid, Job>>,
+    /// Keeps the instances and the results of the runs across restarts
+    database: Option<Arc<Database>>,
+    /// Uuids not in the database, not to look them up on each request
+    missing: Mutex<HashSet<Uuid>>,
+    /// Hashes of the files being parsed
+    parsing: Mutex<HashMap<String, Arc<Parsing>>>,
+}
+
+impl JobStore {
+    pub fn with_database(database: Arc<Database>) -> Self {
+        JobStore {
+            jobs: RwLock::default(),
+            database: Some(database),
+            missing: Mutex::default(),
+            parsing: Mutex::default(),
+        }
+    }
+
+    fn set_missing(&self, uuid: &Uuid) {
+        let mut missing = self.missing.lock().expect("lock");
+        if missing.len() >= MISSING_CAPACITY {
+            missing.clear();
+        }
+        missing.insert(*uuid);
+    }
+
+    pub fn database(&self) -> Option<&Database> {
+        self.database.as_ref().map(|database| &**database)
+    }
+
+    /// Reloads an instance stored by a previous server as a loaded job.
+    fn restore(&self, uuid: &Uuid) {
+        let database = match self.database {
+            Some(ref database) => database,
+            None => return,
+        };
+        if self.jobs.read().expect("lock").contains_key(uuid)
+            || self.missing.lock().expect("lock").contains(uuid)
+        {
+            return;
+        }
+        let row = match database.instance_row(uuid) {
+            Ok(Some(row)) => row,
+            Ok(None) => return self.set_missing(uuid),
+            Err(err) => {
+                eprintln!("cannot restore instance {}: {}", uuid, err);
+                return;
+            }
+        };
+        match database.instance(uuid) {
+            Ok(Some(instance)) => {
+                let mut job = Job::new(row.owner);
+                job.set_instance(Arc::new(instance), row.hash, LoadTimings::default());
+                self.jobs.write().expect("lock").entry(*uuid).or_insert(job);
+            }
+            Ok(None) => self.set_missing(uuid),
+            Err(err) => eprintln!("cannot restore instance {}: {}", uuid, err),
+        }
+    }
+
+    pub fn insert(&self, uuid: Uuid, owner: &str) {
+        self.missing.lock().expect("lock").remove(&uuid);
+        self.jobs
+            .write()
+            .expect("lock")
+            .insert(uuid, Job::new(owner.to_owned()));
+    }
+
+    pub fn remove(&self, uuid: &Uuid) {
+        self.jobs.write().expect("lock").remove(uuid);
+    }
+
+    pub fn set_status(&self, uuid: &Uuid, status: JobStatus) {
+        if let Some(job) = self.jobs.write().expect("lock").get_mut(uuid) {
+            job.status = status;
+        }
+    }
+
+    /// `instance` may be shared with the other jobs uploaded from the same
+    /// file, identified by its `hash`.
+    pub fn set_instance(
+        &self,
+        uuid: &Uuid,
+        name: &str,
+        hash: &str,
+        timings: LoadTimings,
+        instance: Arc<MaintenanceOptimization>,
+    ) {
+        let owner = match self.owner(uuid) {
+            Some(owner) => owner,
+            None => return,
+        };
+        if let Some(ref database) = self.database {
+            if let Err(err) = database.insert_instance(uuid, name, &owner, hash, &instance) {
+                eprintln!("cannot store instance {}: {}", uuid, err);
+            }
+        }
+        if let Some(job) = self.jobs.write().expect("lock").get_mut(uuid) {
+            job.set_instance(instance, hash.to_owned(), timings);
+        }
+    }
+
+    /// New job on the instance of the job `source`, stored as a reference
+    /// to the data of `source`.
+    pub fn set_reference(
+        &self,
+        uuid: &Uuid,
+        name: &str,
+        source: &Uuid,
+        timings: LoadTimings,
+        instance: Arc<MaintenanceOptimization>,
+    ) {
+        let (owner, hash) = match (self.owner(uuid), self.hash(source)) {
+            (Some(owner), Some(hash)) => (owner, hash),
+            _ => return,
+        };
+        if let Some(ref database) = self.database {
+            if let Err(err) = database.insert_reference(uuid, name, &owner, source) {
+                eprintln!("cannot store instance {}: {}", uuid, err);
+            }
+        }
+        if let Some(job) = self.jobs.write().expect("lock").get_mut(uuid) {
+            job.set_instance(instance, hash, timings);
+        }
+    }
+
+    /// Reserves the parsing of the file with this `hash`, or gives the
+    /// parsing to wait for when another worker holds it.
+    pub fn start_parsing(&self, hash: &str) -> Result<ParsingGuard<'_>, Arc<Parsing>> {
+        let mut parsing = self.parsing.lock().expect("lock");
+        if let Some(pending) = parsing.get(hash) {
+            return Err(Arc::clone(pending));
+        }
+        let reserved = Arc::new(Parsing::default());
+        parsing.insert(hash.to_owned(), Arc::clone(&reserved));
+        Ok(ParsingGuard {
+            store: self,
+            hash: hash.to_owned(),
+            parsing: reserved,
+        })
+    }
+
+    /// Job with an instance loaded from a file with this `hash`, restored
+    /// from the database if needed. Only the jobs `caller` can access are
+    /// looked at, all of them without caller.
+    pub fn find_by_hash(
+        &self,
+        hash: &str,
+        caller: Option<&Caller>,
+    ) -> Option<(Uuid, Arc<MaintenanceOptimization>)> {
+        let can_access = |owner: &str| caller.map_or(true, |caller| caller.can_access(owner));
+        let loaded = self
+            .jobs
+            .read()
+            .expect("lock")
+            .iter()
+            .filter(|(_, job)| job.hash.as_ref().map(String::as_str) == Some(hash))
+            .filter(|(_, job)| can_access(&job.owner))
+            .find_map(|(uuid, job)| job.instance.clone().map(|instance| (*uuid, instance)));
+        if loaded.is_some() {
+            return loaded;
+        }
+        let database = self.database.as_ref()?;
+        let of_owner = match caller {
+            Some(caller) if !caller.admin => Some(caller.user.as_str()),
+            _ => None,
+        };
+        match database.instance_by_hash(hash, of_owner) {
+            Ok(Some(uuid)) => self.instance(&uuid).map(|instance| (uuid, instance)),
+            Ok(None) => None,
+            Err(err) => {
+                eprintln!("cannot look for instance {}: {}", hash, err);
+                None
+            }
+        }
+    }
+
+    pub fn owner(&self, uuid: &Uuid) -> Option<String> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .map(|job| job.owner.clone())
+    }
+
+    /// Only the owner of a job or an admin may see or change it.
+    pub fn authorize(&self, uuid: &Uuid, caller: &Caller) -> Result<(), ApiError> {
+        let owner = self.owner(uuid).ok_or_else(ApiError::unknown_job)?;
+        caller.check_access(&owner)
+    }
+
+    /// Name of the uploaded file, only kept in the database
+    pub fn name(&self, uuid: &Uuid) -> Option<String> {
+        match self.database()?.instance_row(uuid) {
+            Ok(row) => row.map(|row| row.name),
+            Err(err) => {
+                eprintln!("cannot read instance {}: {}", uuid, err);
+                None
+            }
+        }
+    }
+
+    fn hash(&self, uuid: &Uuid) -> Option<String> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .and_then(|job| job.hash.clone())
+    }
+
+    pub fn status(&self, uuid: &Uuid) -> Option<JobStatus> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .map(|job| job.status.clone())
+    }
+
+    pub fn instance(&self, uuid: &Uuid) -> Option<Arc<MaintenanceOptimization>> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .and_then(|job| job.instance.clone())
+    }
+
+    /// Once the instance is loaded
+    pub fn summary(&self, uuid: &Uuid) -> Option<MaintenanceSummary> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .and_then(Job::summary)
+    }
+
+    /// Attach a new run to a loaded instance.
+    pub fn start_run(&self, uuid: &Uuid, run: Arc<SolverRun>) -> Result<PreviousRun, RunError> {
+        self.restore(uuid);
+        let mut jobs = self.jobs.write().expect("lock");
+        let job = jobs.get_mut(uuid).ok_or(RunError::UnknownJob)?;
+        match job.status {
+            JobStatus::Loaded | JobStatus::Solved => Ok(PreviousRun {
+                status: std::mem::replace(&mut job.status, JobStatus::Solving),
+                run: std::mem::replace(&mut job.run, Some(run)),
+            }),
+            ref status => Err(RunError::NotReady(status.clone())),
+        }
+    }
+
+    /// Detach a run that never started, the job gets back its previous run.
+    pub fn undo_run(&self, uuid: &Uuid, run: &SolverRun, previous: PreviousRun) {
+        let mut jobs = self.jobs.write().expect("lock");
+        if let Some(job) = jobs.get_mut(uuid) {
+            if job.run.as_ref().map(|current| current.id) == Some(run.id) {
+                job.status = previous.status;
+                job.run = previous.run;
+            }
+        }
+    }
+
+    pub fn run(&self, uuid: &Uuid) -> Option<Arc<SolverRun>> {
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .and_then(|job| job.run.clone())
+    }
+
+    /// Stores the parameters of a run about to start.
+    pub fn record_run(&self, uuid: &Uuid, run: &SolverRun) {
+        if let Some(ref database) = self.database {
+            let parameters = NewRunParameters {
+                id: &run.id,
+                instance_id: uuid,
+                solver: run.solver.name(),
+                time_limit: run.parameters.time_limit.as_secs(),
+                seed: run.parameters.seed,
+                code_version: CODE_VERSION,
+            };
+            if let Err(err) = database.insert_run(&parameters) {
+                eprintln!("cannot store run {}: {}", run.id, err);
+            }
+        }
+    }
+
+    /// Stores the outcome of a run with its best planning.
+    pub fn record_result(&self, run: &SolverRun, status: &str) {
+        if let Some(ref database) = self.database {
+            let best = run.best();
+            let best = best
+                .as_ref()
+                .map(|(improvement, planning)| (improvement, planning));
+            if let Err(err) = database.finish_run(&run.id, status, best, &run.history()) {
+                eprintln!("cannot store result of run {}: {}", run.id, err);
+            }
+        }
+    }
+
+    /// Number of jobs in each state
+    pub fn count_by_status(&self) -> Vec<(&'static str, usize)> {
+        let jobs = self.jobs.read().expect("lock");
+        JobStatus::NAMES
+            .iter()
+            .map(|&name| {
+                let count = jobs
+                    .values()
+                    .filter(|job| job.status.name() == name)
+                    .count();
+                (name, count)
+            })
+            .collect()
+    }
+
+    /// Runs being solved, seen while no run ends.
+    pub fn with_active_runs<R, F: FnOnce(&[Arc<SolverRun>]) -> R>(&self, f: F) -> R {
+        let jobs = self.jobs.read().expect("lock");
+        let runs: Vec<_> = jobs
+            .values()
+            .filter(|job| job.status == JobStatus::Solving)
+            .filter_map(|job| job.run.clone())
+            .collect();
+        f(&runs)
+    }
+
+    /// Ends the run of a job, `count` is called with the status change.
+    pub fn finish_run<F: FnOnce()>(&self, uuid: &Uuid, status: JobStatus, count: F) {
+        let mut jobs = self.jobs.write().expect("lock");
+        count();
+        if let Some(job) = jobs.get_mut(uuid) {
+            job.status = status;
+        }
+    }
+
+    /// Approximate memory held by the loaded instances, in bytes, the
+    /// instances shared between jobs are counted once
+    pub fn instances_memory(&self) -> usize {
+        let jobs = self.jobs.read().expect("lock");
+        let mut seen = HashSet::new();
+        jobs.values()
+            .filter(|job| match job.instance {
+                Some(ref instance) => seen.insert(Arc::as_ptr(instance)),
+                None => true,
+            })
+            .map(|job| job.memory_size)
+            .sum()
+    }
+
+    /// Jobs visible to `caller`
+    pub fn list(&self, caller: &Caller) -> Vec<JobView> {
+        let mut views: Vec<_> = self
+            .jobs
+            .read()
+            .expect("lock")
+            .iter()
+            .filter(|(_, job)| caller.can_access(&job.owner))
+            .map(|(uuid, job)| JobView::new(uuid, job))
+            .collect();
+        views.sort_by(|left, right| left.uuid.cmp(&right.uuid));
+        views
+    }
+
+    pub fn view(&self, uuid: &Uuid) -> Option<JobView> {
+        self.restore(uuid);
+        self.jobs
+            .read()
+            .expect("lock")
+            .get(uuid)
+            .map(|job| JobView::new(uuid, job))
+    }
+}
+
+/// Uuid of a job as a route parameter.
+pub struct JobId(pub Uuid);
+
+impl<'a> FromParam<'a> for JobId {
+    type Error = &'a RawStr;
+
+    fn from_param(param: &'a RawStr) -> Result<Self, Self::Error> {
+        Uuid::parse_str(param.as_str())
+            .map(JobId)
+            .map_err(|_| param)
+    }
+}
+
+/// Jobs of the caller, of every user for an admin.
+#[get("/optim")]
+pub fn list_jobs(caller: Caller, store: rocket::State<Arc<JobStore>>) -> Json<Vec<JobView>> {
+    Json(store.list(&caller))
+}
+
+#[get("/optim/<uuid>")]
+pub fn job_status(
+    uuid: JobId,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+) -> Result<Json<JobView>, ApiError> {
+    store.authorize(&uuid.0, &caller)?;
+    store
+        .view(&uuid.0)
+        .map(Json)
+        .ok_or_else(ApiError::unknown_job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// One intervention over two days
+    const INSTANCE: &str = r#"{
+        "Resources": {"c1": {"min": [0.0, 0.0], "max": [1.0, 1.0]}},
+        "Seasons": {"winter": [], "summer": [], "is": []},
+        "Interventions": {
+            "I1": {"tmax": "2", "Delta": [1.0, 1.0],
+                   "workload": {"c1": {"1": {"1": 1.0}, "2": {"2": 1.0}}},
+                   "risk": {"1": {"1": [1.0]}, "2": {"2": [2.0]}}}
+        },
+        "Exclusions": {},
+        "T": 2,
+        "Scenarios_number": [1, 1],
+        "Quantile": 0.5,
+        "Alpha": 0.5,
+        "ComputationTime": 60
+    }"#;
+
+    fn instance() -> Arc<MaintenanceOptimization> {
+        let m = maintenance_json::read_json(INSTANCE).unwrap();
+        Arc::new(maintenance_json::load_instance(m).unwrap())
+    }
+
+    fn caller(user: &str, admin: bool) -> Caller {
+        Caller {
+            user: user.to_owned(),
+            admin,
+        }
+    }
+
+    fn run() -> Arc<SolverRun> {
+        let parameters = SearchParameters {
+            time_limit: Duration::from_secs(1),
+            seed: 0,
+        };
+        Arc::new(SolverRun::new(Solver::Greedy, parameters))
+    }
+
+    #[test]
+    fn undo_run() {
+        let store = JobStore::default();
+        let uuid = Uuid::new_v4();
+        store.insert(uuid, "alice");
+        assert!(matches!(
+            store.start_run(&uuid, run()),
+            Err(RunError::NotReady(JobStatus::Queued))
+        ));
+
+        store.set_status(&uuid, JobStatus::Solved);
+        let solved = run();
+        store.start_run(&uuid, Arc::clone(&solved)).unwrap();
+        store.set_status(&uuid, JobStatus::Solved);
+
+        let next = run();
+        let previous = store.start_run(&uuid, Arc::clone(&next)).unwrap();
+        assert_eq!(store.status(&uuid), Some(JobStatus::Solving));
+        store.undo_run(&uuid, &next, previous);
+        assert_eq!(store.status(&uuid), Some(JobStatus::Solved));
+        assert_eq!(store.run(&uuid).map(|run| run.id), Some(solved.id));
+    }
+
+    #[test]
+    fn jobs_of_their_owner() {
+        let store = JobStore::default();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert(alice, "alice");
+        store.insert(bob, "bob");
+        let jobs = store.list(&caller("alice", false));
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].uuid, alice.to_string());
+        assert_eq!(jobs[0].owner, "alice");
+        assert_eq!(store.list(&caller("root", true)).len(), 2);
+
+        assert!(store.authorize(&alice, &caller("alice", false)).is_ok());
+        assert!(store.authorize(&bob, &caller("root", true)).is_ok());
+        let denied = store.authorize(&bob, &caller("alice", false)).unwrap_err();
+        assert_eq!(denied.code(), "forbidden");
+        let unknown = store
+            .authorize(&Uuid::new_v4(), &caller("root", true))
+            .unwrap_err();
+        assert_eq!(unknown.code(), "unknown_job");
+    }
+
+    #[test]
+    fn instances_shared_by_hash() {
+        let store = JobStore::default();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert(alice, "alice");
+        store.set_instance(
+            &alice,
+            "small.json",
+            "hash",
+            LoadTimings::default(),
+            instance(),
+        );
+        assert_eq!(store.status(&alice), Some(JobStatus::Loaded));
+        assert!(store.find_by_hash("other", None).is_none());
+        let (source, shared) = store.find_by_hash("hash", None).unwrap();
+        assert_eq!(source, alice);
+        // only the jobs the caller can access
+        assert!(store
+            .find_by_hash("hash", Some(&caller("bob", false)))
+            .is_none());
+        assert!(store
+            .find_by_hash("hash", Some(&caller("root", true)))
+            .is_some());
+
+        store.insert(bob, "bob");
+        store.set_reference(&bob, "small.json", &alice, LoadTimings::default(), shared);
+        let view = store.view(&bob).unwrap();
+        assert_eq!(view.status, "loaded");
+        assert_eq!(view.hash.as_deref(), Some("hash"));
+        assert!(Arc::ptr_eq(
+            &store.instance(&alice).unwrap(),
+            &store.instance(&bob).unwrap()
+        ));
+        let (source, _) = store
+            .find_by_hash("hash", Some(&caller("bob", false)))
+            .unwrap();
+        assert_eq!(source, bob);
+        // the memory of a shared instance is counted once
+        assert_eq!(
+            store.instances_memory(),
+            store.instance(&alice).unwrap().memory_size()
+        );
+    }
+
+    #[test]
+    fn parsed_once() {
+        let store = JobStore::default();
+        let parsing = store.start_parsing("hash").unwrap();
+        let pending = match store.start_parsing("hash") {
+            Ok(_) => panic!("the file is already being parsed"),
+            Err(pending) => pending,
+        };
+        assert!(store.start_parsing("other").is_ok());
+        let waiting = std::thread::spawn(move || pending.wait());
+        drop(parsing);
+        waiting.join().unwrap();
+        assert!(store.start_parsing("hash").is_ok());
+    }
+}
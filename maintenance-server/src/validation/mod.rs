@@ -0,0 +1,181 @@
+//! Check of a solution file against a loaded job.
+use rocket::http::{ContentType, Status};
+use rocket::Data;
+use rocket_contrib::json::Json;
+use rocket_multipart_form_data::{
+    MultipartFormData, MultipartFormDataField, MultipartFormDataOptions,
+};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use maintenance::validation::{validate, Violation};
+use maintenance::{MaintenanceOptimization, IID};
+use maintenance_json::PlanningError;
+
+use crate::auth::Caller;
+use crate::config::ServerConfig;
+use crate::error::ApiError;
+use crate::store::JobStore;
+
+/// Days start at 1 as in the instances.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ViolationView<'a> {
+    Duplicated {
+        intervention: &'a str,
+    },
+    Unplanned {
+        intervention: &'a str,
+    },
+    LateStart {
+        intervention: &'a str,
+        day: usize,
+    },
+    Workload {
+        resource: &'a str,
+        day: usize,
+        workload: f64,
+        min: f64,
+        max: f64,
+    },
+    Exclusion {
+        interventions: [&'a str; 2],
+        start: usize,
+        end: usize,
+    },
+}
+
+impl<'a> ViolationView<'a> {
+    fn new(instance: &'a MaintenanceOptimization, violation: &Violation) -> Self {
+        let intervention = |iid: IID| instance.mapping.interventions[&iid].as_str();
+        match *violation {
+            Violation::Duplicated(iid) => ViolationView::Duplicated {
+                intervention: intervention(iid),
+            },
+            Violation::Unplanned(iid) => ViolationView::Unplanned {
+                intervention: intervention(iid),
+            },
+            Violation::LateStart { iid, day } => ViolationView::LateStart {
+                intervention: intervention(iid),
+                day: day.get() + 1,
+            },
+            Violation::Workload {
+                rid,
+                day,
+                workload,
+                min,
+                max,
+            } => ViolationView::Workload {
+                resource: &instance.mapping.resources[&rid],
+                day: day.get() + 1,
+                workload,
+                min,
+                max,
+            },
+            Violation::Exclusion {
+                first,
+                second,
+                period,
+            } => {
+                let (start, end) = period.days();
+                ViolationView::Exclusion {
+                    interventions: [intervention(first), intervention(second)],
+                    start: start.get() + 1,
+                    end: end.get() + 1,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ValidationView<'a> {
+    feasible: bool,
+    cost: f64,
+    obj1: f64,
+    obj2: f64,
+    /// risks of each day
+    mean_risks: Box<[f64]>,
+    quantile_risks: Box<[f64]>,
+    excess_risks: Box<[f64]>,
+    violations: Vec<ViolationView<'a>>,
+}
+
+fn read_file(path: &Path) -> Result<String, ApiError> {
+    std::fs::read_to_string(path)
+        .map_err(|err| ApiError::bad_request("unreadable_file", err.to_string()))
+}
+
+/// Takes the `uuid` of a loaded job and the `solution` file in the format of
+/// the challenge.
+// NOTE(vincent): no instance file, reading one would hold a Rocket worker:
+// it is uploaded first through `/optim/new`, queued with the other readings.
+#[post("/validate", data = "<data>")]
+pub fn validate_solution(
+    content_type: &ContentType,
+    data: Data,
+    caller: Caller,
+    store: rocket::State<Arc<JobStore>>,
+    config: rocket::State<ServerConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+        MultipartFormDataField::text("uuid"),
+        MultipartFormDataField::file("solution").size_limit(config.solution_size_limit),
+    ]);
+    options.temporary_dir = config.upload_dir();
+    let mut data = MultipartFormData::parse(content_type, data, options)?;
+    let uuid = data
+        .texts
+        .remove("uuid")
+        .ok_or_else(|| ApiError::bad_request("missing_uuid", "no `uuid` in the form"))?;
+    let uuid = Uuid::parse_str(uuid[0].text.trim())
+        .map_err(|err| ApiError::bad_request("invalid_uuid", err.to_string()))?;
+    store.authorize(&uuid, &caller)?;
+    let instance = store
+        .instance(&uuid)
+        .ok_or_else(|| match store.status(&uuid) {
+            Some(status) => ApiError::not_ready(&status),
+            None => ApiError::unknown_job(),
+        })?;
+    let solution = data
+        .files
+        .remove("solution")
+        .ok_or_else(|| ApiError::missing_file("solution"))?;
+    let solution = read_file(&solution[0].path)?;
+    let planning =
+        maintenance_json::read_planning(&solution, &instance.mapping).map_err(|err| match err {
+            PlanningError::Syntax(line) => ApiError::new(
+                Status::UnprocessableEntity,
+                "invalid_solution",
+                format!("line {} is not `intervention day`", line),
+            ),
+            PlanningError::UnknownIntervention(name) => ApiError::new(
+                Status::UnprocessableEntity,
+                "invalid_solution",
+                format!("unknown intervention `{}`", name),
+            ),
+        })?;
+    let validation = validate(&instance.maintenance, &planning);
+    let feasible = validation.is_feasible();
+    let report = validation.report;
+    let view = ValidationView {
+        feasible,
+        cost: report.cost,
+        obj1: report.obj1,
+        obj2: report.obj2,
+        mean_risks: report.mean_risks,
+        quantile_risks: report.quantile_risks,
+        excess_risks: report.excess_risks,
+        violations: validation
+            .violations
+            .iter()
+            .map(|violation| ViolationView::new(&instance, violation))
+            .collect(),
+    };
+    // NOTE(vincent): the view borrows the instance, which may only live here.
+    serde_json::to_value(&view)
+        .map(Json)
+        .map_err(|err| ApiError::internal(err.to_string()))
+}
@@ -0,0 +1,180 @@
+use maintenance::validation::{resources_usage, risk_profile, ResourceUsage};
+use maintenance::{CostReport, Day, MaintenanceOptimization, Planning, RID};
+use wasm_bindgen::prelude::*;
+
+/// Workloads of each resource by day against its bounds, one chart by
+/// resource.
+#[wasm_bindgen]
+pub struct ResourceCharts {
+    names: Vec<String>,
+    usage: Vec<ResourceUsage>,
+}
+
+impl ResourceCharts {
+    pub fn new(instance: &MaintenanceOptimization, planning: &Planning) -> Self {
+        let usage = resources_usage(&instance.maintenance, planning);
+        ResourceCharts {
+            names: (0..usage.len())
+                .map(|rid| instance.mapping.resources.get(&RID::new(rid)).cloned())
+                .map(Option::unwrap_or_default)
+                .collect(),
+            usage,
+        }
+    }
+
+    fn resource(&self, rid: u32) -> Option<&ResourceUsage> {
+        self.usage.get(rid as usize)
+    }
+}
+
+#[wasm_bindgen]
+impl ResourceCharts {
+    pub fn nresources(&self) -> u32 {
+        self.usage.len() as u32
+    }
+
+    pub fn name(&self, rid: u32) -> Option<String> {
+        self.names.get(rid as usize).cloned()
+    }
+
+    pub fn workloads(&self, rid: u32) -> Box<[f64]> {
+        self.resource(rid)
+            .map(|usage| usage.workloads.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn min(&self, rid: u32) -> Box<[f64]> {
+        self.resource(rid)
+            .map(|usage| usage.min.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn max(&self, rid: u32) -> Box<[f64]> {
+        self.resource(rid)
+            .map(|usage| usage.max.clone())
+            .unwrap_or_default()
+    }
+
+    /// Days, from 0, where the workload is out of the bounds
+    pub fn violations(&self, rid: u32) -> Box<[u32]> {
+        self.resource(rid)
+            .map(|usage| {
+                usage
+                    .violations
+                    .iter()
+                    .map(|day| day.get() as u32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Risks of a planning by day, with the risk of each scenario of one day.
+#[wasm_bindgen]
+pub struct RiskChart {
+    report: CostReport,
+    day: Option<u32>,
+    scenarios: Box<[f64]>,
+}
+
+impl RiskChart {
+    pub fn new(instance: &MaintenanceOptimization, planning: &Planning, day: Option<u32>) -> Self {
+        let profile = risk_profile(
+            &instance.maintenance,
+            planning,
+            day.map(|day| Day::new(day as usize)),
+        );
+        RiskChart {
+            report: profile.report,
+            day: profile.day.map(|day| day.get() as u32),
+            scenarios: profile.scenarios,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl RiskChart {
+    pub fn cost(&self) -> f64 {
+        self.report.cost
+    }
+
+    pub fn obj1(&self) -> f64 {
+        self.report.obj1
+    }
+
+    pub fn obj2(&self) -> f64 {
+        self.report.obj2
+    }
+
+    pub fn mean_risks(&self) -> Box<[f64]> {
+        self.report.mean_risks.clone()
+    }
+
+    pub fn quantile_risks(&self) -> Box<[f64]> {
+        self.report.quantile_risks.clone()
+    }
+
+    pub fn excess_risks(&self) -> Box<[f64]> {
+        self.report.excess_risks.clone()
+    }
+
+    /// Day of the scenarios, from 0, none if out of the horizon
+    pub fn day(&self) -> Option<u32> {
+        self.day
+    }
+
+    /// Risk of each scenario of the day
+    pub fn scenarios(&self) -> Box<[f64]> {
+        self.scenarios.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::gantt::planning_from_days;
+    use maintenance::validation::validate;
+
+    #[test]
+    fn workloads_against_bounds() {
+        let instance = fixtures::small();
+        // I1 on days 0 and 1, I2 on day 1
+        let charts = ResourceCharts::new(&instance, &planning_from_days(&instance, &[0, 1]));
+        assert_eq!(charts.nresources(), 1);
+        assert_eq!(charts.name(0).as_deref(), Some("c1"));
+        assert_eq!(&*charts.workloads(0), &[2.0, 5.0, 0.0, 0.0, 0.0]);
+        assert_eq!(&*charts.min(0), &[1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(&*charts.max(0), &[4.0; 5]);
+        assert_eq!(&*charts.violations(0), &[1]);
+
+        let empty = ResourceCharts::new(&instance, &planning_from_days(&instance, &[]));
+        assert_eq!(&*empty.violations(0), &[0]);
+
+        // unknown resource
+        assert_eq!(charts.name(1), None);
+        assert!(charts.workloads(1).is_empty());
+        assert!(charts.violations(1).is_empty());
+    }
+
+    #[test]
+    fn risks_as_validated() {
+        let instance = fixtures::small();
+        let planning = planning_from_days(&instance, &[0, 1]);
+        let report = validate(&instance.maintenance, &planning).report;
+        let chart = RiskChart::new(&instance, &planning, Some(1));
+        assert_eq!(chart.cost(), report.cost);
+        assert_eq!(chart.obj1(), report.obj1);
+        assert_eq!(chart.obj2(), report.obj2);
+        assert_eq!(chart.mean_risks(), report.mean_risks);
+        assert_eq!(chart.quantile_risks(), report.quantile_risks);
+        assert_eq!(chart.excess_risks(), report.excess_risks);
+        assert_eq!(chart.day(), Some(1));
+        // I1 on its second day, I2 on its only day
+        assert_eq!(&*chart.scenarios(), &[2.0, 3.0]);
+
+        let chart = RiskChart::new(&instance, &planning, Some(5));
+        assert_eq!(chart.day(), None);
+        assert!(chart.scenarios().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use maintenance::search::{LocalSearch, MoveReport};
+use maintenance::{Day, MaintenanceOptimization, Planning, IID};
+use wasm_bindgen::prelude::*;
+
+use crate::gantt::{planning_from_days, Gantt};
+
+/// Feasibility of a move and change of the objectives, see `MoveReport`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct MoveEvaluation {
+    pub intervention: u32,
+    pub day: u32,
+    pub latest_start: bool,
+    pub max_workloads: bool,
+    pub exclusions: bool,
+    pub min_workloads: bool,
+    pub feasible: bool,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub cost: f64,
+    pub delta_obj1: f64,
+    pub delta_obj2: f64,
+    pub delta_cost: f64,
+}
+
+impl From<MoveReport> for MoveEvaluation {
+    fn from(report: MoveReport) -> Self {
+        MoveEvaluation {
+            intervention: report.iid.get() as u32,
+            day: report.day.get() as u32,
+            latest_start: report.latest_start,
+            max_workloads: report.max_workloads,
+            exclusions: report.exclusions,
+            min_workloads: report.min_workloads,
+            feasible: report.is_feasible(),
+            obj1: report.obj1,
+            obj2: report.obj2,
+            cost: report.cost,
+            delta_obj1: report.delta_obj1,
+            delta_obj2: report.delta_obj2,
+            delta_cost: report.delta_cost,
+        }
+    }
+}
+
+/// Planning edited by hand, each move updates the state of the search
+/// instead of evaluating the whole planning again.
+#[wasm_bindgen]
+pub struct Editor {
+    // NOTE(vincent): declared first to be dropped before the instance it
+    // borrows.
+    search: LocalSearch<'static>,
+    instance: Rc<MaintenanceOptimization>,
+}
+
+impl Editor {
+    pub fn new(instance: Rc<MaintenanceOptimization>, planning: &Planning) -> Self {
+        // SAFETY: the instance is kept alive, and never moved, by the `Rc`
+        // held next to the search, which is dropped first.
+        let maintenance = unsafe { &*(&instance.maintenance as *const _) };
+        Editor {
+            search: LocalSearch::from_planning(maintenance, planning),
+            instance,
+        }
+    }
+
+    pub fn from_days(instance: Rc<MaintenanceOptimization>, days: &[i32]) -> Self {
+        let planning = planning_from_days(&instance, days);
+        Editor::new(instance, &planning)
+    }
+
+    fn intervention(&self, iid: u32) -> Option<IID> {
+        if (iid as usize) < self.instance.maintenance.ninterventions() {
+            Some(IID::new(iid as usize))
+        } else {
+            None
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Editor {
+    /// Feasibility and cost of moving `intervention` to start at `day`,
+    /// the planning is unchanged.
+    pub fn evaluate(&mut self, intervention: u32, day: u32) -> Option<MoveEvaluation> {
+        let iid = self.intervention(intervention)?;
+        Some(
+            self.search
+                .evaluate_move(iid, Day::new(day as usize))
+                .into(),
+        )
+    }
+
+    /// Moves `intervention` to start at `day`, even if the planning becomes
+    /// infeasible, unless the day is after its latest start.
+    pub fn apply(&mut self, intervention: u32, day: u32) -> Option<MoveEvaluation> {
+        let iid = self.intervention(intervention)?;
+        Some(
+            self.search
+                .move_intervention(iid, Day::new(day as usize))
+                .into(),
+        )
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.search.cost()
+    }
+
+    /// Every intervention planned and minimum workloads reached
+    pub fn is_feasible(&self) -> bool {
+        self.search.is_feasible()
+    }
+
+    /// Start day of each intervention, from 0, -1 when it is not planned.
+    pub fn planning(&self) -> Box<[i32]> {
+        (0..self.instance.maintenance.ninterventions())
+            .map(|iid| {
+                self.search
+                    .start_day(IID::new(iid))
+                    .map_or(-1, |day| day.get() as i32)
+            })
+            .collect()
+    }
+
+    pub fn gantt(&self) -> Gantt {
+        Gantt::new(&self.instance, &self.search.current_planning())
+    }
+
+    /// Edited planning in the format of the challenge.
+    pub fn export_planning(&self) -> String {
+        maintenance_json::ExportPlanning {
+            planning: &self.search.current_planning(),
+            mapping: &self.instance.mapping,
+        }
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn evaluate_then_apply() {
+        // I1 on days 0 and 1, I2 not planned
+        let mut editor = Editor::from_days(Rc::new(fixtures::small()), &[0, -1]);
+        assert!(!editor.is_feasible());
+        let cost = editor.cost();
+
+        // over the maximum workload with I1
+        let evaluation = editor.evaluate(1, 1).unwrap();
+        assert!(!evaluation.max_workloads);
+        assert!(!evaluation.feasible);
+
+        let evaluation = editor.evaluate(1, 2).unwrap();
+        assert!(evaluation.feasible);
+        assert_eq!(evaluation.delta_cost, evaluation.cost - cost);
+        assert_eq!(&*editor.planning(), &[0, -1]);
+        assert_eq!(editor.cost(), cost);
+
+        let applied = editor.apply(1, 2).unwrap();
+        assert_eq!(applied.cost, evaluation.cost);
+        assert_eq!(&*editor.planning(), &[0, 2]);
+        assert!(editor.is_feasible());
+        assert_eq!(editor.cost(), evaluation.cost);
+        assert_eq!(editor.gantt().nrows(), 2);
+        assert_eq!(editor.export_planning(), "I1 1\nI2 3\n");
+    }
+
+    #[test]
+    fn rejected_moves() {
+        let mut editor = Editor::from_days(Rc::new(fixtures::small()), &[0, 2]);
+        assert!(editor.evaluate(2, 0).is_none());
+        assert!(editor.apply(2, 0).is_none());
+
+        // after the latest start of I2
+        let applied = editor.apply(1, 4).unwrap();
+        assert!(!applied.latest_start);
+        assert!(!applied.feasible);
+        assert_eq!(&*editor.planning(), &[0, 2]);
+    }
+}
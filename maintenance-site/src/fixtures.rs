@@ -0,0 +1,30 @@
+//! Instance of the tests, read as the page reads the uploaded files.
+use maintenance::MaintenanceOptimization;
+
+/// Two interventions excluded during the summer, over five days.
+///
+/// I1 lasts two days when it starts before day 3, then one day. I2 lasts
+/// one day and cannot start on the last day.
+const SMALL: &str = r#"{
+    "Resources": {"c1": {"min": [1.0, 0.0, 0.0, 0.0, 0.0], "max": [4.0, 4.0, 4.0, 4.0, 4.0]}},
+    "Seasons": {"winter": ["1", "2"], "summer": ["3", "4", "5"], "is": []},
+    "Interventions": {
+        "I1": {"tmax": "3", "Delta": [2.0, 2.0, 2.0, 1.0, 1.0],
+               "workload": {"c1": {"1": {"1": 2.0}, "2": {"1": 2.0, "2": 2.0}, "3": {"2": 2.0, "3": 2.0}, "4": {"3": 2.0}}},
+               "risk": {"1": {"1": [1.0, 2.0]}, "2": {"1": [1.0, 2.0], "2": [4.0, 4.0]}, "3": {"2": [4.0, 4.0], "3": [2.0, 1.0]}, "4": {"3": [2.0, 1.0]}}},
+        "I2": {"tmax": "4", "Delta": [1.0, 1.0, 1.0, 1.0, 1.0],
+               "workload": {"c1": {"1": {"1": 3.0}, "2": {"2": 3.0}, "3": {"3": 3.0}, "4": {"4": 3.0}}},
+               "risk": {"1": {"1": [5.0, 5.0]}, "2": {"2": [1.0, 1.0]}, "3": {"3": [3.0, 3.0]}, "4": {"4": [0.5, 2.0]}}}
+    },
+    "Exclusions": {"E1": ["I1", "I2", "summer"]},
+    "T": 5,
+    "Scenarios_number": [2, 2, 2, 2, 2],
+    "Quantile": 0.5,
+    "Alpha": 0.5,
+    "ComputationTime": 60
+}"#;
+
+pub fn small() -> MaintenanceOptimization {
+    let m = maintenance_json::read_json(SMALL).unwrap();
+    maintenance_json::load_instance(m).unwrap()
+}
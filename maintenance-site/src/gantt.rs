@@ -0,0 +1,204 @@
+use maintenance::{Day, MaintenanceOptimization, Planning, IID};
+use wasm_bindgen::prelude::*;
+
+/// Vertices of the two triangles of a task
+const VERTICES_BY_TASK: usize = 6;
+
+/// Planning built from the start day of each intervention, from 0, as
+/// returned by `Optim::best_planning`; negative or out of horizon days are
+/// left unplanned.
+pub fn planning_from_days(instance: &MaintenanceOptimization, days: &[i32]) -> Planning {
+    let maintenance = &instance.maintenance;
+    let interventions = days
+        .iter()
+        .enumerate()
+        .take(maintenance.ninterventions())
+        .filter(|&(iid, &day)| {
+            day >= 0 && (day as usize) < maintenance.intervention(IID::new(iid)).nperiods()
+        })
+        .map(|(iid, &day)| (IID::new(iid), Day::new(day as usize)))
+        .collect();
+    Planning { interventions }
+}
+
+/// Rows of a Gantt chart, one by planned intervention ordered by start day.
+///
+/// The resources and the seasons of the row `i` are
+/// `resources[resource_offsets[i]..resource_offsets[i + 1]]` and
+/// `seasons[season_offsets[i]..season_offsets[i + 1]]`.
+#[wasm_bindgen]
+pub struct Gantt {
+    ndays: u32,
+    interventions: Box<[u32]>,
+    starts: Box<[u32]>,
+    /// exclusive
+    ends: Box<[u32]>,
+    names: Vec<String>,
+    resource_offsets: Box<[u32]>,
+    resources: Box<[u32]>,
+    season_offsets: Box<[u32]>,
+    seasons: Box<[u32]>,
+}
+
+impl Gantt {
+    pub fn new(instance: &MaintenanceOptimization, planning: &Planning) -> Self {
+        let maintenance = &instance.maintenance;
+        let mut planned = planning.interventions.clone();
+        planned.sort_by_key(|&(iid, day)| (day.get(), iid.get()));
+        let mut resource_offsets = vec![0u32];
+        let mut resources = Vec::new();
+        let mut season_offsets = vec![0u32];
+        let mut seasons = Vec::new();
+        let mut starts = Vec::with_capacity(planned.len());
+        let mut ends = Vec::with_capacity(planned.len());
+        for &(iid, day) in planned.iter() {
+            let intervention = maintenance.intervention(iid);
+            let period = intervention.period(day);
+            starts.push(period.start().get() as u32);
+            ends.push(period.end_exclusive().get() as u32);
+            resources.extend(intervention.resources().iter().map(|rid| rid.get() as u32));
+            resource_offsets.push(resources.len() as u32);
+            seasons.extend(intervention.seasons(day).ones().map(|sid| sid as u32));
+            season_offsets.push(seasons.len() as u32);
+        }
+        Gantt {
+            ndays: maintenance.ndays() as u32,
+            interventions: planned.iter().map(|(iid, _)| iid.get() as u32).collect(),
+            starts: starts.into_boxed_slice(),
+            ends: ends.into_boxed_slice(),
+            names: planned
+                .iter()
+                .map(|(iid, _)| instance.mapping.interventions.get(iid).cloned())
+                .map(Option::unwrap_or_default)
+                .collect(),
+            resource_offsets: resource_offsets.into_boxed_slice(),
+            resources: resources.into_boxed_slice(),
+            season_offsets: season_offsets.into_boxed_slice(),
+            seasons: seasons.into_boxed_slice(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Gantt {
+    pub fn ndays(&self) -> u32 {
+        self.ndays
+    }
+
+    pub fn nrows(&self) -> u32 {
+        self.starts.len() as u32
+    }
+
+    /// Intervention of each row
+    pub fn interventions(&self) -> Box<[u32]> {
+        self.interventions.clone()
+    }
+
+    /// First day of each row, from 0
+    pub fn starts(&self) -> Box<[u32]> {
+        self.starts.clone()
+    }
+
+    /// Day after the last day of each row
+    pub fn ends(&self) -> Box<[u32]> {
+        self.ends.clone()
+    }
+
+    pub fn name(&self, row: u32) -> Option<String> {
+        self.names.get(row as usize).cloned()
+    }
+
+    pub fn resource_offsets(&self) -> Box<[u32]> {
+        self.resource_offsets.clone()
+    }
+
+    pub fn resources(&self) -> Box<[u32]> {
+        self.resources.clone()
+    }
+
+    pub fn season_offsets(&self) -> Box<[u32]> {
+        self.season_offsets.clone()
+    }
+
+    /// Seasons covered by each row
+    pub fn seasons(&self) -> Box<[u32]> {
+        self.seasons.clone()
+    }
+
+    /// Two triangles by row, `(day, row)` for each vertex, to be drawn with
+    /// `gl.TRIANGLES` and scaled to the canvas by the model matrix.
+    pub fn vertices(&self) -> Box<[f32]> {
+        let mut vertices = Vec::with_capacity(self.starts.len() * VERTICES_BY_TASK * 2);
+        for (row, (&start, &end)) in self.starts.iter().zip(self.ends.iter()).enumerate() {
+            let (x0, x1) = (start as f32, end as f32);
+            let (y0, y1) = (row as f32, row as f32 + 1.0);
+            vertices.extend_from_slice(&[x0, y0, x1, y0, x1, y1, x0, y0, x1, y1, x0, y1]);
+        }
+        vertices.into_boxed_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use maintenance::SID;
+
+    #[test]
+    fn planning_of_days() {
+        let instance = fixtures::small();
+        let planned = |days: &[i32]| -> Vec<(usize, usize)> {
+            planning_from_days(&instance, days)
+                .interventions
+                .iter()
+                .map(|&(iid, day)| (iid.get(), day.get()))
+                .collect()
+        };
+        assert_eq!(planned(&[2, 0]), vec![(0, 2), (1, 0)]);
+        assert_eq!(planned(&[-1, 4]), vec![(1, 4)]);
+        assert_eq!(planned(&[5, 0, 1]), vec![(1, 0)]);
+        assert_eq!(planned(&[1]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn rows_by_start_day() {
+        let instance = fixtures::small();
+        let gantt = Gantt::new(&instance, &planning_from_days(&instance, &[2, 0]));
+        assert_eq!(gantt.ndays(), 5);
+        assert_eq!(gantt.nrows(), 2);
+        assert_eq!(&*gantt.interventions(), &[1, 0]);
+        assert_eq!(gantt.name(0).as_deref(), Some("I2"));
+        assert_eq!(gantt.name(1).as_deref(), Some("I1"));
+        assert_eq!(gantt.name(2), None);
+        assert_eq!(&*gantt.starts(), &[0, 2]);
+        assert_eq!(&*gantt.ends(), &[1, 4]);
+
+        assert_eq!(&*gantt.resource_offsets(), &[0, 1, 2]);
+        assert_eq!(&*gantt.resources(), &[0, 0]);
+        assert_eq!(&*gantt.season_offsets(), &[0, 1, 2]);
+        let season = |sid: u32| instance.mapping.seasons[&SID::new(sid as usize)].as_str();
+        let seasons: Vec<_> = gantt.seasons().iter().map(|&sid| season(sid)).collect();
+        assert_eq!(seasons, vec!["winter", "summer"]);
+    }
+
+    #[test]
+    fn two_triangles_by_row() {
+        let instance = fixtures::small();
+        let gantt = Gantt::new(&instance, &planning_from_days(&instance, &[2, 0]));
+        let vertices = gantt.vertices();
+        assert_eq!(vertices.len(), 2 * VERTICES_BY_TASK * 2);
+        assert_eq!(
+            &vertices[..12],
+            &[0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            &vertices[12..],
+            &[2.0, 1.0, 4.0, 1.0, 4.0, 2.0, 2.0, 1.0, 4.0, 2.0, 2.0, 2.0]
+        );
+
+        let empty = Gantt::new(&instance, &planning_from_days(&instance, &[]));
+        assert_eq!(empty.nrows(), 0);
+        assert!(empty.vertices().is_empty());
+        assert_eq!(&*empty.resource_offsets(), &[0]);
+    }
+}
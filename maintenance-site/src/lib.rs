@@ -1,15 +1,31 @@
+mod charts;
+mod editor;
+#[cfg(test)]
+mod fixtures;
+mod gantt;
 mod utils;
+extern crate js_sys;
 extern crate maintenance;
 extern crate maintenance_capnproto;
 extern crate maintenance_json;
+extern crate rand;
 extern crate serde_json;
 extern crate uuid;
 extern crate web_sys;
 
+use std::rc::Rc;
 use std::time::Instant;
 
 use maintenance::io::reader;
 use maintenance::io::reader::*;
+use maintenance::search::{LocalSearch, Solver};
+use maintenance::{LoadTimings, Planning};
+
+pub use crate::charts::{ResourceCharts, RiskChart};
+pub use crate::editor::{Editor, MoveEvaluation};
+pub use crate::gantt::Gantt;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
 use wasm_bindgen::prelude::*;
 
 use web_sys::console;
@@ -37,36 +53,91 @@ extern "C" {
 }
 #[wasm_bindgen]
 pub struct MaintenanceSummary {
-    summary: maintenance_capnproto::MaintenanceSummaryReader,
+    summary: maintenance::MaintenanceSummary,
 }
 
 #[wasm_bindgen]
 impl MaintenanceSummary {
-    pub fn from_bytes(data: &[u8]) -> Self {
-        let summary = maintenance_capnproto::MaintenanceSummaryReader::from_bytes(data);
-        match summary {
-            Ok(summary) => MaintenanceSummary { summary: summary },
-            _ => unimplemented!(),
-        }
-        //MaintenanceSummary {
-        //summary: maintenance_capnproto::MaintenanceSummaryReader::from_bytes(data).expect("Ok"),
-        //}
+    /// Summary sent by the server as a Cap'n Proto message.
+    pub fn from_bytes(data: &[u8]) -> Option<MaintenanceSummary> {
+        maintenance_capnproto::MaintenanceSummaryReader::from_bytes(data)
+            .and_then(|reader| reader.reader().summary())
+            .ok()
+            .map(|summary| MaintenanceSummary { summary })
+    }
+
+    /// Summary sent by the server as JSON.
+    pub fn from_json(contents: &str) -> Option<MaintenanceSummary> {
+        serde_json::from_str(contents)
+            .ok()
+            .map(|summary| MaintenanceSummary { summary })
     }
 
     pub fn ndays(&self) -> u32 {
-        self.summary.reader().ndays()
+        self.summary.ndays
     }
 
     pub fn ninterventions(&self) -> u32 {
-        self.summary.reader().ninterventions()
+        self.summary.ninterventions
     }
 
     pub fn nresources(&self) -> u32 {
-        self.summary.reader().nresources()
+        self.summary.nresources
     }
 
+    /// Maximum number of scenarios of a day
     pub fn nscenarios(&self) -> u32 {
-        self.summary.reader().nscenarios()
+        self.summary.nscenarios
+    }
+
+    /// Minimum number of scenarios of a day
+    pub fn min_scenarios(&self) -> u32 {
+        self.summary.min_scenarios
+    }
+
+    pub fn nseasons(&self) -> u32 {
+        self.summary.nseasons
+    }
+
+    pub fn nexclusions(&self) -> u32 {
+        self.summary.nexclusions
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.summary.alpha
+    }
+
+    pub fn quantile(&self) -> f64 {
+        self.summary.quantile
+    }
+
+    /// In seconds
+    pub fn computation_time(&self) -> f64 {
+        self.summary.computation_time as f64
+    }
+
+    /// In seconds
+    pub fn read_time(&self) -> f64 {
+        self.summary.timings.read
+    }
+
+    /// In seconds
+    pub fn parse_time(&self) -> f64 {
+        self.summary.timings.parse
+    }
+
+    /// In seconds
+    pub fn convert_time(&self) -> f64 {
+        self.summary.timings.convert
+    }
+
+    /// SHA-256 of the file of the instance, empty if unknown
+    pub fn hash(&self) -> String {
+        self.summary.hash.clone()
+    }
+
+    pub fn set_hash(&mut self, hash: &str) {
+        self.summary.hash = hash.to_owned();
     }
 }
 
@@ -106,30 +177,81 @@ impl MaintenanceSummary {
 //}
 //}
 
+/// Moves tried between two reads of the clock in `Optim::run_for`
+const MOVES_BY_CLOCK_READ: u32 = 256;
+
+/// Search run by slices, the page stays responsive between two slices.
+struct SolverState {
+    // NOTE(vincent): declared first to be dropped before the instance it
+    // borrows, like in `Editor`.
+    search: LocalSearch<'static>,
+    _instance: Rc<MaintenanceOptimization>,
+    solver: Solver,
+    rng: SmallRng,
+    /// The local search only keeps the improving moves, the best planning is
+    /// also the current one.
+    best: Planning,
+    cost: f64,
+    feasible: bool,
+    iterations: u32,
+    stopped: bool,
+}
+
 #[wasm_bindgen]
 pub struct Optim {
-    maintenance: Option<MaintenanceOptimization>,
+    /// shared with the editors
+    maintenance: Option<Rc<MaintenanceOptimization>>,
+    timings: LoadTimings,
+    solver: Option<SolverState>,
 }
 
 #[wasm_bindgen]
 impl Optim {
     pub fn new() -> Optim {
-        Optim { maintenance: None }
+        Optim {
+            maintenance: None,
+            timings: LoadTimings::default(),
+            solver: None,
+        }
     }
 
     pub fn is_loaded(&self) -> bool {
         self.maintenance.is_some()
     }
 
-    // use status enum
+    /// Loads an instance from the contents of its file, returns false when
+    /// it is invalid.
+    ///
+    /// It may run in a worker, a panic would kill it.
     pub fn load_from_bytes(&mut self, data: &[u8]) -> bool {
-        let contents = std::str::from_utf8(data).expect("Found invalid UTF-8");
-        let m = reader::read_json(contents);
-        let m = reader::load_instance(m.unwrap());
-        self.maintenance = Some(m.unwrap());
+        self.maintenance = None;
+        self.solver = None;
+        self.timings = LoadTimings::default();
+        let now = js_sys::Date::now();
+        let m = match std::str::from_utf8(data)
+            .ok()
+            .and_then(|contents| reader::read_json(contents).ok())
+        {
+            Some(m) => m,
+            None => return false,
+        };
+        self.timings.parse = (js_sys::Date::now() - now) / 1000.0;
+        let now = js_sys::Date::now();
+        self.maintenance = reader::load_instance(m).map(Rc::new);
+        self.timings.convert = (js_sys::Date::now() - now) / 1000.0;
         self.is_loaded()
     }
 
+    /// Summary of the loaded instance, without the hash of its file.
+    pub fn summary(&self) -> Option<MaintenanceSummary> {
+        self.maintenance.as_ref().map(|m| MaintenanceSummary {
+            summary: maintenance::MaintenanceSummary {
+                timings: self.timings,
+                ..maintenance::MaintenanceSummary::new(m)
+            },
+        })
+    }
+
     pub fn ninterventions(&self) -> u32 {
         self.maintenance
             .as_ref()
@@ -142,6 +264,185 @@ impl Optim {
         self.maintenance.as_ref().unwrap().maintenance.ndays() as u32
     }
 
+    /// Starts `solver` (`greedy` or `local-search`) from the greedy planning.
+    ///
+    /// Returns false when no instance is loaded or the solver is unknown.
+    pub fn start(&mut self, solver: &str, seed: u32) -> bool {
+        let instance = match self.maintenance {
+            Some(ref m) => Rc::clone(m),
+            None => return false,
+        };
+        let solver = match solver.parse::<Solver>() {
+            Ok(solver) => solver,
+            Err(_) => return false,
+        };
+        // SAFETY: the instance is kept alive, and never moved, by the `Rc`
+        // held next to the search, which is dropped first.
+        let maintenance = unsafe { &*(&instance.maintenance as *const _) };
+        let mut search = LocalSearch::new(maintenance);
+        search.init();
+        self.solver = Some(SolverState {
+            best: search.current_planning(),
+            cost: search.cost(),
+            feasible: search.is_feasible(),
+            search,
+            _instance: instance,
+            solver,
+            rng: SmallRng::seed_from_u64(seed as u64),
+            iterations: 0,
+            stopped: solver == Solver::Greedy,
+        });
+        true
+    }
+
+    /// Tries `moves` moves, returns true when the best planning improved.
+    pub fn step(&mut self, moves: u32) -> bool {
+        let state = match self.solver {
+            Some(ref mut state) if !state.stopped => state,
+            _ => return false,
+        };
+        let mut improved = false;
+        for _ in 0..moves {
+            state.iterations += 1;
+            improved |= state.search.step(&mut state.rng);
+        }
+        if improved {
+            state.best = state.search.current_planning();
+            state.cost = state.search.cost();
+            state.feasible = state.search.is_feasible();
+        }
+        improved
+    }
+
+    /// Tries moves for about `milliseconds`, returns true when the best
+    /// planning improved.
+    pub fn run_for(&mut self, milliseconds: f64) -> bool {
+        let end = js_sys::Date::now() + milliseconds;
+        let mut improved = false;
+        while self.is_running() && js_sys::Date::now() < end {
+            improved |= self.step(MOVES_BY_CLOCK_READ);
+        }
+        improved
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(ref mut state) = self.solver {
+            state.stopped = true;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        match self.solver {
+            Some(ref state) => state.solver == Solver::LocalSearch && !state.stopped,
+            None => false,
+        }
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.solver.as_ref().map_or(0, |state| state.iterations)
+    }
+
+    /// Cost of the best planning, NaN before `start`.
+    pub fn best_cost(&self) -> f64 {
+        self.solver.as_ref().map_or(f64::NAN, |state| state.cost)
+    }
+
+    pub fn best_feasible(&self) -> bool {
+        self.solver.as_ref().is_some_and(|state| state.feasible)
+    }
+
+    /// Start day of each intervention in the best planning, from 0, -1 when
+    /// it is not planned.
+    pub fn best_planning(&self) -> Box<[i32]> {
+        let ninterventions = match self.maintenance {
+            Some(ref m) => m.maintenance.ninterventions(),
+            None => return Box::new([]),
+        };
+        let mut days = vec![-1; ninterventions];
+        if let Some(ref state) = self.solver {
+            for &(iid, day) in state.best.interventions.iter() {
+                days[iid.get()] = day.get() as i32;
+            }
+        }
+        days.into_boxed_slice()
+    }
+
+    /// Gantt chart of the best planning.
+    pub fn gantt(&self) -> Option<Gantt> {
+        match (self.maintenance.as_ref(), self.solver.as_ref()) {
+            (Some(m), Some(state)) => Some(Gantt::new(m, &state.best)),
+            _ => None,
+        }
+    }
+
+    /// Gantt chart of the planning giving the start day of each
+    /// intervention, like `best_planning`.
+    pub fn gantt_of(&self, days: &[i32]) -> Option<Gantt> {
+        self.maintenance
+            .as_ref()
+            .map(|m| Gantt::new(m, &gantt::planning_from_days(m, days)))
+    }
+
+    /// Workloads of the resources in the best planning.
+    pub fn resource_charts(&self) -> Option<ResourceCharts> {
+        match (self.maintenance.as_ref(), self.solver.as_ref()) {
+            (Some(m), Some(state)) => Some(ResourceCharts::new(m, &state.best)),
+            _ => None,
+        }
+    }
+
+    /// Workloads of the resources in the planning giving the start day of
+    /// each intervention, like `best_planning`.
+    pub fn resource_charts_of(&self, days: &[i32]) -> Option<ResourceCharts> {
+        self.maintenance
+            .as_ref()
+            .map(|m| ResourceCharts::new(m, &gantt::planning_from_days(m, days)))
+    }
+
+    /// Risks by day of the best planning, with the scenarios of `day`.
+    pub fn risk_chart(&self, day: Option<u32>) -> Option<RiskChart> {
+        match (self.maintenance.as_ref(), self.solver.as_ref()) {
+            (Some(m), Some(state)) => Some(RiskChart::new(m, &state.best, day)),
+            _ => None,
+        }
+    }
+
+    /// Risks by day of the planning giving the start day of each
+    /// intervention, like `best_planning`.
+    pub fn risk_chart_of(&self, days: &[i32], day: Option<u32>) -> Option<RiskChart> {
+        self.maintenance
+            .as_ref()
+            .map(|m| RiskChart::new(m, &gantt::planning_from_days(m, days), day))
+    }
+
+    /// Editor of the best planning, to move the interventions by hand.
+    pub fn edit(&self) -> Option<Editor> {
+        match (self.maintenance.as_ref(), self.solver.as_ref()) {
+            (Some(m), Some(state)) => Some(Editor::new(Rc::clone(m), &state.best)),
+            _ => None,
+        }
+    }
+
+    /// Editor of the planning giving the start day of each intervention,
+    /// like `best_planning`.
+    pub fn edit_days(&self, days: &[i32]) -> Option<Editor> {
+        self.maintenance
+            .as_ref()
+            .map(|m| Editor::from_days(Rc::clone(m), days))
+    }
+
+    /// Best planning in the format of the challenge.
+    pub fn export_planning(&self) -> String {
+        match (self.maintenance.as_ref(), self.solver.as_ref()) {
+            (Some(m), Some(state)) => maintenance_json::ExportPlanning {
+                planning: &state.best,
+                mapping: &m.mapping,
+            }
+            .to_string(),
+            _ => String::new(),
+        }
+    }
+
     // use status enum
     pub fn load(&mut self, path: &str) -> bool {
         alert(&format!("load: {}", path));
@@ -151,7 +452,9 @@ impl Optim {
         let m = reader::read_json(&contents);
         println!("Json reading in: {}s", now.elapsed().as_secs());
         let m = reader::load_instance(m.unwrap());
-        self.maintenance = Some(m.unwrap());
+        self.maintenance = Some(Rc::new(m.unwrap()));
+        self.timings = LoadTimings::default();
+        self.solver = None;
         self.is_loaded()
     }
 }
@@ -167,4 +470,96 @@ impl Uuid {
         let uuid = maintenance_capnproto::capnp_uuid::decode(data).expect("OK");
         Uuid { uuid: uuid }
     }
+
+    /// Hyphenated form used in the urls of the server.
+    pub fn as_string(&self) -> String {
+        self.uuid.to_hyphenated().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded() -> Optim {
+        Optim {
+            maintenance: Some(Rc::new(fixtures::small())),
+            timings: LoadTimings::default(),
+            solver: None,
+        }
+    }
+
+    fn days(planning: &Planning) -> Vec<(usize, usize)> {
+        let mut days: Vec<_> = planning
+            .interventions
+            .iter()
+            .map(|&(iid, day)| (iid.get(), day.get()))
+            .collect();
+        days.sort();
+        days
+    }
+
+    #[test]
+    fn search_by_steps() {
+        let mut optim = loaded();
+        assert!(!optim.start("unknown", 0));
+        assert!(optim.start("local-search", 0));
+        assert!(optim.is_running());
+        let mut cost = optim.best_cost();
+        for _ in 0..20 {
+            optim.step(10);
+            assert!(optim.best_cost() <= cost);
+            cost = optim.best_cost();
+            // the search goes on from where the previous step stopped
+            let state = optim.solver.as_ref().unwrap();
+            assert_eq!(days(&state.search.current_planning()), days(&state.best));
+        }
+        assert_eq!(optim.iterations(), 200);
+        let editor = optim.edit_days(&optim.best_planning()).unwrap();
+        assert_eq!(editor.cost(), optim.best_cost());
+        assert_eq!(editor.is_feasible(), optim.best_feasible());
+
+        optim.stop();
+        assert!(!optim.is_running());
+        assert!(!optim.step(10));
+        assert_eq!(optim.iterations(), 200);
+    }
+
+    #[test]
+    fn summary_from_json() {
+        let summary = maintenance::MaintenanceSummary {
+            hash: "abc".to_owned(),
+            ..maintenance::MaintenanceSummary::new(&fixtures::small())
+        };
+        let json = serde_json::to_string(&summary).unwrap();
+        let summary = MaintenanceSummary::from_json(&json).unwrap();
+        assert_eq!(summary.ndays(), 5);
+        assert_eq!(summary.ninterventions(), 2);
+        assert_eq!(summary.nresources(), 1);
+        assert_eq!(summary.nscenarios(), 2);
+        assert_eq!(summary.min_scenarios(), 2);
+        assert_eq!(summary.nseasons(), 3);
+        assert_eq!(summary.nexclusions(), 1);
+        assert_eq!(summary.alpha(), 0.5);
+        assert_eq!(summary.quantile(), 0.5);
+        assert_eq!(summary.computation_time(), 60.0);
+        assert_eq!(summary.hash(), "abc");
+        assert!(MaintenanceSummary::from_json("{}").is_none());
+
+        // loaded in the page, the hash of the file is unknown
+        let summary = loaded().summary().unwrap();
+        assert_eq!(summary.ninterventions(), 2);
+        assert_eq!(summary.hash(), "");
+    }
+
+    #[test]
+    fn greedy_only_starts() {
+        let mut optim = loaded();
+        assert!(optim.start("greedy", 0));
+        assert!(!optim.is_running());
+        assert!(!optim.step(10));
+        assert_eq!(optim.iterations(), 0);
+        assert!(optim.best_cost().is_finite());
+        assert!(!Optim::new().start("greedy", 0));
+    }
 }
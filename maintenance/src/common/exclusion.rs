@@ -44,11 +44,14 @@ impl InterventionExclusions {
                 return false;
             }
             let slice = &self.exclusions[pos..];
-            if let Ok(idx) = slice.binary_search_by(|(ex_iid, _)| ex_iid.cmp(&iid)) {
-                pos = idx;
-                if !intersect(&unsafe { self.exclusions.get_unchecked(idx) }.1, &seasons) {
-                    return true;
+            match slice.binary_search_by(|(ex_iid, _)| ex_iid.cmp(&iid)) {
+                Ok(idx) => {
+                    pos += idx;
+                    if intersect(&unsafe { self.exclusions.get_unchecked(pos) }.1, &seasons) {
+                        return true;
+                    }
                 }
+                Err(idx) => pos += idx,
             }
         }
         false
@@ -77,3 +80,36 @@ impl InterventionExclusions {
         possible_seasons
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seasons(sids: &[usize]) -> Seasons {
+        let mut seasons = Seasons::with_capacity(3);
+        sids.iter().for_each(|&sid| seasons.insert(sid));
+        seasons
+    }
+
+    fn iids(iids: &[usize]) -> Vec<IID> {
+        iids.iter().cloned().map(IID::new).collect()
+    }
+
+    #[test]
+    fn is_excluded() {
+        let exclusions = InterventionExclusions {
+            exclusions: vec![(IID::new(1), seasons(&[1])), (IID::new(3), seasons(&[0]))]
+                .into_boxed_slice(),
+        };
+        assert!(exclusions.is_excluded(&seasons(&[1]), iids(&[0, 1])));
+        assert!(!exclusions.is_excluded(&seasons(&[0]), iids(&[1])));
+        assert!(exclusions.is_excluded(&seasons(&[0]), iids(&[1, 3])));
+        assert!(exclusions.is_excluded(&seasons(&[0, 2]), iids(&[0, 2, 3])));
+        assert!(!exclusions.is_excluded(&seasons(&[1]), iids(&[2, 3])));
+        assert!(!exclusions.is_excluded(&seasons(&[2]), iids(&[0, 1, 2, 3])));
+        assert!(!InterventionExclusions {
+            exclusions: vec![].into_boxed_slice()
+        }
+        .is_excluded(&seasons(&[0]), iids(&[0])));
+    }
+}
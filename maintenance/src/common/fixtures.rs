@@ -0,0 +1,133 @@
+//! Instances of the tests, built as the json loader does.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::common::exclusion::*;
+use crate::common::intervention::*;
+use crate::common::risks::*;
+use crate::common::types::*;
+use crate::common::{Maintenance, MaintenanceMapping, MaintenanceOptimization, Resource};
+
+/// Season of each day of the small instance: winter, then summer.
+const SEASON_OF_DAYS: [usize; 5] = [0, 0, 1, 1, 1];
+/// Winter, summer, and the season of the days of no season.
+const NSEASONS: usize = 3;
+const NSCENARIOS: usize = 2;
+
+fn seasons(sids: &[usize]) -> Seasons {
+    let mut seasons = Seasons::with_capacity(NSEASONS);
+    sids.iter().for_each(|&sid| seasons.insert(sid));
+    seasons
+}
+
+/// Intervention of one resource, with the workloads and the risks of each
+/// day of the period starting on each day.
+fn intervention(latest_start: usize, periods: &[(&[f64], &[[f64; NSCENARIOS]])]) -> Intervention {
+    let periods_slice = std::iter::once(0)
+        .chain(periods.iter().scan(0, |offset, (workloads, _)| {
+            *offset += workloads.len() * NSCENARIOS;
+            Some(*offset)
+        }))
+        .collect();
+    let risks = periods
+        .iter()
+        .flat_map(|(_, risks)| risks.iter().flat_map(|risks| risks.iter().cloned()))
+        .collect();
+    let workloads = periods
+        .iter()
+        .map(|(workloads, _)| Workload::new(RID::new(0), workloads.to_vec().into_boxed_slice()))
+        .collect();
+    let periods = periods
+        .iter()
+        .enumerate()
+        .map(|(start, (workloads, _))| {
+            Period::new(Day::new(start), Day::new(workloads.len())).unwrap()
+        })
+        .collect::<Box<[Period]>>();
+    let seasons_of_periods = periods
+        .iter()
+        .map(|period| {
+            let (begin, end) = period.days_exclusive();
+            seasons(&SEASON_OF_DAYS[begin.get()..end.get()])
+        })
+        .collect();
+    Intervention::builder()
+        .set_latest_start(Day::new(latest_start))
+        .set_periods(periods)
+        .set_seasons(seasons_of_periods)
+        .set_risks(
+            Risks::builder()
+                .set_nscenarios(NSCENARIOS)
+                .set_periods(periods_slice)
+                .set_risks(risks)
+                .build(),
+        )
+        .set_workloads(workloads)
+        .set_resources(vec![RID::new(0)].into_boxed_slice())
+        .build()
+}
+
+/// Two interventions excluded during the summer, over five days.
+///
+/// I1 lasts two days when it starts before day 3, then one day. I2 lasts
+/// one day and cannot start on the last day.
+pub fn small() -> MaintenanceOptimization {
+    let interventions = vec![
+        intervention(
+            2,
+            &[
+                (&[2.0, 2.0], &[[1.0, 2.0], [1.0, 2.0]]),
+                (&[2.0, 2.0], &[[4.0, 4.0], [4.0, 4.0]]),
+                (&[2.0, 2.0], &[[2.0, 1.0], [2.0, 1.0]]),
+                (&[0.0], &[[0.0, 0.0]]),
+                (&[0.0], &[[0.0, 0.0]]),
+            ],
+        ),
+        intervention(
+            3,
+            &[
+                (&[3.0], &[[5.0, 5.0]]),
+                (&[3.0], &[[1.0, 1.0]]),
+                (&[3.0], &[[3.0, 3.0]]),
+                (&[3.0], &[[0.5, 2.0]]),
+                (&[0.0], &[[0.0, 0.0]]),
+            ],
+        ),
+    ];
+    let exclusion = |other| InterventionExclusions {
+        exclusions: vec![(IID::new(other), seasons(&[1]))].into_boxed_slice(),
+    };
+    let maintenance = Maintenance::builder()
+        .set_ndays(SEASON_OF_DAYS.len())
+        .set_quantile(0.5)
+        .set_alpha(0.5)
+        .set_interventions(interventions.into_boxed_slice())
+        .set_resources(
+            vec![Resource {
+                min: vec![1.0, 0.0, 0.0, 0.0, 0.0].into(),
+                max: vec![4.0; 5].into(),
+            }]
+            .into_boxed_slice(),
+        )
+        .set_exclusions(vec![exclusion(1), exclusion(0)].into_boxed_slice())
+        .set_scenarios_number(vec![NSCENARIOS; SEASON_OF_DAYS.len()])
+        .set_season_of_days(SEASON_OF_DAYS.iter().cloned().map(SID::new).collect())
+        .build();
+    let mut resources = HashMap::new();
+    resources.insert(RID::new(0), "c1".to_owned());
+    let mut interventions = HashMap::new();
+    interventions.insert(IID::new(0), "I1".to_owned());
+    interventions.insert(IID::new(1), "I2".to_owned());
+    let mut seasons = HashMap::new();
+    seasons.insert(SID::new(0), "winter".to_owned());
+    seasons.insert(SID::new(1), "summer".to_owned());
+    MaintenanceOptimization {
+        maintenance,
+        mapping: MaintenanceMapping {
+            resources,
+            interventions,
+            seasons,
+        },
+        computation_time: Duration::from_secs(60),
+    }
+}
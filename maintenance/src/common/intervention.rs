@@ -67,6 +67,27 @@ pub struct Intervention {
 }
 
 impl Intervention {
+    /// Approximate memory held by the intervention, in bytes
+    pub fn memory_size(&self) -> usize {
+        use std::mem::size_of;
+        let seasons: usize = self
+            .seasons
+            .iter()
+            .map(|seasons| size_of::<Seasons>() + seasons.len() / 8)
+            .sum();
+        let workloads: usize = self
+            .workloads
+            .iter()
+            .map(|workload| size_of::<Workload>() + 2 * workload.workloads.len() * size_of::<f64>())
+            .sum();
+        size_of::<Intervention>()
+            + self.periods.len() * size_of::<Period>()
+            + seasons
+            + self.risks.memory_size()
+            + workloads
+            + self.resources.len() * size_of::<RID>()
+    }
+
     pub fn latest_start(&self) -> Day {
         self.latest_start
     }
@@ -75,6 +96,17 @@ impl Intervention {
         day <= self.latest_start
     }
 
+    /// The day is not after the latest start, and the period starting on
+    /// this day ends in the horizon.
+    pub fn can_start_on(&self, day: Day) -> bool {
+        self.is_day_compatible(day) && day.get() < self.nperiods()
+    }
+
+    /// Number of days the intervention can start on, from the first day
+    pub fn nstarts(&self) -> usize {
+        std::cmp::min(self.latest_start.get() + 1, self.nperiods())
+    }
+
     pub fn days(&self) -> impl Iterator<Item = Day> {
         (0..=self.latest_start.get()).map(Day::new)
     }
@@ -83,6 +115,10 @@ impl Intervention {
         self.periods.iter()
     }
 
+    pub fn nperiods(&self) -> usize {
+        self.periods.len()
+    }
+
     pub fn period(&self, day: Day) -> &Period {
         unsafe { &self.periods.get_unchecked(day.get()) }
     }
@@ -109,10 +145,18 @@ impl Intervention {
         unsafe { &self.seasons.get_unchecked(day.get()) }
     }
 
+    pub fn risks(&self) -> &Risks {
+        &self.risks
+    }
+
     pub fn nresources(&self) -> usize {
         self.resources.len()
     }
 
+    pub fn resources(&self) -> &[RID] {
+        &self.resources
+    }
+
     pub fn workloads(&self, day: Day) -> &[Workload] {
         let start = day.get() * self.nresources();
         //let end = (day.get() + 1) * self.nresources();
@@ -1,4 +1,6 @@
 pub mod exclusion;
+#[cfg(test)]
+pub mod fixtures;
 pub mod intervention;
 pub mod risks;
 pub mod types;
@@ -93,6 +95,7 @@ pub struct MaintenanceBuilder {
     resources: Option<Box<[Resource]>>,
     exclusions: Option<Box<[InterventionExclusions]>>,
     scenarios_number: Option<Vec<usize>>,
+    season_of_days: Option<Box<[SID]>>,
 }
 
 impl MaintenanceBuilder {
@@ -117,6 +120,7 @@ impl MaintenanceBuilder {
             resources: self.resources.unwrap(),
             exclusions: self.exclusions.unwrap(),
             scenarios_number,
+            season_of_days: self.season_of_days.unwrap(),
             nscenarios,
         }
     }
@@ -130,6 +134,7 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: self.exclusions,
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -142,6 +147,7 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: self.exclusions,
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -154,6 +160,7 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: self.exclusions,
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -166,6 +173,7 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: self.exclusions,
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -178,6 +186,7 @@ impl MaintenanceBuilder {
             resources: Some(resources),
             exclusions: self.exclusions,
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -190,6 +199,7 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: Some(exclusions),
             scenarios_number: self.scenarios_number,
+            season_of_days: self.season_of_days,
         }
     }
 
@@ -202,6 +212,20 @@ impl MaintenanceBuilder {
             resources: self.resources,
             exclusions: self.exclusions,
             scenarios_number: Some(scenarios_number),
+            season_of_days: self.season_of_days,
+        }
+    }
+
+    pub fn set_season_of_days(self, season_of_days: Box<[SID]>) -> Self {
+        MaintenanceBuilder {
+            ndays: self.ndays,
+            quantile: self.quantile,
+            alpha: self.alpha,
+            interventions: self.interventions,
+            resources: self.resources,
+            exclusions: self.exclusions,
+            scenarios_number: self.scenarios_number,
+            season_of_days: Some(season_of_days),
         }
     }
 }
@@ -217,6 +241,7 @@ pub struct Maintenance {
     exclusions: Box<[InterventionExclusions]>,
     scenarios_number: Vec<usize>, // expected values
     quantiles: Vec<usize>,        // expected values
+    season_of_days: Box<[SID]>,
     nscenarios: usize,
 }
 
@@ -271,6 +296,33 @@ impl Maintenance {
         self.ndays
     }
 
+    /// Season of a day of the horizon; the days of no season share the
+    /// last season.
+    pub fn season(&self, day: Day) -> SID {
+        self.season_of_days[day.get()]
+    }
+
+    /// Days two exclusive interventions overlap, if one of them belongs to
+    /// one of their excluded seasons.
+    ///
+    /// NOTE(vincent): the seasons of the rest of the periods do not matter.
+    pub fn excluded_overlap(
+        &self,
+        lhs: &Period,
+        rhs: &Period,
+        excluded_seasons: &Seasons,
+    ) -> Option<Period> {
+        let overlap = lhs.intersection(rhs)?;
+        let (begin, end) = overlap.days_exclusive();
+        if (begin.get()..end.get())
+            .any(|day| excluded_seasons.contains(self.season(Day::new(day)).get()))
+        {
+            Some(overlap)
+        } else {
+            None
+        }
+    }
+
     pub fn ninterventions(&self) -> usize {
         self.interventions.len()
     }
@@ -294,6 +346,21 @@ impl Maintenance {
         self.nscenarios
     }
 
+    /// Excluded pairs of interventions, counted once by season
+    pub fn nexclusions(&self) -> usize {
+        self.exclusions
+            .iter()
+            .enumerate()
+            .flat_map(|(iid, exclusions)| {
+                exclusions
+                    .exclusions
+                    .iter()
+                    .filter(move |(other, _)| other.get() > iid)
+            })
+            .map(|(_, seasons)| seasons.count_ones(..))
+            .sum()
+    }
+
     pub fn interventions_ids(&self) -> impl Iterator<Item = IID> {
         (0..self.interventions.len()).map(IID::new)
     }
@@ -301,12 +368,106 @@ impl Maintenance {
     pub fn scenarios_ids(&self) -> impl Iterator<Item = SID> {
         (0..self.scenarios_number.len()).map(SID::new)
     }
+
+    /// Approximate memory held by the instance, in bytes
+    pub fn memory_size(&self) -> usize {
+        use std::mem::size_of;
+        let interventions: usize = self
+            .interventions
+            .iter()
+            .map(Intervention::memory_size)
+            .sum();
+        let resources =
+            self.resources.len() * (size_of::<Resource>() + 2 * self.ndays * size_of::<f64>());
+        let exclusions: usize = self
+            .exclusions
+            .iter()
+            .flat_map(|exclusions| exclusions.exclusions.iter())
+            .map(|(_, seasons)| size_of::<(IID, Seasons)>() + seasons.len() / 8)
+            .sum();
+        size_of::<Maintenance>()
+            + interventions
+            + resources
+            + self.exclusions.len() * size_of::<InterventionExclusions>()
+            + exclusions
+            + (self.scenarios_number.len() + self.quantiles.len()) * size_of::<usize>()
+            + self.season_of_days.len() * size_of::<SID>()
+    }
 }
 
+#[derive(Clone, Debug, Default)]
 pub struct Planning {
     pub interventions: Vec<(IID, Day)>,
 }
 
+/// Objectives of a planning and risks of each day
+#[derive(Clone, Debug, Default)]
+pub struct CostReport {
+    /// mean risk
+    pub obj1: f64,
+    /// expected excess
+    pub obj2: f64,
+    pub cost: f64,
+    pub mean_risks: Box<[f64]>,
+    pub quantile_risks: Box<[f64]>,
+    pub excess_risks: Box<[f64]>,
+}
+
+/// Time spent loading an instance, in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct LoadTimings {
+    pub read: f64,
+    pub parse: f64,
+    pub convert: f64,
+}
+
+/// Size and parameters of an instance
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MaintenanceSummary {
+    pub ndays: u32,
+    pub ninterventions: u32,
+    pub nresources: u32,
+    /// maximum number of scenarios of a day
+    pub nscenarios: u32,
+    /// minimum number of scenarios of a day
+    pub min_scenarios: u32,
+    pub nseasons: u32,
+    pub nexclusions: u32,
+    pub alpha: f64,
+    pub quantile: f64,
+    /// in seconds
+    pub computation_time: u64,
+    pub timings: LoadTimings,
+    /// SHA-256 of the file of the instance, in hexadecimal, empty if unknown
+    pub hash: String,
+}
+
+impl MaintenanceSummary {
+    pub fn new(instance: &MaintenanceOptimization) -> Self {
+        let maintenance = &instance.maintenance;
+        MaintenanceSummary {
+            ndays: maintenance.ndays() as u32,
+            ninterventions: maintenance.ninterventions() as u32,
+            nresources: maintenance.nresources() as u32,
+            nscenarios: maintenance.nscenarios() as u32,
+            min_scenarios: maintenance
+                .scenarios_number()
+                .iter()
+                .min()
+                .map_or(0, |&n| n as u32),
+            nseasons: instance.mapping.seasons.len() as u32,
+            nexclusions: maintenance.nexclusions() as u32,
+            alpha: maintenance.alpha(),
+            quantile: maintenance.quantile(),
+            computation_time: instance.computation_time.as_secs(),
+            timings: LoadTimings::default(),
+            hash: String::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct MaintenanceOptimization {
@@ -314,3 +475,18 @@ pub struct MaintenanceOptimization {
     pub mapping: MaintenanceMapping,
     pub computation_time: Duration,
 }
+
+impl MaintenanceOptimization {
+    /// Approximate memory held by the instance and its names, in bytes
+    pub fn memory_size(&self) -> usize {
+        let names: usize = self
+            .mapping
+            .resources
+            .values()
+            .chain(self.mapping.interventions.values())
+            .chain(self.mapping.seasons.values())
+            .map(|name| std::mem::size_of::<String>() + name.len())
+            .sum();
+        self.maintenance.memory_size() + names
+    }
+}
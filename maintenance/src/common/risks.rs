@@ -14,17 +14,38 @@ pub struct Risks {
 }
 
 impl Risks {
+    /// Approximate memory held by the risks, in bytes
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Risks>()
+            + self.periods_slice.len() * std::mem::size_of::<usize>()
+            + (self.risks.len() + self.summed_risks.len()) * std::mem::size_of::<f64>()
+    }
+
     pub fn builder() -> RiskBuilder {
         RiskBuilder
     }
 
+    pub fn nscenarios(&self) -> usize {
+        self.nscenarios
+    }
+
+    /// Offsets of each period in the raw risks
+    pub fn periods_slice(&self) -> &[usize] {
+        &self.periods_slice
+    }
+
+    /// Raw risks of every period, scenarios being contiguous
+    pub fn raw_values(&self) -> &[f64] {
+        &self.risks
+    }
+
     // for the whole period
     pub fn values(&self, day: Day) -> &[f64] {
         let idx = day.get();
         unsafe {
             let start = self.periods_slice.get_unchecked(idx);
             let end = self.periods_slice.get_unchecked(idx + 1);
-            let n = end - start + 1;
+            let n = end - start;
             let ptr = self.risks.get_unchecked(*start) as *const f64;
 
             std::slice::from_raw_parts(ptr, n)
@@ -36,13 +57,12 @@ impl Risks {
     /// sum of risk for each day
     pub fn summed_values(&self, day: Day) -> &[f64] {
         let idx = day.get();
-        // BOUNDS are false now
         // can't divie by nscernarios
         // because nscenarios depends of the day
         unsafe {
             let start = self.periods_slice.get_unchecked(idx) / self.nscenarios;
             let end = self.periods_slice.get_unchecked(idx + 1) / self.nscenarios;
-            let n = end - start + 1;
+            let n = end - start;
             let ptr = self.summed_risks.get_unchecked(start) as *const f64;
 
             std::slice::from_raw_parts(ptr, n)
@@ -126,3 +146,22 @@ impl RiskBuilderStepFinal {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_of_periods() {
+        // two scenarios: a period of two days, then a period of one day
+        let risks = Risks::builder()
+            .set_nscenarios(2)
+            .set_periods(vec![0, 4, 6].into_boxed_slice())
+            .set_risks(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0].into_boxed_slice())
+            .build();
+        assert_eq!(risks.values(Day::new(0)), &[0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(risks.values(Day::new(1)), &[4.0, 5.0]);
+        assert_eq!(risks.summed_values(Day::new(0)), &[1.0, 5.0]);
+        assert_eq!(risks.summed_values(Day::new(1)), &[9.0]);
+    }
+}
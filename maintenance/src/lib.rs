@@ -3,13 +3,18 @@
 
 extern crate fast_floats;
 extern crate fixedbitset;
+extern crate rand;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde;
 
 pub use self::common::exclusion::{InterventionExclusions, Seasons};
 pub use self::common::intervention::{Intervention, Workload};
 pub use self::common::risks::Risks;
 pub use self::common::types::{Day, Period, IID, PID, PRID, RID, SID};
 pub use self::common::{
-    Maintenance, MaintenanceMapping, MaintenanceOptimization, Planning, Resource,
+    CostReport, LoadTimings, Maintenance, MaintenanceMapping, MaintenanceOptimization,
+    MaintenanceSummary, Planning, Resource,
 };
 
 pub mod builder {
@@ -20,3 +25,4 @@ pub mod builder {
 mod common;
 pub mod search;
 mod utils;
+pub mod validation;
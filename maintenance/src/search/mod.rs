@@ -1,12 +1,135 @@
-use crate::common::exclusion::*;
 use crate::common::intervention::*;
 use crate::common::types::*;
-use crate::common::{Maintenance, Planning};
+use crate::common::{CostReport, Maintenance, Planning};
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 mod state;
 
 use self::state::*;
 
+/// Parameters of a search run
+#[derive(Clone, Copy, Debug)]
+pub struct SearchParameters {
+    pub time_limit: Duration,
+    pub seed: u64,
+}
+
+/// Shared with the thread running a search
+#[derive(Debug, Default)]
+pub struct SearchControl {
+    /// Set to stop the search
+    pub stop: AtomicBool,
+    /// Iterations done so far
+    pub iterations: AtomicUsize,
+}
+
+impl SearchControl {
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations.load(Ordering::Relaxed)
+    }
+}
+
+/// New best solution found during a search
+#[derive(Clone, Debug)]
+pub struct Improvement {
+    pub cost: f64,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub feasible: bool,
+    pub elapsed: Duration,
+    pub iteration: usize,
+}
+
+/// Feasibility and objectives of a planning after moving an intervention
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveReport {
+    pub iid: IID,
+    pub day: Day,
+    /// The day is not after the latest start of the intervention
+    pub latest_start: bool,
+    /// Maximum workloads respected during the new period of the intervention
+    pub max_workloads: bool,
+    /// No exclusion with the interventions planned during the new period
+    pub exclusions: bool,
+    /// Every intervention planned and minimum workloads reached
+    pub min_workloads: bool,
+    pub obj1: f64,
+    pub obj2: f64,
+    pub cost: f64,
+    pub delta_obj1: f64,
+    pub delta_obj2: f64,
+    pub delta_cost: f64,
+}
+
+impl MoveReport {
+    pub fn is_feasible(&self) -> bool {
+        self.latest_start && self.max_workloads && self.exclusions && self.min_workloads
+    }
+}
+
+/// Available solvers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Solver {
+    /// Only the initial greedy planning
+    Greedy,
+    /// Greedy planning improved by random moves
+    LocalSearch,
+}
+
+impl Solver {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::Greedy => "greedy",
+            Solver::LocalSearch => "local-search",
+        }
+    }
+
+    /// Run the solver until the time limit is reached or `control` is stopped.
+    ///
+    /// `on_improvement` is called each time a better planning is found.
+    pub fn run<F>(
+        &self,
+        maintenance: &Maintenance,
+        parameters: &SearchParameters,
+        control: &SearchControl,
+        mut on_improvement: F,
+    ) -> Planning
+    where
+        F: FnMut(&Improvement, &Planning),
+    {
+        let mut ls = LocalSearch::new(maintenance);
+        ls.init();
+        match self {
+            Solver::Greedy => {
+                let planning = ls.current_planning();
+                on_improvement(&ls.improvement(Duration::new(0, 0), 0), &planning);
+                planning
+            }
+            Solver::LocalSearch => ls.run(parameters, control, on_improvement),
+        }
+    }
+}
+
+impl std::str::FromStr for Solver {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "greedy" => Ok(Solver::Greedy),
+            "local-search" => Ok(Solver::LocalSearch),
+            _ => Err(format!("unknown solver: {}", name)),
+        }
+    }
+}
+
 pub trait Search<'maintenance> {
     fn build(maintenance: &'maintenance Maintenance) -> Self;
     fn search(&self) -> Planning;
@@ -117,40 +240,34 @@ impl<'maintenance> LocalSearch<'maintenance> {
 
     fn schedulable(&self, iid: IID, day: Day) -> bool {
         let intervention = self.maintenance.intervention(iid);
+        if !intervention.can_start_on(day) {
+            return false;
+        }
         let period = intervention.period(day);
-        self.check_exclusion(
-            &period,
-            intervention.seasons(day),
-            self.maintenance.exclusions(iid),
-        ) && self.state.workloads.check_adding(
-            &period,
-            intervention.workloads(period.start()),
-            self.maintenance.resources(),
-        )
+        self.check_exclusion(iid, period)
+            && self.state.workloads.check_adding(
+                &period,
+                intervention.workloads(period.start()),
+                self.maintenance.resources(),
+            )
     }
 
-    fn check_exclusion(
-        &self,
-        period: &Period,
-        seasons: &Seasons,
-        exclusions: &InterventionExclusions,
-    ) -> bool {
-        let iids = self
-            .state
-            .interventions
+    /// No exclusion between an intervention planned during `period` and the
+    /// interventions planned so far.
+    fn check_exclusion(&self, iid: IID, period: &Period) -> bool {
+        self.maintenance
+            .exclusions(iid)
+            .exclusions
             .iter()
-            .enumerate()
-            .filter_map(|(iid, p)| match p.as_ref() {
-                Some(p) if p.intersect(&period) => Some(iid),
-                _ => None,
+            .all(|(other, excluded_seasons)| {
+                match unsafe { self.state.interventions.get_unchecked(other.get()) } {
+                    Some(other_period) if *other != iid => self
+                        .maintenance
+                        .excluded_overlap(period, other_period, excluded_seasons)
+                        .is_none(),
+                    _ => true,
+                }
             })
-            .filter_map(
-                |iid| match unsafe { self.state.seasons.get_unchecked(iid) } {
-                    Some(s) if intersect(s, &seasons) => Some(IID::new(iid)),
-                    _ => None,
-                },
-            );
-        !exclusions.is_excluded(&seasons, iids)
     }
 
     fn schedule(&mut self, iid: IID, new_day: Day) {
@@ -158,10 +275,8 @@ impl<'maintenance> LocalSearch<'maintenance> {
         let new_period = *intervention.period(new_day);
         match unsafe { self.state.interventions.get_unchecked(iid.get()) } {
             Some(ref cur_period) if *cur_period != new_period => {
-                //unimplemented!()
-                //decrease_risks();
-                //increase_risks().
-                //...
+                self.unschedule(iid);
+                self.schedule(iid, new_day);
             }
             None => {
                 self.state.unplanned.remove_item(&iid);
@@ -180,12 +295,32 @@ impl<'maintenance> LocalSearch<'maintenance> {
         }
     }
 
+    fn unschedule(&mut self, iid: IID) {
+        let intervention = self.maintenance.intervention(iid);
+        if let Some(period) = unsafe { *self.state.interventions.get_unchecked(iid.get()) } {
+            self.state.planned.remove_item(&iid);
+            self.state.unplanned.push(iid);
+            self.decrease_workloads(&period, intervention);
+            self.decrease_risks(&period, intervention);
+            unsafe {
+                *self.state.interventions.get_unchecked_mut(iid.get()) = None;
+                *self.state.seasons.get_unchecked_mut(iid.get()) = None;
+            }
+        }
+    }
+
     fn increase_workloads(&mut self, period: &Period, intervention: &Intervention) {
         self.state
             .workloads
             .increase_workloads(period, intervention.workloads(period.start()));
     }
 
+    fn decrease_workloads(&mut self, period: &Period, intervention: &Intervention) {
+        self.state
+            .workloads
+            .decrease_workloads(period, intervention.workloads(period.start()));
+    }
+
     fn increase_risks(&mut self, period: &Period, intervention: &Intervention) {
         self.state
             .risk_incrementer()
@@ -204,6 +339,265 @@ impl<'maintenance> LocalSearch<'maintenance> {
             .update_cost(self.maintenance.ndays(), self.maintenance.alpha());
     }
 
+    fn decrease_risks(&mut self, period: &Period, intervention: &Intervention) {
+        self.state
+            .risk_incrementer()
+            .remove_risks(period, intervention.period_risks(period.start()))
+            .remove_mean(
+                period,
+                intervention.summed_risks(period.start()),
+                &self.maintenance.scenarios_number_by_period(period),
+            )
+            .update_quantile(
+                period,
+                &self.maintenance.scenarios_number_by_period(period),
+                &self.maintenance.quantiles_by_period(period),
+            )
+            .update_excess(period)
+            .update_cost(self.maintenance.ndays(), self.maintenance.alpha());
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.state.cost.cost
+    }
+
+    /// Every intervention is planned and the minimum workloads are reached.
+    ///
+    /// Maximum workloads and exclusions are enforced when scheduling.
+    pub fn is_feasible(&self) -> bool {
+        self.state.unplanned.is_empty()
+            && self
+                .state
+                .workloads
+                .min_violation(self.maintenance.resources())
+                <= 0.0f64
+    }
+
+    /// Same as `is_feasible`, with the start days, the maximum workloads and
+    /// the exclusions checked again: `move_intervention` and `from_planning`
+    /// do not enforce them.
+    ///
+    /// Agrees with `validate` on the planning of the search.
+    pub fn is_strictly_feasible(&self) -> bool {
+        self.is_feasible()
+            && self
+                .state
+                .workloads
+                .max_violation(self.maintenance.resources())
+                <= 0.0f64
+            && self.maintenance.interventions_ids().all(|iid| {
+                match self.state.interventions[iid.get()] {
+                    Some(ref period) => {
+                        self.maintenance
+                            .intervention(iid)
+                            .can_start_on(period.start())
+                            && self.check_exclusion(iid, period)
+                    }
+                    None => true,
+                }
+            })
+    }
+
+    // lexicographic order: planned interventions, minimum workloads, cost
+    fn score(&self) -> (usize, f64, f64) {
+        (
+            self.state.unplanned.len(),
+            self.state
+                .workloads
+                .min_violation(self.maintenance.resources()),
+            self.cost(),
+        )
+    }
+
+    fn improvement(&self, elapsed: Duration, iteration: usize) -> Improvement {
+        let (obj1, obj2, cost) = self.objectives();
+        Improvement {
+            cost,
+            obj1,
+            obj2,
+            feasible: self.is_feasible(),
+            elapsed,
+            iteration,
+        }
+    }
+
+    /// Try to move a random intervention to a random day.
+    ///
+    /// The move is kept only if it improves the planning.
+    pub fn step(&mut self, rng: &mut SmallRng) -> bool {
+        let ninterventions = self.maintenance.ninterventions();
+        if ninterventions == 0 {
+            return false;
+        }
+        let iid = IID::new(rng.gen_range(0, ninterventions));
+        let nstarts = self.maintenance.intervention(iid).nstarts();
+        if nstarts == 0 {
+            return false;
+        }
+        let day = Day::new(rng.gen_range(0, nstarts));
+        let old_day = self.start_day(iid);
+        if old_day == Some(day) {
+            return false;
+        }
+        let score = self.score();
+        self.unschedule(iid);
+        if self.schedulable(iid, day) {
+            self.schedule(iid, day);
+            if self.score() < score {
+                return true;
+            }
+            self.unschedule(iid);
+        }
+        if let Some(old_day) = old_day {
+            self.schedule(iid, old_day);
+        }
+        false
+    }
+
+    /// Improve the current planning until the time limit or a stop request.
+    pub fn run<F>(
+        &mut self,
+        parameters: &SearchParameters,
+        control: &SearchControl,
+        mut on_improvement: F,
+    ) -> Planning
+    where
+        F: FnMut(&Improvement, &Planning),
+    {
+        let now = Instant::now();
+        let mut rng = SmallRng::seed_from_u64(parameters.seed);
+        let mut best = self.current_planning();
+        on_improvement(&self.improvement(now.elapsed(), 0), &best);
+        let mut iteration = 0usize;
+        while now.elapsed() < parameters.time_limit && !control.is_stopped() {
+            iteration += 1;
+            control.iterations.store(iteration, Ordering::Relaxed);
+            if self.step(&mut rng) {
+                best = self.current_planning();
+                on_improvement(&self.improvement(now.elapsed(), iteration), &best);
+            }
+        }
+        best
+    }
+
+    /// Build the state of a given planning.
+    ///
+    /// Interventions are scheduled without checking resources, exclusions and
+    /// latest starts, days out of the horizon of an intervention are ignored.
+    pub fn from_planning(
+        maintenance: &'maintenance Maintenance,
+        planning: &Planning,
+    ) -> LocalSearch<'maintenance> {
+        let mut search = LocalSearch::new(maintenance);
+        for &(iid, day) in planning.interventions.iter() {
+            if iid.get() < maintenance.ninterventions()
+                && day.get() < maintenance.intervention(iid).nperiods()
+            {
+                search.schedule(iid, day);
+            }
+        }
+        search
+    }
+
+    pub fn cost_report(&self) -> CostReport {
+        let cost = &self.state.cost;
+        let ndays = self.maintenance.ndays() as f64;
+        CostReport {
+            obj1: cost.summed_mean_risks / ndays,
+            obj2: cost.summed_excess / ndays,
+            cost: cost.cost,
+            mean_risks: cost.mean_risks.clone(),
+            quantile_risks: cost.quantile_risks.clone(),
+            excess_risks: cost.excess_risks.clone(),
+        }
+    }
+
+    fn objectives(&self) -> (f64, f64, f64) {
+        let ndays = self.maintenance.ndays() as f64;
+        (
+            self.state.cost.summed_mean_risks / ndays,
+            self.state.cost.summed_excess / ndays,
+            self.cost(),
+        )
+    }
+
+    /// Move an intervention to another start day, even if the planning
+    /// becomes infeasible, as long as the day is in its horizon.
+    ///
+    /// Only the period of the intervention is updated.
+    pub fn move_intervention(&mut self, iid: IID, day: Day) -> MoveReport {
+        let (obj1, obj2, cost) = self.objectives();
+        let intervention = self.maintenance.intervention(iid);
+        let mut report = MoveReport {
+            iid,
+            day,
+            latest_start: intervention.can_start_on(day),
+            max_workloads: false,
+            exclusions: false,
+            min_workloads: false,
+            obj1,
+            obj2,
+            cost,
+            delta_obj1: 0.0f64,
+            delta_obj2: 0.0f64,
+            delta_cost: 0.0f64,
+        };
+        if !report.latest_start {
+            return report;
+        }
+        self.unschedule(iid);
+        let period = intervention.period(day);
+        report.max_workloads = self.state.workloads.check_adding(
+            period,
+            intervention.workloads(period.start()),
+            self.maintenance.resources(),
+        );
+        report.exclusions = self.check_exclusion(iid, period);
+        self.schedule(iid, day);
+        report.min_workloads = self.is_feasible();
+        let (new_obj1, new_obj2, new_cost) = self.objectives();
+        report.obj1 = new_obj1;
+        report.obj2 = new_obj2;
+        report.cost = new_cost;
+        report.delta_obj1 = new_obj1 - obj1;
+        report.delta_obj2 = new_obj2 - obj2;
+        report.delta_cost = new_cost - cost;
+        report
+    }
+
+    /// Report of `move_intervention` without keeping the move.
+    pub fn evaluate_move(&mut self, iid: IID, day: Day) -> MoveReport {
+        let old_day = self.start_day(iid);
+        let report = self.move_intervention(iid, day);
+        if report.latest_start {
+            self.unschedule(iid);
+            if let Some(old_day) = old_day {
+                self.schedule(iid, old_day);
+            }
+        }
+        report
+    }
+
+    /// Start day of an intervention, if planned
+    pub fn start_day(&self, iid: IID) -> Option<Day> {
+        self.state
+            .interventions
+            .get(iid.get())
+            .and_then(|period| period.map(|period| period.start()))
+    }
+
+    /// Risk of each scenario of a day, summed over the planned interventions
+    pub fn scenario_risks(&self, day: Day) -> &[f64] {
+        let nscenarios = self.state.cost.nscenarios;
+        let begin = day.get() * nscenarios;
+        &self.state.cost.risks[begin..begin + self.maintenance.scenarios_number()[day.get()]]
+    }
+
+    /// Workloads of each resource by day
+    pub fn workloads(&self) -> &[Box<[f64]>] {
+        &self.state.workloads.workloads
+    }
+
     pub fn current_planning(&self) -> Planning {
         let interventions = self
             .state
@@ -234,3 +628,132 @@ impl<'maintenance> Search<'maintenance> for LocalSearch<'maintenance> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixtures;
+    use crate::validation::validate;
+
+    fn planning(days: &[(usize, usize)]) -> Planning {
+        Planning {
+            interventions: days
+                .iter()
+                .map(|&(iid, day)| (IID::new(iid), Day::new(day)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn quantile_of_scenario_risks() {
+        let instance = fixtures::small();
+        let maintenance = &instance.maintenance;
+        let search = LocalSearch::from_planning(maintenance, &planning(&[(0, 0), (1, 1)]));
+        let report = search.cost_report();
+        // I1 alone on day 0, then I1 and I2 on day 1
+        assert_eq!(&report.quantile_risks[..2], &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn exclusion_of_overlapping_periods() {
+        let instance = fixtures::small();
+        let maintenance = &instance.maintenance;
+        let search = LocalSearch::from_planning(maintenance, &planning(&[(0, 1)]));
+        let check = |iid, day| {
+            let intervention = maintenance.intervention(IID::new(iid));
+            search.check_exclusion(IID::new(iid), intervention.period(Day::new(day)))
+        };
+        // I1 runs on days 1 and 2, day 2 is in the summer
+        assert!(check(1, 0));
+        assert!(check(1, 1));
+        assert!(!check(1, 2));
+        assert!(check(1, 3));
+        // I1 from day 1 overlaps I2 on day 1 only, in the winter
+        let period = maintenance.intervention(IID::new(0)).period(Day::new(1));
+        let search = LocalSearch::from_planning(maintenance, &planning(&[(1, 1)]));
+        assert!(search.check_exclusion(IID::new(0), period));
+        let search = LocalSearch::from_planning(maintenance, &planning(&[(1, 2)]));
+        assert!(!search.check_exclusion(IID::new(0), period));
+    }
+
+    #[test]
+    fn strictly_feasible_as_validated() {
+        let instance = fixtures::small();
+        let maintenance = &instance.maintenance;
+        let starts = || (0..=maintenance.ndays()).map(Some).chain(Some(None));
+        for first in starts() {
+            for second in starts() {
+                let days: Vec<_> = vec![first, second]
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(iid, day)| day.map(|day| (iid, day)))
+                    .collect();
+                let planning = planning(&days);
+                assert_eq!(
+                    LocalSearch::from_planning(maintenance, &planning).is_strictly_feasible(),
+                    validate(maintenance, &planning).is_feasible(),
+                    "{:?}",
+                    days
+                );
+            }
+        }
+    }
+
+    fn assert_same_state(lhs: &LocalSearch, rhs: &LocalSearch) {
+        let close = |lhs: &[f64], rhs: &[f64]| {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(x, y)| (x - y).abs() < 1e-9)
+        };
+        let (lhs_report, rhs_report) = (lhs.cost_report(), rhs.cost_report());
+        assert!(close(&[lhs_report.cost], &[rhs_report.cost]));
+        assert!(close(&lhs_report.mean_risks, &rhs_report.mean_risks));
+        assert!(close(
+            &lhs_report.quantile_risks,
+            &rhs_report.quantile_risks
+        ));
+        assert!(close(&lhs_report.excess_risks, &rhs_report.excess_risks));
+        assert!(close(&lhs.state.cost.risks, &rhs.state.cost.risks));
+        for (lhs, rhs) in lhs.workloads().iter().zip(rhs.workloads()) {
+            assert!(close(lhs, rhs));
+        }
+        assert_eq!(
+            lhs.current_planning().interventions,
+            rhs.current_planning().interventions
+        );
+    }
+
+    #[test]
+    fn unschedule() {
+        let instance = fixtures::small();
+        let maintenance = &instance.maintenance;
+        let mut search = LocalSearch::from_planning(maintenance, &planning(&[(0, 0), (1, 1)]));
+        search.unschedule(IID::new(1));
+        assert_same_state(
+            &search,
+            &LocalSearch::from_planning(maintenance, &planning(&[(0, 0)])),
+        );
+        assert_eq!(search.state.unplanned, vec![IID::new(1)]);
+        search.unschedule(IID::new(0));
+        assert_same_state(&search, &LocalSearch::new(maintenance));
+        assert_eq!(search.cost(), 0.0);
+    }
+
+    #[test]
+    fn move_intervention() {
+        let instance = fixtures::small();
+        let maintenance = &instance.maintenance;
+        let mut search = LocalSearch::from_planning(maintenance, &planning(&[(0, 0), (1, 1)]));
+        let report = search.move_intervention(IID::new(0), Day::new(1));
+        assert_same_state(
+            &search,
+            &LocalSearch::from_planning(maintenance, &planning(&[(0, 1), (1, 1)])),
+        );
+        assert!(!report.max_workloads);
+        let report = search.evaluate_move(IID::new(1), Day::new(2));
+        assert!(!report.max_workloads && !report.exclusions);
+        assert!(report.latest_start && report.delta_cost != 0.0);
+        assert_same_state(
+            &search,
+            &LocalSearch::from_planning(maintenance, &planning(&[(0, 1), (1, 1)])),
+        );
+    }
+}
@@ -38,6 +38,61 @@ impl WorkloadsState {
             )
         });
     }
+
+    pub fn decrease_workloads(&mut self, period: &Period, workloads: &[Workload]) {
+        let begin = period.start().get();
+        let end = period.end_exclusive().get();
+        workloads.iter().for_each(|wl| {
+            sub_vec_in_place(
+                &mut self.workloads[wl.rid().get()][begin..end],
+                wl.workloads(),
+            )
+        });
+    }
+
+    /// Sum of the missing workloads to reach the minimum of each resource
+    pub fn min_violation(&self, resource_bounds: &[Resource]) -> f64 {
+        self.workloads
+            .iter()
+            .zip(resource_bounds.iter())
+            .map(|(workloads, resource)| {
+                workloads
+                    .iter()
+                    .enumerate()
+                    .map(|(day, &wl)| {
+                        let min = resource.min[day];
+                        if wl < min {
+                            min - wl
+                        } else {
+                            0.0f64
+                        }
+                    })
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+
+    /// Sum of the workloads above the maximum of each resource
+    pub fn max_violation(&self, resource_bounds: &[Resource]) -> f64 {
+        self.workloads
+            .iter()
+            .zip(resource_bounds.iter())
+            .map(|(workloads, resource)| {
+                workloads
+                    .iter()
+                    .enumerate()
+                    .map(|(day, &wl)| {
+                        let max = resource.max[day];
+                        if wl > max {
+                            wl - max
+                        } else {
+                            0.0f64
+                        }
+                    })
+                    .sum::<f64>()
+            })
+            .sum()
+    }
 }
 
 #[allow(dead_code)]
@@ -183,6 +238,22 @@ impl<'state> RisksIncrementerAddRisks<'state> {
 
         RisksIncrementerUpdateMean { state: self.state }
     }
+
+    #[inline]
+    pub fn remove_risks(
+        self,
+        period: &Period,
+        risks: &[f64],
+    ) -> RisksIncrementerUpdateMean<'state> {
+        let begin = period.start().get() * self.state.nscenarios;
+        let mut st_risks = unsafe {
+            let ptr = self.state.risks.get_unchecked_mut(begin);
+            std::slice::from_raw_parts_mut(ptr, risks.len())
+        };
+        sub_vec_in_place(&mut st_risks, risks);
+
+        RisksIncrementerUpdateMean { state: self.state }
+    }
 }
 
 impl<'state> RisksIncrementerUpdateMean<'state> {
@@ -192,6 +263,27 @@ impl<'state> RisksIncrementerUpdateMean<'state> {
         period: &Period,
         summed_risks: &[f64],
         scenarios_number: &[usize],
+    ) -> RisksIncrementerUpdateQuantile<'state> {
+        self.apply_mean(period, summed_risks, scenarios_number, add_vec_in_place)
+    }
+
+    #[inline]
+    pub fn remove_mean(
+        self,
+        period: &Period,
+        summed_risks: &[f64],
+        scenarios_number: &[usize],
+    ) -> RisksIncrementerUpdateQuantile<'state> {
+        self.apply_mean(period, summed_risks, scenarios_number, sub_vec_in_place)
+    }
+
+    #[inline(always)]
+    fn apply_mean(
+        self,
+        period: &Period,
+        summed_risks: &[f64],
+        scenarios_number: &[usize],
+        apply: fn(&mut [f64], &[f64]),
     ) -> RisksIncrementerUpdateQuantile<'state> {
         let begin = period.start().get();
         //let end = period.end_exclusive().get();
@@ -199,7 +291,7 @@ impl<'state> RisksIncrementerUpdateMean<'state> {
             let ptr = self.state.summed_risks.get_unchecked_mut(begin);
             std::slice::from_raw_parts_mut(ptr, summed_risks.len())
         };
-        add_vec_in_place(&mut st_summed_risks, summed_risks);
+        apply(&mut st_summed_risks, summed_risks);
         let mut st_mean_risks = unsafe {
             let ptr = self.state.mean_risks.get_unchecked_mut(begin);
             std::slice::from_raw_parts_mut(ptr, summed_risks.len())
@@ -237,9 +329,9 @@ impl<'state> RisksIncrementerUpdateQuantile<'state> {
         let ri = unsafe {
             let ptr = self
                 .state
-                .quantile_risks
-                .get_unchecked_mut(begin * self.state.nscenarios);
-            std::slice::from_raw_parts_mut(ptr, len * self.state.nscenarios)
+                .risks
+                .get_unchecked(begin * self.state.nscenarios) as *const f64;
+            std::slice::from_raw_parts(ptr, len * self.state.nscenarios)
                 .chunks(self.state.nscenarios)
         };
         // (&self.state.risks[begin * self.state.nscenarios..end * self.state.nscenarios])
@@ -5,7 +5,6 @@ pub fn add_vec_in_place<T: Copy + Add<Output = T>>(x: &mut [T], y: &[T]) {
     x.iter_mut().zip(y.iter()).for_each(|(x, &y)| *x = *x + y);
 }
 
-#[allow(dead_code)]
 #[inline(always)]
 pub fn sub_vec_in_place<T: Copy + Sub<Output = T>>(x: &mut [T], y: &[T]) {
     x.iter_mut().zip(y.iter()).for_each(|(x, &y)| *x = *x - y);
@@ -0,0 +1,359 @@
+//! Check of a planning that does not come from the solver, e.g. edited by hand.
+use crate::common::types::*;
+use crate::common::{CostReport, Maintenance, Planning};
+use crate::search::LocalSearch;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// The intervention is planned more than once
+    Duplicated(IID),
+    Unplanned(IID),
+    /// Start after the latest start of the intervention, or too late for
+    /// the intervention to end in the horizon
+    LateStart {
+        iid: IID,
+        day: Day,
+    },
+    /// Workload of a resource out of its bounds
+    Workload {
+        rid: RID,
+        day: Day,
+        workload: f64,
+        min: f64,
+        max: f64,
+    },
+    /// Two exclusive interventions planned at the same time on a day of one
+    /// of their excluded seasons
+    Exclusion {
+        first: IID,
+        second: IID,
+        period: Period,
+    },
+}
+
+/// Workloads of a resource by day against its bounds
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceUsage {
+    pub rid: RID,
+    pub workloads: Box<[f64]>,
+    pub min: Box<[f64]>,
+    pub max: Box<[f64]>,
+    /// Days where the workload is out of the bounds
+    pub violations: Vec<Day>,
+}
+
+pub struct Validation {
+    pub violations: Vec<Violation>,
+    /// Objectives of the interventions that could be planned
+    pub report: CostReport,
+}
+
+impl Validation {
+    pub fn is_feasible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+fn planned_days(
+    maintenance: &Maintenance,
+    planning: &Planning,
+    violations: &mut Vec<Violation>,
+) -> Vec<Option<Day>> {
+    let mut days = vec![None; maintenance.ninterventions()];
+    for &(iid, day) in planning.interventions.iter() {
+        if iid.get() >= days.len() {
+            continue;
+        }
+        if !maintenance.intervention(iid).can_start_on(day) {
+            violations.push(Violation::LateStart { iid, day });
+        }
+        // NOTE(vincent): the first day of a duplicated intervention is kept.
+        if days[iid.get()].is_some() {
+            violations.push(Violation::Duplicated(iid));
+        } else {
+            days[iid.get()] = Some(day);
+        }
+    }
+    for (iid, day) in days.iter_mut().enumerate() {
+        match *day {
+            None => violations.push(Violation::Unplanned(IID::new(iid))),
+            Some(d) if !maintenance.intervention(IID::new(iid)).can_start_on(d) => *day = None,
+            _ => (),
+        }
+    }
+    days
+}
+
+fn workload_violations(
+    maintenance: &Maintenance,
+    workloads: &[Box<[f64]>],
+    violations: &mut Vec<Violation>,
+) {
+    for (rid, (workloads, resource)) in workloads
+        .iter()
+        .zip(maintenance.resources().iter())
+        .enumerate()
+    {
+        for (day, &workload) in workloads.iter().enumerate() {
+            let (min, max) = (resource.min[day], resource.max[day]);
+            if workload < min || workload > max {
+                violations.push(Violation::Workload {
+                    rid: RID::new(rid),
+                    day: Day::new(day),
+                    workload,
+                    min,
+                    max,
+                });
+            }
+        }
+    }
+}
+
+fn exclusion_violations(
+    maintenance: &Maintenance,
+    days: &[Option<Day>],
+    violations: &mut Vec<Violation>,
+) {
+    // same filter as `LocalSearch::from_planning`
+    let planned = |iid: IID| {
+        days[iid.get()].filter(|day| day.get() < maintenance.intervention(iid).nperiods())
+    };
+    for first in maintenance.interventions_ids() {
+        let day = match planned(first) {
+            Some(day) => day,
+            None => continue,
+        };
+        let intervention = maintenance.intervention(first);
+        for (second, excluded_seasons) in maintenance.exclusions(first).exclusions.iter() {
+            let other_day = match planned(*second) {
+                Some(other_day) if *second > first => other_day,
+                _ => continue,
+            };
+            let other = maintenance.intervention(*second);
+            if let Some(period) = maintenance.excluded_overlap(
+                intervention.period(day),
+                other.period(other_day),
+                excluded_seasons,
+            ) {
+                violations.push(Violation::Exclusion {
+                    first,
+                    second: *second,
+                    period,
+                });
+            }
+        }
+    }
+}
+
+/// Usage of each resource by a planning, indexed by resource identifier.
+///
+/// Interventions planned out of their horizon are ignored.
+pub fn resources_usage(maintenance: &Maintenance, planning: &Planning) -> Vec<ResourceUsage> {
+    let search = LocalSearch::from_planning(maintenance, planning);
+    let ndays = maintenance.ndays();
+    search
+        .workloads()
+        .iter()
+        .zip(maintenance.resources().iter())
+        .enumerate()
+        .map(|(rid, (workloads, resource))| {
+            let min: Box<[f64]> = (0..ndays).map(|day| resource.min[day]).collect();
+            let max: Box<[f64]> = (0..ndays).map(|day| resource.max[day]).collect();
+            let violations = workloads
+                .iter()
+                .enumerate()
+                .filter(|&(day, &workload)| workload < min[day] || workload > max[day])
+                .map(|(day, _)| Day::new(day))
+                .collect();
+            ResourceUsage {
+                rid: RID::new(rid),
+                workloads: workloads.clone(),
+                min,
+                max,
+                violations,
+            }
+        })
+        .collect()
+}
+
+/// Risks of a planning by day, with the risk of each scenario of one day
+#[derive(Clone, Debug, Default)]
+pub struct RiskProfile {
+    pub report: CostReport,
+    /// Day of the scenarios, if in the horizon
+    pub day: Option<Day>,
+    pub scenarios: Box<[f64]>,
+}
+
+/// Mean, quantile and excess risks of each day of a planning and, for the
+/// given day, the distribution of the risk over the scenarios.
+///
+/// Interventions planned out of their horizon are ignored.
+pub fn risk_profile(
+    maintenance: &Maintenance,
+    planning: &Planning,
+    day: Option<Day>,
+) -> RiskProfile {
+    let search = LocalSearch::from_planning(maintenance, planning);
+    let day = day.filter(|day| day.get() < maintenance.ndays());
+    RiskProfile {
+        report: search.cost_report(),
+        day,
+        scenarios: day
+            .map(|day| search.scenario_risks(day).into())
+            .unwrap_or_default(),
+    }
+}
+
+/// Violations of the constraints of the instance and objectives of a planning.
+pub fn validate(maintenance: &Maintenance, planning: &Planning) -> Validation {
+    let mut violations = Vec::new();
+    let days = planned_days(maintenance, planning, &mut violations);
+    let planning = Planning {
+        interventions: days
+            .iter()
+            .enumerate()
+            .filter_map(|(iid, day)| day.map(|day| (IID::new(iid), day)))
+            .collect(),
+    };
+    let search = LocalSearch::from_planning(maintenance, &planning);
+    workload_violations(maintenance, search.workloads(), &mut violations);
+    exclusion_violations(maintenance, &days, &mut violations);
+    Validation {
+        violations,
+        report: search.cost_report(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixtures;
+
+    fn violations(days: &[(usize, usize)]) -> Vec<Violation> {
+        let instance = fixtures::small();
+        let planning = Planning {
+            interventions: days
+                .iter()
+                .map(|&(iid, day)| (IID::new(iid), Day::new(day)))
+                .collect(),
+        };
+        validate(&instance.maintenance, &planning).violations
+    }
+
+    fn period(start: usize, duration: usize) -> Period {
+        Period::new(Day::new(start), Day::new(duration)).unwrap()
+    }
+
+    #[test]
+    fn feasible() {
+        assert_eq!(violations(&[(0, 0), (1, 3)]), vec![]);
+    }
+
+    #[test]
+    fn duplicated_keeps_first_day() {
+        assert_eq!(
+            violations(&[(0, 0), (1, 3), (0, 2)]),
+            vec![Violation::Duplicated(IID::new(0))]
+        );
+        assert_eq!(
+            violations(&[(0, 0), (1, 3), (0, 4)]),
+            vec![
+                Violation::LateStart {
+                    iid: IID::new(0),
+                    day: Day::new(4),
+                },
+                Violation::Duplicated(IID::new(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unplanned() {
+        assert_eq!(
+            violations(&[(0, 0)]),
+            vec![Violation::Unplanned(IID::new(1))]
+        );
+    }
+
+    #[test]
+    fn late_start() {
+        assert_eq!(
+            violations(&[(0, 0), (1, 4)]),
+            vec![Violation::LateStart {
+                iid: IID::new(1),
+                day: Day::new(4),
+            }]
+        );
+        // out of the periods of the intervention
+        assert_eq!(
+            violations(&[(0, 0), (1, 7)]),
+            vec![Violation::LateStart {
+                iid: IID::new(1),
+                day: Day::new(7),
+            }]
+        );
+    }
+
+    #[test]
+    fn workload() {
+        assert_eq!(
+            violations(&[(0, 1), (1, 1)]),
+            vec![
+                Violation::Workload {
+                    rid: RID::new(0),
+                    day: Day::new(0),
+                    workload: 0.0,
+                    min: 1.0,
+                    max: 4.0,
+                },
+                Violation::Workload {
+                    rid: RID::new(0),
+                    day: Day::new(1),
+                    workload: 5.0,
+                    min: 0.0,
+                    max: 4.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn exclusion() {
+        // I2 overlaps I1 on the first day of summer
+        assert_eq!(
+            violations(&[(0, 1), (1, 2)]),
+            vec![
+                Violation::Workload {
+                    rid: RID::new(0),
+                    day: Day::new(0),
+                    workload: 0.0,
+                    min: 1.0,
+                    max: 4.0,
+                },
+                Violation::Workload {
+                    rid: RID::new(0),
+                    day: Day::new(2),
+                    workload: 5.0,
+                    min: 0.0,
+                    max: 4.0,
+                },
+                Violation::Exclusion {
+                    first: IID::new(0),
+                    second: IID::new(1),
+                    period: period(2, 1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_season_overlap() {
+        // I1 spans the last day of winter and the first day of summer, I2
+        // only overlaps it during the winter.
+        let violations = violations(&[(0, 1), (1, 1)]);
+        assert!(!violations
+            .iter()
+            .any(|violation| matches!(violation, Violation::Exclusion { .. })));
+    }
+}
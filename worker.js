@@ -0,0 +1,245 @@
+// Loads and solves the instances out of the main thread, the page only
+// draws what is posted back.
+//
+// page -> worker:
+//   {type: "load", bytes}                  instance file
+//   {type: "solve", solver, seed}          starts from the greedy planning
+//   {type: "stop"}                         stops and edits the best planning
+//   {type: "risks", day}                   scenarios of another day
+//   {type: "evaluate", intervention, day}  move without keeping it
+//   {type: "apply", intervention, day}     move kept in the edited planning
+// worker -> page:
+//   {type: "loaded", summary} or {type: "error", message}
+//   {type: "progress", improvement, charts} on each improvement
+//   {type: "result", planning, export, charts} once stopped
+//   {type: "move", evaluation}             and, once applied, planning,
+//                                          export and charts
+//   {type: "risks", risks}
+
+// time given to the solver between two reads of the messages, in ms
+const SLICE = 50;
+
+let optim;
+let editor;
+let risk_day;
+let started;
+
+const ganttData = (gantt) => {
+    const names = [];
+    for(var row = 0; row < gantt.nrows(); ++row) {
+        names.push(gantt.name(row));
+    }
+    const data = {
+        ndays: gantt.ndays(),
+        nrows: gantt.nrows(),
+        interventions: gantt.interventions(),
+        starts: gantt.starts(),
+        ends: gantt.ends(),
+        names: names,
+        vertices: gantt.vertices(),
+    };
+    gantt.free();
+    return data;
+};
+
+// with the fields of `maintenance::MaintenanceSummary`
+const summaryData = (summary, hash) => {
+    const data = {
+        ndays: summary.ndays(),
+        ninterventions: summary.ninterventions(),
+        nresources: summary.nresources(),
+        nscenarios: summary.nscenarios(),
+        min_scenarios: summary.min_scenarios(),
+        nseasons: summary.nseasons(),
+        nexclusions: summary.nexclusions(),
+        alpha: summary.alpha(),
+        quantile: summary.quantile(),
+        computation_time: summary.computation_time(),
+        timings: {
+            read: summary.read_time(),
+            parse: summary.parse_time(),
+            convert: summary.convert_time(),
+        },
+        hash: hash,
+    };
+    summary.free();
+    return data;
+};
+
+// SHA-256 of the file in hexadecimal, as computed by the server
+const fileHash = (bytes) => crypto.subtle.digest('SHA-256', bytes)
+    .then(digest => Array.from(new Uint8Array(digest))
+        .map(byte => byte.toString(16).padStart(2, '0'))
+        .join(''));
+
+const resourcesData = (charts) => {
+    const resources = [];
+    for(var rid = 0; rid < charts.nresources(); ++rid) {
+        resources.push({
+            name: charts.name(rid),
+            workloads: charts.workloads(rid),
+            min: charts.min(rid),
+            max: charts.max(rid),
+            violations: charts.violations(rid),
+        });
+    }
+    charts.free();
+    return resources;
+};
+
+const risksData = (chart) => {
+    if(!chart) {
+        return undefined;
+    }
+    const risks = {
+        cost: chart.cost(),
+        mean_risks: chart.mean_risks(),
+        quantile_risks: chart.quantile_risks(),
+        excess_risks: chart.excess_risks(),
+        day: chart.day(),
+        scenarios: chart.scenarios(),
+    };
+    chart.free();
+    return risks;
+};
+
+// the planning edited by hand, otherwise the best one
+const risks = () => risksData(editor
+    ? optim.risk_chart_of(editor.planning(), risk_day)
+    : optim.risk_chart(risk_day));
+
+const charts = () => ({
+    gantt: ganttData(editor ? editor.gantt() : optim.gantt()),
+    resources: resourcesData(editor
+        ? optim.resource_charts_of(editor.planning())
+        : optim.resource_charts()),
+    risks: risks(),
+});
+
+const improvement = () => ({
+    elapsed: (performance.now() - started) / 1000,
+    cost: optim.best_cost(),
+    feasible: optim.best_feasible(),
+    iteration: optim.iterations(),
+});
+
+const evaluationData = (evaluation) => {
+    if(!evaluation) {
+        return undefined;
+    }
+    const data = {
+        intervention: evaluation.intervention,
+        day: evaluation.day,
+        latest_start: evaluation.latest_start,
+        max_workloads: evaluation.max_workloads,
+        exclusions: evaluation.exclusions,
+        min_workloads: evaluation.min_workloads,
+        feasible: evaluation.feasible,
+        obj1: evaluation.obj1,
+        obj2: evaluation.obj2,
+        cost: evaluation.cost,
+        delta_obj1: evaluation.delta_obj1,
+        delta_obj2: evaluation.delta_obj2,
+        delta_cost: evaluation.delta_cost,
+    };
+    evaluation.free();
+    return data;
+};
+
+const closeEditor = () => {
+    if(editor) {
+        editor.free();
+        editor = undefined;
+    }
+};
+
+// the messages are read between two slices, e.g. to stop the search
+const solveSlice = () => {
+    if(!optim.is_running()) {
+        return;
+    }
+    if(optim.run_for(SLICE)) {
+        postMessage({type: "progress", improvement: improvement(), charts: charts()});
+    }
+    setTimeout(solveSlice, 0);
+};
+
+const handlers = {
+    load: (message) => {
+        closeEditor();
+        if(!optim.load_from_bytes(message.bytes)) {
+            postMessage({type: "error", message: "cannot load the instance"});
+            return;
+        }
+        const summary = optim.summary();
+        fileHash(message.bytes).then(hash => {
+            postMessage({type: "loaded", summary: summaryData(summary, hash)});
+        });
+    },
+    solve: (message) => {
+        closeEditor();
+        if(!optim.start(message.solver, message.seed || 0)) {
+            postMessage({type: "error", message: "cannot solve the instance"});
+            return;
+        }
+        started = performance.now();
+        postMessage({type: "progress", improvement: improvement(), charts: charts()});
+        solveSlice();
+    },
+    stop: (message) => {
+        optim.stop();
+        closeEditor();
+        editor = optim.edit();
+        if(!editor) {
+            return;
+        }
+        postMessage({
+            type: "result",
+            planning: editor.planning(),
+            export: optim.export_planning(),
+            charts: charts(),
+        });
+    },
+    risks: (message) => {
+        risk_day = message.day;
+        const data = risks();
+        if(data) {
+            postMessage({type: "risks", risks: data});
+        }
+    },
+    evaluate: (message) => {
+        if(editor) {
+            postMessage({type: "move", evaluation: evaluationData(editor.evaluate(message.intervention, message.day))});
+        }
+    },
+    apply: (message) => {
+        if(editor) {
+            const evaluation = evaluationData(editor.apply(message.intervention, message.day));
+            postMessage({
+                type: "move",
+                evaluation: evaluation,
+                planning: editor.planning(),
+                export: editor.export_planning(),
+                charts: charts(),
+            });
+        }
+    },
+};
+
+const handle = (message) => {
+    const handler = handlers[message.type];
+    if(handler) {
+        handler(message);
+    } else {
+        postMessage({type: "error", message: "unknown message: " + message.type});
+    }
+};
+
+// the messages received while the module loads are handled once it is ready
+const pending = [];
+self.onmessage = (event) => pending.push(event.data);
+import("maintenance_site").then(({Optim}) => {
+    optim = Optim.new();
+    self.onmessage = (event) => handle(event.data);
+    pending.forEach(handle);
+});